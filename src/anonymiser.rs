@@ -4,6 +4,28 @@ use crate::parsers::custom_classifications::ClassificationConfig;
 use crate::parsers::strategies::Strategies;
 use crate::parsers::strategy_file;
 use crate::parsers::strategy_structs::TransformerOverrides;
+use crate::parsers::unique;
+use crate::progress::ProgressMode;
+use crate::report::RunReport;
+
+/// What's shown and written about a run and the memory it can use, as opposed to what it does to
+/// the data
+pub struct RunOptions {
+    pub report_file: Option<String>,
+    pub progress_mode: Option<ProgressMode>,
+    /// The most fingerprints of unique values kept in memory at once
+    pub max_unique_values: usize,
+}
+
+impl Default for RunOptions {
+    fn default() -> Self {
+        RunOptions {
+            report_file: None,
+            progress_mode: None,
+            max_unique_values: unique::DEFAULT_MAX_VALUES,
+        }
+    }
+}
 
 pub fn anonymise(
     input_file: String,
@@ -12,6 +34,7 @@ pub fn anonymise(
     compress_output: Option<Option<CompressionType>>,
     transformer_overrides: TransformerOverrides,
     custom_classifications: ClassificationConfig,
+    run_options: RunOptions,
 ) -> Result<(), std::io::Error> {
     match strategy_file::read(&strategy_file) {
         Ok(strategies) => {
@@ -21,12 +44,22 @@ pub fn anonymise(
                 &custom_classifications,
             ) {
                 Ok(parsed_strategies) => {
+                    let mut report = run_options
+                        .report_file
+                        .as_ref()
+                        .map(|_| RunReport::new(&input_file, &output_file, &strategy_file));
                     file_reader::read(
                         input_file,
                         output_file,
                         &parsed_strategies,
                         compress_output,
+                        report.as_mut(),
+                        run_options.progress_mode,
+                        run_options.max_unique_values,
                     )?;
+                    if let (Some(report), Some(report_file)) = (report, run_options.report_file) {
+                        report.write(&report_file)?;
+                    }
                     Ok(())
                 }
                 Err(duplicate_columns) => {
@@ -58,6 +91,7 @@ mod tests {
             None,
             TransformerOverrides::none(),
             ClassificationConfig::default(),
+            RunOptions::default(),
         )
         .is_ok());
     }
@@ -72,6 +106,7 @@ mod tests {
             None,
             TransformerOverrides::none(),
             ClassificationConfig::default(),
+            RunOptions::default(),
         )
         .is_ok());
     }
@@ -86,6 +121,7 @@ mod tests {
             None,
             TransformerOverrides::none(),
             ClassificationConfig::default(),
+            RunOptions::default(),
         )
         .is_ok());
 
@@ -123,6 +159,7 @@ mod tests {
             None,
             TransformerOverrides::none(),
             ClassificationConfig::default(),
+            RunOptions::default(),
         )
         .is_ok());
 
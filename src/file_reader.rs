@@ -1,22 +1,34 @@
 use crate::compression_type::CompressionType;
 use crate::parsers::rng;
 use crate::parsers::row_parser;
-use crate::parsers::state::State;
+use crate::parsers::state::{Position, State};
 use crate::parsers::strategies::Strategies;
+use crate::parsers::unique::UniqueValues;
+use crate::progress::{Progress, ProgressMode};
+use crate::report::RunReport;
+use flate2::read::MultiGzDecoder;
 use flate2::write::GzEncoder;
 use flate2::Compression;
+use std::cell::Cell;
 use std::fs::File;
 use std::io::prelude::*;
 use std::io::BufReader;
 use std::io::BufWriter;
+use std::rc::Rc;
+
+const ZSTD_MAGIC: [u8; 4] = [0x28, 0xb5, 0x2f, 0xfd];
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
 
 pub fn read(
     input_file_path: String,
     output_file_path: String,
     strategies: &Strategies,
     compress_output: Option<Option<CompressionType>>,
+    mut report: Option<&mut RunReport>,
+    progress_mode: Option<ProgressMode>,
+    max_unique_values: usize,
 ) -> Result<(), std::io::Error> {
-    let output_file = File::create(output_file_path)?;
+    let output_file = File::create(&output_file_path)?;
     let mut file_writer: Box<dyn Write> = match compress_output {
         Some(Some(CompressionType::Zstd)) => {
             Box::new(zstd::Encoder::new(output_file, 1)?.auto_finish())
@@ -29,33 +41,120 @@ pub fn read(
         None => Box::new(BufWriter::new(output_file)),
     };
 
-    let file_reader = File::open(&input_file_path)
+    let bytes_read = Rc::new(Cell::new(0));
+    let mut reader = open_counting(&input_file_path, bytes_read.clone())
         .unwrap_or_else(|_| panic!("Input file '{}' does not exist", input_file_path));
-
-    let mut reader = BufReader::new(file_reader);
+    let mut progress = progress_mode.map(|mode| {
+        let input_size = std::fs::metadata(&input_file_path).map_or(0, |m| m.len());
+        Progress::new(mode, input_size)
+    });
     let mut line = String::new();
 
     let mut row_parser_state = State::new();
+    row_parser_state.unique_values =
+        UniqueValues::new(strategies.unique_groups(), max_unique_values);
 
     let mut rng = rng::get();
 
     loop {
-        let bytes_read = reader.read_line(&mut line)?;
-        if bytes_read == 0 {
+        if reader.read_line(&mut line)? == 0 {
             break;
         }
 
+        let was_in_copy = matches!(row_parser_state.position, Position::InCopy { .. });
         let transformed_row = row_parser::parse(&mut rng, &line, &mut row_parser_state, strategies);
         file_writer.write_all(transformed_row.as_bytes())?;
+
+        match (was_in_copy, &row_parser_state.position) {
+            (false, Position::InCopy { current_table }) => {
+                if let Some(report) = report.as_deref_mut() {
+                    report.start_table(current_table);
+                }
+                if let Some(progress) = progress.as_mut() {
+                    progress.start_table(&current_table.table_name);
+                }
+            }
+            (true, Position::InCopy { current_table }) => {
+                if let Some(report) = report.as_deref_mut() {
+                    report.add_row(current_table, &line, &transformed_row);
+                }
+                if let Some(progress) = progress.as_mut() {
+                    progress.add_row();
+                }
+            }
+            (true, _) => {
+                if let Some(report) = report.as_deref_mut() {
+                    report.finish_table();
+                }
+                if let Some(progress) = progress.as_mut() {
+                    progress.finish_table();
+                }
+            }
+            (false, _) => (),
+        }
+        if let Some(progress) = progress.as_mut() {
+            progress.update(bytes_read.get());
+        }
         line.clear();
     }
+
+    // The writer has to be finished before the size of the output is known
+    drop(file_writer);
+    if let Some(report) = report {
+        report.finish(
+            bytes_read.get(),
+            std::fs::metadata(&output_file_path)?.len(),
+        );
+    }
+    if let Some(progress) = progress.as_mut() {
+        progress.finish(bytes_read.get());
+    }
     Ok(())
 }
 
+/// Opens a dump, uncompressing it first if it was written with `--compress-output`
+pub fn open(file_name: &str) -> std::io::Result<Box<dyn BufRead>> {
+    open_counting(file_name, Rc::default())
+}
+
+/// Like `open`, also counting the bytes read from the file itself (before it's uncompressed) so
+/// that progress can be measured against the size of the file
+fn open_counting(file_name: &str, bytes_read: Rc<Cell<u64>>) -> std::io::Result<Box<dyn BufRead>> {
+    let file = CountingReader {
+        inner: File::open(file_name)?,
+        bytes_read,
+    };
+    let mut reader = BufReader::new(file);
+    let start = reader.fill_buf()?;
+    if start.starts_with(&ZSTD_MAGIC) {
+        Ok(Box::new(BufReader::new(zstd::Decoder::with_buffer(
+            reader,
+        )?)))
+    } else if start.starts_with(&GZIP_MAGIC) {
+        Ok(Box::new(BufReader::new(MultiGzDecoder::new(reader))))
+    } else {
+        Ok(Box::new(reader))
+    }
+}
+
+struct CountingReader<R> {
+    inner: R,
+    bytes_read: Rc<Cell<u64>>,
+}
+
+impl<R: Read> Read for CountingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let read = self.inner.read(buf)?;
+        self.bytes_read.set(self.bytes_read.get() + read as u64);
+        Ok(read)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::parsers::strategy_structs::*;
+    use crate::parsers::unique;
     use crate::uncompress::uncompress;
     use pretty_assertions::assert_eq;
     use std::collections::HashMap;
@@ -114,7 +213,16 @@ mod tests {
         let _ = fs::remove_file(&output_file).ok();
         let strategies = default_strategies();
 
-        assert!(read(input_file.clone(), output_file.clone(), &strategies, None).is_ok());
+        assert!(read(
+            input_file.clone(),
+            output_file.clone(),
+            &strategies,
+            None,
+            None,
+            None,
+            unique::DEFAULT_MAX_VALUES
+        )
+        .is_ok());
 
         let original =
             fs::read_to_string(&input_file).expect("Something went wrong reading the file");
@@ -125,6 +233,44 @@ mod tests {
         assert_eq!(original, processed);
     }
 
+    #[test]
+    fn can_report_on_the_run() {
+        let input_file = "test_files/dump_file.sql".to_string();
+        let output_file = "test_files/file_reader_report_test_results.sql".to_string();
+        let strategies = default_strategies();
+        let mut report = RunReport::new(&input_file, &output_file, "test_files/strategy.json");
+
+        read(
+            input_file.clone(),
+            output_file.clone(),
+            &strategies,
+            None,
+            Some(&mut report),
+            None,
+            unique::DEFAULT_MAX_VALUES,
+        )
+        .unwrap();
+        let _ = fs::remove_file(&output_file);
+
+        let rows: Vec<(&str, u64)> = report
+            .tables
+            .iter()
+            .map(|(table_name, table)| (table_name.as_str(), table.rows))
+            .collect();
+        assert_eq!(
+            rows,
+            vec![
+                ("public.extra_data", 5),
+                ("public.orders", 8),
+                ("public.products", 4),
+                ("public.users", 7),
+            ]
+        );
+        assert_eq!(report.tables["public.users"].columns.len(), 10);
+        assert_eq!(report.input_bytes, 7158);
+        assert_eq!(report.output_bytes, 7158);
+    }
+
     #[test]
     fn can_read_and_output_compressed_with_default() {
         let input_file = "test_files/dump_file.sql".to_string();
@@ -141,7 +287,10 @@ mod tests {
             input_file.clone(),
             compressed_file.clone(),
             &strategies,
-            Some(None)
+            Some(None),
+            None,
+            None,
+            unique::DEFAULT_MAX_VALUES
         )
         .is_ok());
 
@@ -174,7 +323,10 @@ mod tests {
             input_file.clone(),
             compressed_file.clone(),
             &strategies,
-            Some(Some(CompressionType::Zstd))
+            Some(Some(CompressionType::Zstd)),
+            None,
+            None,
+            unique::DEFAULT_MAX_VALUES
         )
         .is_ok());
 
@@ -193,6 +345,46 @@ mod tests {
         assert_eq!(original, processed);
     }
 
+    #[test]
+    fn can_read_compressed_input() {
+        let input_file = "test_files/dump_file.sql".to_string();
+        let compressed_input = "test_files/compressed_input_file_reader_test.sql.gz".to_string();
+        let output_file = "test_files/compressed_input_file_reader_test_results.sql".to_string();
+        let original =
+            fs::read_to_string(&input_file).expect("Something went wrong reading the file");
+
+        let mut encoder = GzEncoder::new(
+            File::create(&compressed_input).unwrap(),
+            Compression::best(),
+        );
+        encoder.write_all(original.as_bytes()).unwrap();
+        encoder.finish().unwrap();
+
+        let strategies = default_strategies();
+        let mut report = RunReport::new(&input_file, &output_file, "test_files/strategy.json");
+        read(
+            compressed_input.clone(),
+            output_file.clone(),
+            &strategies,
+            None,
+            Some(&mut report),
+            None,
+            unique::DEFAULT_MAX_VALUES,
+        )
+        .unwrap();
+
+        let processed =
+            fs::read_to_string(&output_file).expect("Something went wrong reading the file");
+        assert_eq!(original, processed);
+        assert_eq!(
+            report.input_bytes,
+            fs::metadata(&compressed_input).unwrap().len()
+        );
+
+        fs::remove_file(&compressed_input).unwrap();
+        fs::remove_file(&output_file).unwrap();
+    }
+
     fn strategy_tuple(column_name: &str) -> (String, ColumnInfo) {
         (
             column_name.to_string(),
@@ -1,3 +1,4 @@
+use crate::parsers::column_rules::ColumnRules;
 use crate::parsers::strategy_errors::DbErrors;
 use crate::parsers::strategy_structs::{ColumnInFile, SimpleColumn, StrategyInFile};
 use std::collections::HashMap;
@@ -5,20 +6,30 @@ use std::collections::HashMap;
 pub fn fix(
     current_file_contents: Vec<StrategyInFile>,
     db_mismatch_error: DbErrors,
+    rules: &ColumnRules,
+    column_types: &HashMap<SimpleColumn, String>,
 ) -> Vec<StrategyInFile> {
     let missing_columns = db_mismatch_error.missing_from_strategy_file;
     let redundant_columns = db_mismatch_error.missing_from_db;
-    let file_contents_with_missing = add_missing(current_file_contents, &missing_columns);
+    let file_contents_with_missing =
+        add_missing(current_file_contents, &missing_columns, rules, column_types);
     remove_redundant(file_contents_with_missing, &redundant_columns)
 }
 
-fn add_missing(current: Vec<StrategyInFile>, missing: &[SimpleColumn]) -> Vec<StrategyInFile> {
+/// Adds the columns missing from the strategy file, classified by the first rule that matches
+/// them (or left as `Unknown` for someone to classify)
+fn add_missing(
+    current: Vec<StrategyInFile>,
+    missing: &[SimpleColumn],
+    rules: &ColumnRules,
+    column_types: &HashMap<SimpleColumn, String>,
+) -> Vec<StrategyInFile> {
     let missing_columns_by_table = missing.iter().fold(
         HashMap::new(),
-        |mut acc: HashMap<std::string::String, Vec<String>>, column| {
+        |mut acc: HashMap<std::string::String, Vec<ColumnInFile>>, column| {
             acc.entry(column.table_name.clone())
                 .or_default()
-                .push(column.column_name.clone());
+                .push(rules.classify(column, column_types));
             acc
         },
     );
@@ -29,22 +40,17 @@ fn add_missing(current: Vec<StrategyInFile>, missing: &[SimpleColumn]) -> Vec<St
         match new_strategies.iter().position(|c| c.table_name == table) {
             Some(position) => {
                 let existing_table = new_strategies.get_mut(position).unwrap();
-                for column in missing_columns {
-                    existing_table.columns.push(ColumnInFile::new(&column));
-                }
+                existing_table.columns.extend(missing_columns);
             }
             None => {
-                let mut new_table = StrategyInFile {
+                new_strategies.push(StrategyInFile {
                     truncate: false,
                     table_name: table.clone(),
                     description: "".to_string(),
-                    columns: vec![],
+                    columns: missing_columns,
                     salt: None,
-                };
-                for column in missing_columns {
-                    new_table.columns.push(ColumnInFile::new(&column));
-                }
-                new_strategies.push(new_table);
+                    locale: None,
+                });
             }
         }
     }
@@ -95,6 +101,7 @@ fn remove_redundant(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::parsers::strategy_structs::{DataCategory, TransformerType};
 
     #[test]
     fn adds_missing_columns() {
@@ -104,6 +111,7 @@ mod tests {
             truncate: false,
             columns: vec![ColumnInFile::new("id"), ColumnInFile::new("first_name")],
             salt: None,
+            locale: None,
         }];
 
         let missing = vec![
@@ -121,7 +129,7 @@ mod tests {
             },
         ];
 
-        let result = add_missing(current, &missing);
+        let result = add_missing(current, &missing, &ColumnRules::default(), &HashMap::new());
 
         let expected = vec![
             StrategyInFile {
@@ -134,6 +142,7 @@ mod tests {
                     ColumnInFile::new("last_name"),
                 ],
                 salt: None,
+                locale: None,
             },
             StrategyInFile {
                 table_name: "public.location".to_string(),
@@ -141,12 +150,46 @@ mod tests {
                 truncate: false,
                 columns: vec![ColumnInFile::new("id"), ColumnInFile::new("post_code")],
                 salt: None,
+                locale: None,
             },
         ];
 
         assert_eq!(result, expected);
     }
 
+    #[test]
+    fn classifies_missing_columns_with_the_rules() {
+        let email = SimpleColumn {
+            table_name: "public.person".to_string(),
+            column_name: "email".to_string(),
+        };
+        let notes = SimpleColumn {
+            table_name: "public.person".to_string(),
+            column_name: "notes".to_string(),
+        };
+        let column_types = HashMap::from([
+            (email.clone(), "character varying".to_string()),
+            (notes.clone(), "text".to_string()),
+        ]);
+
+        let result = add_missing(
+            Vec::new(),
+            &[email, notes],
+            &ColumnRules::default(),
+            &column_types,
+        );
+
+        let columns = &result[0].columns;
+        assert_eq!(columns[0].data_category, DataCategory::Pii);
+        assert_eq!(columns[0].transformer.name, TransformerType::FakeEmail);
+        assert_eq!(
+            columns[0].description,
+            "Auto-classified by rule 'email', please review"
+        );
+        assert_eq!(columns[1].data_category, DataCategory::Unknown);
+        assert_eq!(columns[1].transformer.name, TransformerType::Error);
+    }
+
     #[test]
     fn removes_redundant_columns() {
         let existing_columns = vec![
@@ -156,6 +199,7 @@ mod tests {
                 truncate: false,
                 columns: vec![ColumnInFile::new("id"), ColumnInFile::new("post_code")],
                 salt: None,
+                locale: None,
             },
             StrategyInFile {
                 table_name: "public.person".to_string(),
@@ -167,6 +211,7 @@ mod tests {
                     ColumnInFile::new("last_name"),
                 ],
                 salt: None,
+                locale: None,
             },
         ];
 
@@ -193,6 +238,7 @@ mod tests {
             truncate: false,
             columns: vec![ColumnInFile::new("id"), ColumnInFile::new("first_name")],
             salt: None,
+            locale: None,
         }];
 
         assert_eq!(result, expected);
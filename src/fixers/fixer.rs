@@ -1,7 +1,11 @@
 use crate::fixers::{db_mismatch, validation};
+use crate::parsers::column_rules::ColumnRules;
 use crate::parsers::custom_classifications::ClassificationConfig;
 use crate::parsers::strategy_errors::StrategyFileError;
 use crate::parsers::strategy_file;
+use crate::parsers::strategy_structs::SimpleColumn;
+use log::info;
+use std::collections::HashMap;
 
 pub fn can_fix(error: &StrategyFileError) -> bool {
     match *error {
@@ -36,6 +40,8 @@ pub fn fix(
     strategy_file: &str,
     error: StrategyFileError,
     custom_classifications: ClassificationConfig,
+    rules: &ColumnRules,
+    column_types: &HashMap<SimpleColumn, String>,
 ) {
     let current_file_contents = strategy_file::read(strategy_file).unwrap_or_else(|_| Vec::new());
     match error {
@@ -43,9 +49,9 @@ pub fn fix(
             let new_file_contents = validation::fix(current_file_contents, *validation_error);
 
             if !custom_classifications.classifications.is_empty() {
-                println!(
-                    "Using {} custom classifications for validation",
-                    custom_classifications.classifications.len()
+                info!(
+                    count = custom_classifications.classifications.len();
+                    "Using custom classifications for validation"
                 );
             }
 
@@ -53,7 +59,12 @@ pub fn fix(
                 .expect("Unable to write to file :(");
         }
         StrategyFileError::DbMismatchError(db_mismatch_error) => {
-            let new_file_contents = db_mismatch::fix(current_file_contents, *db_mismatch_error);
+            let new_file_contents = db_mismatch::fix(
+                current_file_contents,
+                *db_mismatch_error,
+                rules,
+                column_types,
+            );
 
             strategy_file::write(strategy_file, new_file_contents)
                 .expect("Unable to write to file :(");
@@ -61,7 +72,10 @@ pub fn fix(
     }
 }
 fn sha256_digest(strategy_file: &str) -> String {
-    let bytes = std::fs::read(strategy_file).unwrap();
+    let bytes: Vec<u8> = strategy_file::files(strategy_file)
+        .iter()
+        .flat_map(|file| std::fs::read(file).unwrap())
+        .collect();
     sha256::digest(bytes.as_slice())
 }
 
@@ -69,7 +83,6 @@ fn sha256_digest(strategy_file: &str) -> String {
 mod tests {
     use super::*;
     use crate::parsers::strategy_errors::{DbErrors, ValidationErrors};
-    use crate::parsers::strategy_structs::SimpleColumn;
 
     #[test]
     fn cannot_fix_db_mismatch_error_if_no_missing_columns() {
@@ -77,6 +90,7 @@ mod tests {
             DbErrors {
                 missing_from_db: Vec::new(),
                 missing_from_strategy_file: Vec::new(),
+                nulled_non_nullable_columns: Vec::new(),
             }
         ))));
     }
@@ -92,6 +106,7 @@ mod tests {
                     column_name: "column".to_string(),
                     table_name: "table".to_string()
                 }],
+                nulled_non_nullable_columns: Vec::new(),
             }
         ))));
     }
@@ -105,6 +120,7 @@ mod tests {
                     table_name: "table".to_string()
                 }],
                 missing_from_strategy_file: Vec::new(),
+                nulled_non_nullable_columns: Vec::new(),
             }
         ))));
     }
@@ -118,6 +134,7 @@ mod tests {
                     column_name: "column".to_string(),
                     table_name: "table".to_string()
                 }],
+                nulled_non_nullable_columns: Vec::new(),
             }
         ))));
     }
@@ -132,6 +149,8 @@ mod tests {
                 duplicate_tables: Vec::new(),
                 deterministic_without_id: Vec::new(),
                 invalid_custom_classifications: Vec::new(),
+                invalid_transformer_args: Vec::new(),
+                incompatible_column_types: Vec::new(),
             }
         ))));
     }
@@ -151,6 +170,8 @@ mod tests {
                 duplicate_tables: Vec::new(),
                 deterministic_without_id: Vec::new(),
                 invalid_custom_classifications: Vec::new(),
+                invalid_transformer_args: Vec::new(),
+                incompatible_column_types: Vec::new(),
             }
         ))));
     }
@@ -170,6 +191,8 @@ mod tests {
                 duplicate_tables: Vec::new(),
                 deterministic_without_id: Vec::new(),
                 invalid_custom_classifications: Vec::new(),
+                invalid_transformer_args: Vec::new(),
+                incompatible_column_types: Vec::new(),
             }
         ))));
     }
@@ -186,6 +209,8 @@ mod tests {
                 duplicate_tables: error,
                 deterministic_without_id: Vec::new(),
                 invalid_custom_classifications: Vec::new(),
+                invalid_transformer_args: Vec::new(),
+                incompatible_column_types: Vec::new(),
             }
         ))));
     }
@@ -1,19 +1,24 @@
-use crate::parsers::strategy_structs::{Transformer, TransformerType};
+use crate::parsers::strategy_structs::{Transformer, TransformerArgs, TransformerType};
 use crate::parsers::transformer;
+use crate::parsers::transformer_args::ParsedTransformer;
 use crate::parsers::types::Type;
 use rand::rngs::SmallRng;
 use rand::SeedableRng;
-use std::collections::HashMap;
 
 const HELPER_TABLE_NAME: &str = "anonymiser_helper";
 
-/// Anonymise an email address using the FakeEmail transformer
-pub fn anonymise_email(email: &str, global_salt: Option<&str>) -> Result<String, String> {
-    // Create a transformer struct
-    let transformer = Transformer {
+/// Anonymise an email address using the FakeEmail transformer, with the same args as in a
+/// strategy file so that the result matches the anonymised database
+pub fn anonymise_email(
+    email: &str,
+    args: Option<TransformerArgs>,
+    global_salt: Option<&str>,
+) -> Result<String, String> {
+    // Parse the args here so that mistakes are an error rather than a panic
+    let transformer = ParsedTransformer::parse(&Transformer {
         name: TransformerType::FakeEmail,
-        args: None,
-    };
+        args,
+    })?;
 
     // Create a dummy RNG (will be replaced by transformer's internal RNG for deterministic operations)
     let mut rng = SmallRng::seed_from_u64(0);
@@ -41,14 +46,13 @@ pub fn anonymise_email(email: &str, global_salt: Option<&str>) -> Result<String,
 pub fn anonymise_id(
     id: &str,
     transformer_type: TransformerType,
-    args: Option<HashMap<String, String>>,
+    args: Option<TransformerArgs>,
     global_salt: Option<&str>,
 ) -> Result<String, String> {
-    // Create a transformer struct
-    let transformer = Transformer {
+    let transformer = ParsedTransformer::parse(&Transformer {
         name: transformer_type,
         args,
-    };
+    })?;
 
     // Create a dummy RNG (will be replaced by transformer's internal RNG for deterministic operations)
     let mut rng = SmallRng::seed_from_u64(0);
@@ -75,28 +79,65 @@ pub fn anonymise_id(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::collections::HashMap;
 
     #[test]
     fn test_anonymise_email() {
         let email = "test@example.com";
-        let result = anonymise_email(email, None).unwrap();
+        let result = anonymise_email(email, None, None).unwrap();
 
         // Should not be the same as input
         assert_ne!(result, email);
 
         // Should be deterministic
-        let result2 = anonymise_email(email, None).unwrap();
+        let result2 = anonymise_email(email, None, None).unwrap();
         assert_eq!(result, result2);
 
         // Should contain a hash prefix
         assert!(result.contains('-'));
     }
 
+    #[test]
+    fn test_anonymise_email_with_args() {
+        let mut args = HashMap::new();
+        args.insert("domain".to_string(), "example.test".into());
+        let result = anonymise_email("jo@multiverse.io", Some(args), None).unwrap();
+        assert!(result.ends_with("@example.test"));
+
+        // Matches the transformer with the same args
+        let mut args = HashMap::new();
+        args.insert("domain".to_string(), "example.test".into());
+        let transformer = ParsedTransformer::parse(&Transformer {
+            name: TransformerType::FakeEmail,
+            args: Some(args),
+        })
+        .unwrap();
+        let transformed = transformer::transform(
+            &mut SmallRng::seed_from_u64(1),
+            "jo@multiverse.io",
+            &Type::SingleValue {
+                sub_type: crate::parsers::types::SubType::Character,
+            },
+            &transformer,
+            "public.users",
+            &[],
+            None,
+        );
+        assert_eq!(result, transformed);
+
+        let mut args = HashMap::new();
+        args.insert("keep_domains".to_string(), "multiverse.io".into());
+        assert_eq!(
+            anonymise_email("jo@multiverse.io", Some(args), None).unwrap_err(),
+            "invalid args for FakeEmail: 'keep_domains' must be a list of domains"
+        );
+    }
+
     #[test]
     fn test_anonymise_id_with_fake_uuid() {
         let id = "12345";
         let mut args = HashMap::new();
-        args.insert("deterministic".to_string(), "true".to_string());
+        args.insert("deterministic".to_string(), "true".into());
 
         let result = anonymise_id(id, TransformerType::FakeUUID, Some(args), None).unwrap();
 
@@ -105,7 +146,7 @@ mod tests {
 
         // Should be deterministic
         let mut args2 = HashMap::new();
-        args2.insert("deterministic".to_string(), "true".to_string());
+        args2.insert("deterministic".to_string(), "true".into());
         let result2 = anonymise_id(id, TransformerType::FakeUUID, Some(args2), None).unwrap();
         assert_eq!(result, result2);
 
@@ -0,0 +1,238 @@
+use chrono::{SecondsFormat, Utc};
+use log::kv::{self, Key, VisitSource};
+use log::{LevelFilter, Log, Metadata, Record};
+use serde_json::{Map, Number, Value};
+use std::io::Write;
+use std::str::FromStr;
+
+/// The only field values from the dump may be logged in, e.g. `trace!(value = word; "...")`.
+/// It's redacted unless `--log-values` is given, so values must never go in the message itself
+pub const VALUE: &str = "value";
+const REDACTED: &str = "[redacted]";
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LogFormat {
+    Text,
+    /// One JSON object per line, with the fields as keys
+    Json,
+}
+type ParseError = &'static str;
+
+impl FromStr for LogFormat {
+    type Err = ParseError;
+    fn from_str(log_format: &str) -> Result<Self, Self::Err> {
+        match log_format {
+            "text" => Ok(LogFormat::Text),
+            "json" => Ok(LogFormat::Json),
+            _ => Err("Could not parse log format"),
+        }
+    }
+}
+
+/// Writes log events to stderr, with the subcommand being run as the `command` field of each
+pub struct Logger {
+    level: LevelFilter,
+    format: LogFormat,
+    command: &'static str,
+    log_values: bool,
+}
+
+pub fn init(level: LevelFilter, format: LogFormat, command: &'static str, log_values: bool) {
+    let logger = Logger {
+        level,
+        format,
+        command,
+        log_values,
+    };
+    log::set_boxed_logger(Box::new(logger))
+        .map(|()| log::set_max_level(level))
+        .expect("Unable to set up logging");
+}
+
+impl Log for Logger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= self.level
+    }
+
+    fn log(&self, record: &Record) {
+        if self.enabled(record.metadata()) {
+            let _ = writeln!(
+                std::io::stderr().lock(),
+                "{}",
+                self.format_record(record, &timestamp())
+            );
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+impl Logger {
+    fn format_record(&self, record: &Record, timestamp: &str) -> String {
+        let mut fields = Fields {
+            fields: vec![("command".to_string(), Value::from(self.command))],
+            log_values: self.log_values,
+        };
+        let _ = record.key_values().visit(&mut fields);
+
+        match self.format {
+            LogFormat::Text => {
+                let fields: String = fields
+                    .fields
+                    .iter()
+                    .map(|(key, value)| format!(" {}={}", key, text_value(value)))
+                    .collect();
+                format!(
+                    "{} {:5} {}{}",
+                    timestamp,
+                    record.level(),
+                    record.args(),
+                    fields
+                )
+            }
+            LogFormat::Json => {
+                let mut object = Map::new();
+                object.insert("timestamp".to_string(), Value::from(timestamp));
+                object.insert("level".to_string(), Value::from(record.level().as_str()));
+                object.insert(
+                    "message".to_string(),
+                    Value::from(record.args().to_string()),
+                );
+                object.extend(fields.fields);
+                Value::Object(object).to_string()
+            }
+        }
+    }
+}
+
+struct Fields {
+    fields: Vec<(String, Value)>,
+    log_values: bool,
+}
+
+impl<'kvs> VisitSource<'kvs> for Fields {
+    fn visit_pair(&mut self, key: Key<'kvs>, value: kv::Value<'kvs>) -> Result<(), kv::Error> {
+        let value = if key.as_str() == VALUE && !self.log_values {
+            Value::from(REDACTED)
+        } else if let Some(b) = value.to_bool() {
+            Value::from(b)
+        } else if let Some(n) = value.to_u64() {
+            Value::from(n)
+        } else if let Some(n) = value.to_i64() {
+            Value::from(n)
+        } else if let Some(n) = value.to_f64().and_then(Number::from_f64) {
+            Value::Number(n)
+        } else {
+            Value::from(value.to_string())
+        };
+        self.fields.push((key.as_str().to_string(), value));
+        Ok(())
+    }
+}
+
+fn text_value(value: &Value) -> String {
+    match value {
+        Value::String(s)
+            if s.is_empty() || s.contains(|c: char| c.is_whitespace() || c == '"' || c == '=') =>
+        {
+            value.to_string()
+        }
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+fn timestamp() -> String {
+    Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use log::Level;
+
+    fn logger(format: LogFormat, log_values: bool) -> Logger {
+        Logger {
+            level: LevelFilter::Info,
+            format,
+            command: "check-strategies",
+            log_values,
+        }
+    }
+
+    fn format(logger: &Logger, fields: &[(&str, kv::Value)]) -> String {
+        logger.format_record(
+            &Record::builder()
+                .args(format_args!("Missing from the strategy file"))
+                .level(Level::Error)
+                .key_values(&fields)
+                .build(),
+            "2024-05-01T09:00:00.000Z",
+        )
+    }
+
+    #[test]
+    fn formats_text_with_fields_after_the_message() {
+        let line = format(
+            &logger(LogFormat::Text, false),
+            &[
+                ("table", kv::Value::from("public.users")),
+                ("reason", kv::Value::from("two words")),
+                ("rows", kv::Value::from(3)),
+            ],
+        );
+        assert_eq!(
+            line,
+            "2024-05-01T09:00:00.000Z ERROR Missing from the strategy file command=check-strategies table=public.users reason=\"two words\" rows=3"
+        );
+    }
+
+    #[test]
+    fn formats_json_with_fields_as_keys() {
+        let line = format(
+            &logger(LogFormat::Json, false),
+            &[
+                ("table", kv::Value::from("public.users")),
+                ("rows", kv::Value::from(3)),
+                ("ratio", kv::Value::from(0.5)),
+            ],
+        );
+        assert_eq!(
+            serde_json::from_str::<Value>(&line).unwrap(),
+            serde_json::json!({
+                "timestamp": "2024-05-01T09:00:00.000Z",
+                "level": "ERROR",
+                "message": "Missing from the strategy file",
+                "command": "check-strategies",
+                "table": "public.users",
+                "rows": 3,
+                "ratio": 0.5,
+            })
+        );
+    }
+
+    #[test]
+    fn redacts_values_unless_enabled() {
+        let fields = [(VALUE, kv::Value::from("jane@example.com"))];
+        assert!(format(&logger(LogFormat::Text, false), &fields).ends_with(" value=[redacted]"));
+        assert!(
+            format(&logger(LogFormat::Text, true), &fields).ends_with(" value=jane@example.com")
+        );
+    }
+
+    #[test]
+    fn only_logs_at_or_above_the_level() {
+        let logger = logger(LogFormat::Text, false);
+        let metadata = |level| Metadata::builder().level(level).build();
+        assert!(logger.enabled(&metadata(Level::Warn)));
+        assert!(logger.enabled(&metadata(Level::Info)));
+        assert!(!logger.enabled(&metadata(Level::Debug)));
+    }
+
+    #[test]
+    fn parses_log_formats() {
+        assert_eq!(LogFormat::from_str("text"), Ok(LogFormat::Text));
+        assert_eq!(LogFormat::from_str("json"), Ok(LogFormat::Json));
+        assert!(LogFormat::from_str("xml").is_err());
+    }
+}
@@ -3,22 +3,32 @@ mod compression_type;
 mod file_reader;
 mod fixers;
 mod helpers;
+mod logger;
 mod opts;
 mod parsers;
+mod progress;
+mod report;
+mod scanner;
 mod uncompress;
+mod verifier;
 
 use crate::fixers::fixer;
 use crate::fixers::fixer::SortResult;
 use crate::opts::{Anonymiser, Opts};
+use crate::parsers::column_rules::ColumnRules;
 use crate::parsers::custom_classifications::ClassificationConfig;
 use crate::parsers::strategies::Strategies;
 use crate::parsers::strategy_errors::StrategyFileError;
-use crate::parsers::strategy_structs::{StrategyInFile, TransformerOverrides, TransformerType};
-use colored::Colorize;
+use crate::parsers::strategy_schema;
+use crate::parsers::strategy_structs::{
+    StrategyInFile, TransformerArgs, TransformerOverrides, TransformerType,
+};
+use crate::progress::ProgressMode;
+use log::{error, info, warn};
 use native_tls::TlsConnector;
 use postgres_native_tls::MakeTlsConnector;
 
-use parsers::{db_schema, strategy_file};
+use parsers::{column_rules, db_schema, strategy_file};
 use structopt::StructOpt;
 
 use mimalloc::MiMalloc;
@@ -28,6 +38,12 @@ static GLOBAL: MiMalloc = MiMalloc;
 
 fn main() -> Result<(), std::io::Error> {
     let opt = Opts::from_args();
+    logger::init(
+        opt.log_level,
+        opt.log_format,
+        opt.commands.name(),
+        opt.log_values,
+    );
 
     match opt.commands {
         Anonymiser::Anonymise {
@@ -39,6 +55,9 @@ fn main() -> Result<(), std::io::Error> {
             allow_commercially_sensitive,
             scramble_blank,
             classifications_file,
+            report_file,
+            progress,
+            max_unique_values,
         } => {
             let transformer_overrides = TransformerOverrides {
                 allow_potential_pii,
@@ -56,6 +75,12 @@ fn main() -> Result<(), std::io::Error> {
                 compress_output,
                 transformer_overrides,
                 custom_classifications,
+                anonymiser::RunOptions {
+                    report_file,
+                    progress_mode: progress
+                        .map(|mode| mode.unwrap_or_else(ProgressMode::for_stderr)),
+                    max_unique_values,
+                },
             )?
         }
         Anonymiser::ToCsv {
@@ -75,25 +100,23 @@ fn main() -> Result<(), std::io::Error> {
             match read_strategy_file(&strategy_file, &db_url) {
                 Ok(strategies) => {
                     match strategy_differences(strategies, db_url.clone(), custom_classifications) {
-                        Ok(()) => println!("All up to date"),
+                        Ok(()) => info!("All up to date"),
                         Err(err) => {
-                            println!("{}", err);
+                            log_strategy_errors(&err);
                             if fixer::can_fix(&err) {
-                                let retry_command = format!(
-                                    "anonymiser fix-strategies --db-url={} --strategy-file={}",
+                                info!(
+                                    "But the great news is we can fix at least some of your mess... try running: anonymiser fix-strategies --db-url={} --strategy-file={}",
                                     db_url, strategy_file
-                                )
-                                .green();
-                                println!("But the great news is we can fix at least some of your mess... try running:\n{}", retry_command);
+                                );
                             } else {
-                                println!("Bad news... we currently cannot fix this for you, you'll have to sort it out yourself!");
+                                error!("Bad news... we currently cannot fix this for you, you'll have to sort it out yourself!");
                             }
                             std::process::exit(1);
                         }
                     }
                 }
                 Err(err) => {
-                    println!("{}", err);
+                    error!("{}", err);
                     std::process::exit(1);
                 }
             }
@@ -103,29 +126,41 @@ fn main() -> Result<(), std::io::Error> {
             strategy_file,
             db_url,
             classifications_file,
+            column_rules,
         } => {
             let custom_classifications = load_custom_classifications(classifications_file);
+            let column_rules = load_column_rules(column_rules);
             match read_strategy_file(&strategy_file, &db_url) {
                 Ok(strategies) => {
-                    match strategy_differences(strategies, db_url, custom_classifications.clone()) {
+                    match strategy_differences(
+                        strategies,
+                        db_url.clone(),
+                        custom_classifications.clone(),
+                    ) {
                         Ok(()) => match fixer::just_sort(&strategy_file) {
                             SortResult::Sorted => {
-                                println!("Ok, we've updated that for you, check your diff!")
+                                info!("Ok, we've updated that for you, check your diff!")
                             }
                             SortResult::NoChange => {
-                                println!("Somehow you got lucky and your file was already sorted perfectly")
+                                info!("Somehow you got lucky and your file was already sorted perfectly")
                             }
                         },
                         Err(err) => {
-                            println!("{}", err);
-                            println!("Ok! lets try and fix some of this!");
-                            fixer::fix(&strategy_file, *err, custom_classifications);
-                            println!("All done, you probably want to run \"check-strategies\" again to make sure");
+                            log_strategy_errors(&err);
+                            info!("Ok! lets try and fix some of this!");
+                            fixer::fix(
+                                &strategy_file,
+                                *err,
+                                custom_classifications,
+                                &column_rules,
+                                &db_schema::column_types(&mut connect(&db_url)),
+                            );
+                            info!("All done, you probably want to run \"check-strategies\" again to make sure");
                         }
                     }
                 }
                 Err(err) => {
-                    println!("{}", err);
+                    error!("{}", err);
                     std::process::exit(1);
                 }
             }
@@ -135,29 +170,123 @@ fn main() -> Result<(), std::io::Error> {
             strategy_file,
             db_url,
             classifications_file,
+            column_rules,
         } => {
             let custom_classifications = load_custom_classifications(classifications_file);
+            let column_rules = load_column_rules(column_rules);
             match strategy_differences(Vec::new(), db_url.clone(), custom_classifications.clone()) {
-                Ok(()) => println!("All up to date"),
+                Ok(()) => info!("All up to date"),
                 Err(err) => {
                     if fixer::can_fix(&err) {
-                        fixer::fix(&strategy_file, *err, custom_classifications);
-                        println!("All done, you'll need to set a data_type and transformer for those fields");
+                        fixer::fix(
+                            &strategy_file,
+                            *err,
+                            custom_classifications,
+                            &column_rules,
+                            &db_schema::column_types(&mut connect(&db_url)),
+                        );
+                        info!("All done, you'll need to set a data_type and transformer for the fields left as Unknown, and review the ones marked \"{}\"", column_rules::AUTO_CLASSIFIED);
                     }
                     std::process::exit(1);
                 }
             }
         }
+        Anonymiser::Scan {
+            strategy_file,
+            db_url,
+            sample_size,
+            threshold,
+            write,
+        } => {
+            let strategies = read_strategy_file(&strategy_file, &db_url).unwrap_or_else(|err| {
+                error!("{}", err);
+                std::process::exit(1);
+            });
+            let mut client = connect(&db_url);
+            let column_types = db_schema::column_types(&mut client);
+            let scans = scanner::scan(&mut client, &column_types, sample_size);
+            let findings = scanner::findings(scans, &strategies, threshold);
+            if findings.is_empty() {
+                info!("No unclassified PII found");
+            } else {
+                for finding in &findings {
+                    warn!(
+                        table = finding.scan.column.table_name.as_str(),
+                        column = finding.scan.column.column_name.as_str();
+                        "Looks like it holds PII, but isn't classified as PII: {}",
+                        finding
+                    );
+                }
+                if write {
+                    strategy_file::write(&strategy_file, scanner::apply(strategies, &findings))
+                        .expect("Unable to write to file :(");
+                    info!("Ok, we've updated that for you, check your diff!");
+                } else {
+                    std::process::exit(1);
+                }
+            }
+        }
+        Anonymiser::Verify {
+            input_file,
+            output_file,
+            strategy_file,
+            extract,
+            min_length,
+            max_values,
+        } => {
+            let strategies = strategy_file::read(&strategy_file).unwrap_or_else(|_| {
+                error!("Strategy file {} not found", strategy_file);
+                std::process::exit(1);
+            });
+            let extractors = verifier::extractors(&extract).unwrap_or_else(|err| {
+                error!("{}", err);
+                std::process::exit(1);
+            });
+            let leaks = verifier::verify(
+                &input_file,
+                &output_file,
+                &strategies,
+                &extractors,
+                min_length,
+                max_values,
+            )?;
+            if leaks.is_empty() {
+                info!("No original values found in {}", output_file);
+            } else {
+                for leak in &leaks {
+                    let (table, column) = leak.location.as_ref().map_or(("", ""), |c| {
+                        (c.table_name.as_str(), c.column_name.as_str())
+                    });
+                    error!(
+                        table = table,
+                        column = column,
+                        source_table = leak.source.table_name.as_str(),
+                        source_column = leak.source.column_name.as_str(),
+                        count = leak.count;
+                        "Original values were found in {}: {}",
+                        output_file,
+                        leak
+                    );
+                }
+                std::process::exit(1);
+            }
+        }
         Anonymiser::Uncompress {
             input_file,
             output_file,
         } => uncompress::uncompress(input_file, output_file).expect("failed to uncompress"),
+        Anonymiser::Schema => println!(
+            "{}",
+            serde_json::to_string_pretty(&strategy_schema::schema()).unwrap()
+        ),
 
-        Anonymiser::AnonymiseEmail { email, salt } => {
-            match crate::helpers::anonymise_email(&email, salt.as_deref()) {
+        Anonymiser::AnonymiseEmail { email, args, salt } => {
+            match parse_args(args)
+                .and_then(|args| crate::helpers::anonymise_email(&email, args, salt.as_deref()))
+            {
                 Ok(result) => println!("{}", result),
                 Err(err) => {
-                    eprintln!("Error: {}", err);
+                    error!("{}", err);
                     std::process::exit(1);
                 }
             }
@@ -171,7 +300,7 @@ fn main() -> Result<(), std::io::Error> {
         } => match handle_anonymise_id(id, transformer, args, salt) {
             Ok(result) => println!("{}", result),
             Err(err) => {
-                eprintln!("Error: {}", err);
+                error!("{}", err);
                 std::process::exit(1);
             }
         },
@@ -191,31 +320,33 @@ fn handle_anonymise_id(
         serde_json::from_str(&format!("\"{}\"", transformer))
             .map_err(|_| format!("Unknown transformer type: {}", transformer))?;
 
-    // Parse args if provided
-    let parsed_args = match args {
-        Some(args_str) => {
-            match serde_json::from_str::<std::collections::HashMap<String, String>>(&args_str) {
-                Ok(args_map) => Some(args_map),
-                Err(err) => return Err(format!("Invalid JSON args: {}", err)),
-            }
-        }
-        None => None,
-    };
+    let parsed_args = parse_args(args)?;
 
     // Call the helper function
     crate::helpers::anonymise_id(&id, transformer_type, parsed_args, salt.as_deref())
 }
 
+/// Parses the JSON transformer args of the helper commands, if provided
+fn parse_args(args: Option<String>) -> Result<Option<TransformerArgs>, String> {
+    match args {
+        Some(args_str) => match serde_json::from_str::<TransformerArgs>(&args_str) {
+            Ok(args_map) => Ok(Some(args_map)),
+            Err(err) => Err(format!("Invalid JSON args: {}", err)),
+        },
+        None => Ok(None),
+    }
+}
+
 /// Loads custom classifications from the provided file or returns an empty config if none provided
 fn load_custom_classifications(file: Option<String>) -> ClassificationConfig {
     if let Some(file_path) = file {
         match ClassificationConfig::from_file(&file_path) {
             Ok(config) => {
-                println!("Loaded custom classifications from file: {}", file_path);
+                info!(file = file_path.as_str(); "Loaded custom classifications");
                 config
             }
             Err(err) => {
-                eprintln!("Failed to load custom classifications from file: {}", err);
+                warn!(file = file_path.as_str(); "Failed to load custom classifications: {}", err);
                 ClassificationConfig::default()
             }
         }
@@ -224,19 +355,44 @@ fn load_custom_classifications(file: Option<String>) -> ClassificationConfig {
     }
 }
 
+fn load_column_rules(file: Option<String>) -> ColumnRules {
+    match file {
+        Some(file_path) => ColumnRules::from_file(&file_path).unwrap_or_else(|err| {
+            error!(file = file_path.as_str(); "Failed to load column rules: {}", err);
+            std::process::exit(1);
+        }),
+        None => ColumnRules::default(),
+    }
+}
+
+fn connect(db_url: &str) -> postgres::Client {
+    let builder = TlsConnector::builder();
+    let connector =
+        MakeTlsConnector::new(builder.build().expect("should be able to create builder!"));
+
+    postgres::Client::connect(db_url, connector).expect("expected to connect!")
+}
+
 fn read_strategy_file(strategy_file: &str, db_url: &str) -> Result<Vec<StrategyInFile>, String> {
     match strategy_file::read(strategy_file) {
         Ok(strategies) => Ok(strategies),
-        Err(_) => {
-            let retry_command = format!(
-                "anonymiser generate-strategies --db-url={} --strategy-file={}",
-                db_url, strategy_file
-            )
-            .green();
-            Err(format!(
-                "Strategy file {} not found. You can use \n{}\nto create an initial file",
-                strategy_file, retry_command
-            ))
+        Err(_) => Err(format!(
+            "Strategy file {} not found. You can use `anonymiser generate-strategies --db-url={} --strategy-file={}` to create an initial file",
+            strategy_file, db_url, strategy_file
+        )),
+    }
+}
+
+fn log_strategy_errors(err: &StrategyFileError) {
+    for problem in err.problems() {
+        match problem.column_name {
+            Some(column_name) => error!(
+                table = problem.table_name.as_str(),
+                column = column_name.as_str();
+                "{}",
+                problem.description
+            ),
+            None => error!(table = problem.table_name.as_str(); "{}", problem.description),
         }
     }
 }
@@ -251,15 +407,18 @@ fn strategy_differences(
         Strategies::from_strategies_in_file(strategies, &transformer, &custom_classifications)
             .map_err(|e| Box::new(StrategyFileError::ValidationError(Box::new(*e))))?;
 
-    let builder = TlsConnector::builder();
-    let connector =
-        MakeTlsConnector::new(builder.build().expect("should be able to create builder!"));
-
-    let mut client = postgres::Client::connect(&db_url, connector).expect("expected to connect!");
+    let mut client = connect(&db_url);
     let db_columns = db_schema::parse(&mut client);
+    let non_nullable_columns = db_schema::non_nullable_columns(&mut client);
     parsed_strategies
         .validate_against_db(db_columns)
+        .and_then(|()| parsed_strategies.validate_nullability(&non_nullable_columns))
         .map_err(|e| Box::new(StrategyFileError::DbMismatchError(Box::new(e))))?;
+
+    let column_types = db_schema::column_types(&mut client);
+    parsed_strategies
+        .validate_column_types(&column_types)
+        .map_err(|e| Box::new(StrategyFileError::ValidationError(e)))?;
     Ok(())
 }
 
@@ -1,4 +1,7 @@
 use crate::compression_type::CompressionType;
+use crate::logger::LogFormat;
+use crate::progress::ProgressMode;
+use log::LevelFilter;
 use std::path::PathBuf;
 use structopt::StructOpt;
 #[derive(Debug, StructOpt)]
@@ -6,6 +9,19 @@ use structopt::StructOpt;
 pub struct Opts {
     #[structopt(subcommand)]
     pub commands: Anonymiser,
+
+    /// The least severe level logged, one of 'error', 'warn', 'info', 'debug' or 'trace'
+    #[structopt(long, global = true, default_value = "info")]
+    pub log_level: LevelFilter,
+
+    /// Either 'text' or 'json' (one object per line)
+    #[structopt(long, global = true, default_value = "text")]
+    pub log_format: LogFormat,
+
+    /// Allows values from the dump in logs, which are redacted otherwise. Only for debugging
+    /// with data that isn't sensitive
+    #[structopt(long, global = true)]
+    pub log_values: bool,
 }
 
 #[derive(Debug, StructOpt)]
@@ -16,7 +32,7 @@ pub enum Anonymiser {
         input_file: String,
         #[structopt(short, long, default_value = "./output.sql")]
         output_file: String,
-        /// Path to the strategy.json file
+        /// Path to the strategy file (.json, .yaml, .yml or .toml) or a directory of them
         #[structopt(short, long, default_value = "./strategy.json")]
         strategy_file: String,
         /// Either just a flag
@@ -36,6 +52,19 @@ pub enum Anonymiser {
         /// Path to a local file with custom classifications (format: path/to/file.json)
         #[structopt(long)]
         classifications_file: Option<String>,
+        /// Writes a JSON report of the run to this path, with row and null counts per table and
+        /// column, the transformers applied, timings and a hash of the strategy file
+        #[structopt(long)]
+        report_file: Option<String>,
+        /// Shows progress on stderr, either just a flag
+        /// e.g. '--progress' in which case it's a bar when stderr is a terminal and log lines
+        /// otherwise, or with a mode e.g. '--progress bar' or '--progress log' (a line every 30s)
+        #[structopt(long)]
+        progress: Option<Option<ProgressMode>>,
+        /// The most fingerprints of values kept in memory for 'ensure_unique' and 'unique_group'
+        /// at once (about 30-40 bytes each), the run stops if there are more
+        #[structopt(long, default_value = "50000000")]
+        max_unique_values: usize,
     },
 
     /// Creates a CSV file of PII or PotentialPII fields
@@ -43,7 +72,7 @@ pub enum Anonymiser {
         /// Path to write csv file to
         #[structopt(short, long, default_value = "./output.csv")]
         output_file: String,
-        /// Path to the strategy.json file
+        /// Path to the strategy file (.json, .yaml, .yml or .toml) or a directory of them
         #[structopt(short, long, default_value = "./strategy.json")]
         strategy_file: String,
         /// Path to a local file with custom classifications (format: path/to/file.json)
@@ -76,6 +105,10 @@ pub enum Anonymiser {
         /// Path to a local file with custom classifications (format: path/to/file.json)
         #[structopt(long)]
         classifications_file: Option<String>,
+
+        /// Path to a json file of rules for classifying new columns, tried before the built in ones
+        #[structopt(long)]
+        column_rules: Option<String>,
     },
 
     /// Generates a new skeleton strategy file from a db connection
@@ -89,6 +122,58 @@ pub enum Anonymiser {
         /// Path to a local file with custom classifications (format: path/to/file.json)
         #[structopt(long)]
         classifications_file: Option<String>,
+
+        /// Path to a json file of rules for classifying new columns, tried before the built in ones
+        #[structopt(long)]
+        column_rules: Option<String>,
+    },
+
+    /// Samples the text and json columns of a database for PII, and reports the ones the strategy
+    /// file doesn't classify as PII
+    Scan {
+        #[structopt(short, long, default_value = "./strategy.json")]
+        strategy_file: String,
+
+        #[structopt(short, long, env = "DATABASE_URL")]
+        db_url: String,
+
+        /// The number of values sampled from each column
+        #[structopt(long, default_value = "1000")]
+        sample_size: usize,
+
+        /// The fraction of sampled values that have to look like PII for a column to be reported
+        #[structopt(long, default_value = "0.1")]
+        threshold: f64,
+
+        /// Writes the suggested data category and transformer of the reported columns into the
+        /// strategy file
+        #[structopt(long)]
+        write: bool,
+    },
+
+    /// Checks that no original value of a column which should be anonymised turns up anywhere in
+    /// the anonymised dump
+    Verify {
+        /// The clear text dump that was anonymised
+        #[structopt(short, long, default_value = "./clear_text_dump.sql")]
+        input_file: String,
+        /// The anonymised dump, which can be compressed
+        #[structopt(short, long, default_value = "./output.sql")]
+        output_file: String,
+        /// Path to the strategy file (.json, .yaml, .yml or .toml) or a directory of them
+        #[structopt(short, long, default_value = "./strategy.json")]
+        strategy_file: String,
+        /// Built in patterns used to pull values out of free text and check for them too,
+        /// e.g. '--extract email,phone_number,uk_postcode'
+        #[structopt(long, default_value = "email,phone_number", use_delimiter = true)]
+        extract: Vec<String>,
+        /// Original values shorter than this are ignored, as they turn up in the output by chance
+        #[structopt(long, default_value = "4")]
+        min_length: usize,
+        /// The most original values held in memory at once, both dumps are read again for each
+        /// this many of them
+        #[structopt(long, default_value = "5000000")]
+        max_values: usize,
     },
 
     /// Uncompress a zstd sql dump to a file, or stdout if no file specified
@@ -103,11 +188,17 @@ pub enum Anonymiser {
         output_file: Option<PathBuf>,
     },
 
+    /// Prints the JSON Schema of the strategy file, for editor integration
+    Schema,
+
     /// Get anonymised email for a given email address
     AnonymiseEmail {
         /// Original email address to anonymise
         #[structopt(long)]
         email: String,
+        /// Optional FakeEmail arguments in JSON format (e.g., '{"domain": "example.test"}')
+        #[structopt(long)]
+        args: Option<String>,
         /// Optional global salt for deterministic generation
         #[structopt(long)]
         salt: Option<String>,
@@ -129,3 +220,22 @@ pub enum Anonymiser {
         salt: Option<String>,
     },
 }
+
+impl Anonymiser {
+    /// The name of the subcommand as it's given on the command line
+    pub fn name(&self) -> &'static str {
+        match self {
+            Anonymiser::Anonymise { .. } => "anonymise",
+            Anonymiser::ToCsv { .. } => "to-csv",
+            Anonymiser::CheckStrategies { .. } => "check-strategies",
+            Anonymiser::FixStrategies { .. } => "fix-strategies",
+            Anonymiser::GenerateStrategies { .. } => "generate-strategies",
+            Anonymiser::Scan { .. } => "scan",
+            Anonymiser::Verify { .. } => "verify",
+            Anonymiser::Uncompress { .. } => "uncompress",
+            Anonymiser::Schema => "schema",
+            Anonymiser::AnonymiseEmail { .. } => "anonymise-email",
+            Anonymiser::AnonymiseId { .. } => "anonymise-id",
+        }
+    }
+}
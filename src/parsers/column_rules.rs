@@ -0,0 +1,436 @@
+use crate::parsers::strategy_structs::{ColumnInFile, DataCategory, SimpleColumn, Transformer};
+use crate::parsers::transformer_args::ParsedTransformer;
+use regex::{Regex, RegexBuilder};
+use serde::Deserialize;
+use serde_json::json;
+use std::collections::HashMap;
+use std::fs;
+
+/// The start of the description of every column classified by a rule, so that reviewers can spot
+/// (and search for) the classifications nobody has checked yet
+pub const AUTO_CLASSIFIED: &str = "Auto-classified";
+
+/// A rule for classifying new columns. Every pattern given has to match (case insensitively) for
+/// the rule to apply
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ColumnRule {
+    /// Shown in the description of the columns it classifies, defaults to the column pattern
+    pub name: Option<String>,
+    /// A pattern for the schema and name of the table e.g. `^public\.`
+    pub table: Option<String>,
+    /// A pattern for the name of the column e.g. `email`
+    pub column: Option<String>,
+    /// A pattern for the Postgres type of the column e.g. `^timestamp`
+    #[serde(rename = "type")]
+    pub column_type: Option<String>,
+    pub data_category: DataCategory,
+    pub transformer: Transformer,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RulesFile {
+    rules: Vec<ColumnRule>,
+    /// Whether the built in rules are tried after the ones in the file
+    #[serde(default = "use_default_rules")]
+    use_default_rules: bool,
+}
+
+fn use_default_rules() -> bool {
+    true
+}
+
+#[derive(Debug)]
+struct CompiledRule {
+    rule: ColumnRule,
+    table: Option<Regex>,
+    column: Option<Regex>,
+    column_type: Option<Regex>,
+}
+
+/// An ordered list of rules, where the first rule to match a column classifies it
+#[derive(Debug)]
+pub struct ColumnRules {
+    rules: Vec<CompiledRule>,
+}
+
+impl Default for ColumnRules {
+    fn default() -> Self {
+        ColumnRules::new(default_rules()).unwrap()
+    }
+}
+
+impl ColumnRules {
+    pub fn new(rules: Vec<ColumnRule>) -> Result<Self, String> {
+        let rules = rules
+            .into_iter()
+            .enumerate()
+            .map(|(index, rule)| {
+                let context = rule
+                    .name
+                    .clone()
+                    .unwrap_or_else(|| format!("{}", index + 1));
+                let compile = |pattern: &Option<String>| {
+                    pattern
+                        .as_ref()
+                        .map(|pattern| {
+                            RegexBuilder::new(pattern)
+                                .case_insensitive(true)
+                                .build()
+                                .map_err(|e| {
+                                    format!("rule {} has an invalid pattern: {}", context, e)
+                                })
+                        })
+                        .transpose()
+                };
+                ParsedTransformer::parse(&rule.transformer)
+                    .map_err(|e| format!("rule {} has {}", context, e))?;
+                Ok(CompiledRule {
+                    table: compile(&rule.table)?,
+                    column: compile(&rule.column)?,
+                    column_type: compile(&rule.column_type)?,
+                    rule,
+                })
+            })
+            .collect::<Result<Vec<CompiledRule>, String>>()?;
+        Ok(ColumnRules { rules })
+    }
+
+    /// Reads the rules from a json file, which are tried before the built in ones unless
+    /// `use_default_rules` is `false`
+    pub fn from_file(path: &str) -> Result<Self, String> {
+        let content = fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read column rules file: {}", e))?;
+        let file = serde_json::from_str::<RulesFile>(&content)
+            .map_err(|e| format!("Failed to parse column rules JSON: {}", e))?;
+
+        let mut rules = file.rules;
+        if file.use_default_rules {
+            rules.extend(default_rules());
+        }
+        ColumnRules::new(rules)
+    }
+
+    /// The column for the strategy file, classified by the first rule that matches it or left for
+    /// someone to classify (as `Unknown` with an `Error` transformer) if none do
+    pub fn classify(
+        &self,
+        column: &SimpleColumn,
+        column_types: &HashMap<SimpleColumn, String>,
+    ) -> ColumnInFile {
+        let column_type = column_types.get(column).map(String::as_str);
+        let matches = |pattern: &Option<Regex>, text: Option<&str>| match (pattern, text) {
+            (None, _) => true,
+            (Some(pattern), Some(text)) => pattern.is_match(text),
+            (Some(_), None) => false,
+        };
+
+        self.rules
+            .iter()
+            .find(|compiled| {
+                matches(&compiled.table, Some(&column.table_name))
+                    && matches(&compiled.column, Some(&column.column_name))
+                    && matches(&compiled.column_type, column_type)
+            })
+            .map(|compiled| {
+                let rule = &compiled.rule;
+                let rule_name = rule
+                    .name
+                    .as_deref()
+                    .or(rule.column.as_deref())
+                    .or(rule.column_type.as_deref())
+                    .unwrap_or_default();
+                ColumnInFile {
+                    data_category: rule.data_category.clone(),
+                    description: format!(
+                        "{} by rule '{}', please review",
+                        AUTO_CLASSIFIED, rule_name
+                    ),
+                    name: column.column_name.clone(),
+                    transformer: rule.transformer.clone(),
+                }
+            })
+            .unwrap_or_else(|| ColumnInFile::new(&column.column_name))
+    }
+}
+
+/// The built in rules, for the columns most databases have. The PII rules come before the generic
+/// `id` and `timestamp` ones, so `born_on` is a date of birth rather than a timestamp, and the
+/// types keep e.g. `email_verified_at` from being an email. There's no rule for booleans, as
+/// flags like `has_disability` can be special category data
+fn default_rules() -> Vec<ColumnRule> {
+    serde_json::from_value(json!([
+        {
+            "name": "date of birth",
+            "column": "^(dob|date_of_birth|birth_?date|birthday|born_(on|at))$",
+            "data_category": "Pii",
+            "transformer": {"name": "ObfuscateDay"}
+        },
+        {
+            "name": "email",
+            "column": "e_?mail",
+            "type": "char|text",
+            "data_category": "Pii",
+            "transformer": {"name": "FakeEmail"}
+        },
+        {
+            "name": "first name",
+            "column": "^(first|given|fore)_?name$",
+            "data_category": "Pii",
+            "transformer": {"name": "FakeFirstName"}
+        },
+        {
+            "name": "last name",
+            "column": "^(last|family|sur)_?name$",
+            "data_category": "Pii",
+            "transformer": {"name": "FakeLastName"}
+        },
+        {
+            "name": "full name",
+            "column": "^full_?name$",
+            "data_category": "Pii",
+            "transformer": {"name": "FakeFullName"}
+        },
+        {
+            "name": "phone number",
+            "column": "phone|mobile|^tel$|telephone",
+            "type": "char|text",
+            "data_category": "Pii",
+            "transformer": {"name": "FakePhoneNumber"}
+        },
+        {
+            "name": "post code",
+            "column": "^(post_?code|postal_code|zip(_?code)?)$",
+            "data_category": "Pii",
+            "transformer": {"name": "FakePostCode"}
+        },
+        {
+            "name": "street address",
+            "column": "^(street|address_line_?\\d*|address_?\\d)$",
+            "data_category": "Pii",
+            "transformer": {"name": "FakeStreetAddress"}
+        },
+        {
+            "name": "address",
+            "column": "^(full_)?address$",
+            "type": "char|text",
+            "data_category": "Pii",
+            "transformer": {"name": "FakeFullAddress"}
+        },
+        {
+            "name": "city",
+            "column": "^(city|town)$",
+            "data_category": "Pii",
+            "transformer": {"name": "FakeCity"}
+        },
+        {
+            "name": "username",
+            "column": "^(user_?name|login)$",
+            "data_category": "Pii",
+            "transformer": {"name": "FakeUsername"}
+        },
+        {
+            "name": "national identity number",
+            "column": "^(ni_number|national_insurance_number|ssn|social_security_number)$",
+            "data_category": "Pii",
+            "transformer": {"name": "FakeNationalIdentityNumber"}
+        },
+        {
+            "name": "ip address",
+            "column": "^ip(_address)?$",
+            "data_category": "Pii",
+            "transformer": {"name": "FakeIPv4"}
+        },
+        {
+            "name": "iban",
+            "column": "iban",
+            "data_category": "Pii",
+            "transformer": {"name": "FakeIban"}
+        },
+        {
+            "name": "sort code",
+            "column": "sort_?code",
+            "data_category": "Pii",
+            "transformer": {"name": "FakeSortCode"}
+        },
+        {
+            "name": "account number",
+            "column": "account_?number",
+            "data_category": "Pii",
+            "transformer": {"name": "FakeAccountNumber"}
+        },
+        {
+            "name": "card number",
+            "column": "card_?number|^pan$",
+            "data_category": "Pii",
+            "transformer": {"name": "FakeCardNumber"}
+        },
+        {
+            "name": "secret",
+            "column": "password|secret|token|api_?key",
+            "type": "char|text",
+            "data_category": "Security",
+            "transformer": {"name": "Scramble"}
+        },
+        {
+            "name": "id",
+            "column": "^id$|_id$",
+            "type": "^(smallint|integer|bigint|uuid)$",
+            "data_category": "General",
+            "transformer": {"name": "Identity"}
+        },
+        {
+            "name": "timestamp",
+            "column": "_(at|on)$|^(inserted|created|updated|modified|deleted)(_date)?$",
+            "type": "^(timestamp|date)",
+            "data_category": "General",
+            "transformer": {"name": "Identity"}
+        }
+    ]))
+    .unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parsers::strategy_structs::TransformerType;
+    use std::io::Write;
+    use tempfile::NamedTempFile;
+
+    fn column(table_name: &str, column_name: &str) -> SimpleColumn {
+        SimpleColumn {
+            table_name: table_name.to_string(),
+            column_name: column_name.to_string(),
+        }
+    }
+
+    fn classify(rules: &ColumnRules, column_name: &str, column_type: &str) -> ColumnInFile {
+        let users_column = column("public.users", column_name);
+        let column_types = HashMap::from([(users_column.clone(), column_type.to_string())]);
+        rules.classify(&users_column, &column_types)
+    }
+
+    #[test]
+    fn classifies_common_columns_with_the_default_rules() {
+        let rules = ColumnRules::default();
+        let classified = |column_name: &str, column_type: &str| {
+            let column = classify(&rules, column_name, column_type);
+            (column.data_category, column.transformer.name)
+        };
+
+        assert_eq!(
+            classified("id", "bigint"),
+            (DataCategory::General, TransformerType::Identity)
+        );
+        assert_eq!(
+            classified("email", "character varying"),
+            (DataCategory::Pii, TransformerType::FakeEmail)
+        );
+        assert_eq!(
+            classified("email_verified_at", "timestamp without time zone"),
+            (DataCategory::General, TransformerType::Identity)
+        );
+        assert_eq!(
+            classified("born_on", "date"),
+            (DataCategory::Pii, TransformerType::ObfuscateDay)
+        );
+        assert_eq!(
+            classified("has_disability", "boolean"),
+            (DataCategory::Unknown, TransformerType::Error)
+        );
+        assert_eq!(
+            classified("First_Name", "text"),
+            (DataCategory::Pii, TransformerType::FakeFirstName)
+        );
+        assert_eq!(
+            classified("bio", "text"),
+            (DataCategory::Unknown, TransformerType::Error)
+        );
+    }
+
+    #[test]
+    fn marks_classified_columns_for_review() {
+        let column = classify(&ColumnRules::default(), "email", "text");
+        assert_eq!(
+            column.description,
+            "Auto-classified by rule 'email', please review"
+        );
+
+        let unclassified = classify(&ColumnRules::default(), "bio", "text");
+        assert_eq!(unclassified.description, "");
+    }
+
+    #[test]
+    fn columns_without_a_known_type_only_match_rules_without_a_type() {
+        let rules = ColumnRules::default();
+        let email = rules.classify(&column("public.users", "email"), &HashMap::new());
+        assert_eq!(email.transformer.name, TransformerType::Error);
+
+        let city = rules.classify(&column("public.users", "city"), &HashMap::new());
+        assert_eq!(city.transformer.name, TransformerType::FakeCity);
+    }
+
+    #[test]
+    fn rules_from_a_file_come_before_the_default_ones() {
+        let mut file = NamedTempFile::new().unwrap();
+        file.write_all(
+            br#"{
+                "rules": [
+                    {
+                        "name": "audit emails",
+                        "table": "^audit\\.",
+                        "column": "email",
+                        "data_category": "General",
+                        "transformer": {"name": "Fixed", "args": {"value": "audit@example.test"}}
+                    }
+                ]
+            }"#,
+        )
+        .unwrap();
+        let rules = ColumnRules::from_file(file.path().to_str().unwrap()).unwrap();
+
+        let audit = rules.classify(&column("audit.events", "email"), &HashMap::new());
+        assert_eq!(audit.transformer.name, TransformerType::Fixed);
+        assert_eq!(
+            audit.description,
+            "Auto-classified by rule 'audit emails', please review"
+        );
+        assert_eq!(
+            classify(&rules, "email", "text").transformer.name,
+            TransformerType::FakeEmail
+        );
+    }
+
+    #[test]
+    fn rules_can_replace_the_default_ones() {
+        let mut file = NamedTempFile::new().unwrap();
+        file.write_all(br#"{"rules": [], "use_default_rules": false}"#)
+            .unwrap();
+        let rules = ColumnRules::from_file(file.path().to_str().unwrap()).unwrap();
+
+        let email = classify(&rules, "email", "text");
+        assert_eq!(email.data_category, DataCategory::Unknown);
+        assert_eq!(email.transformer.name, TransformerType::Error);
+    }
+
+    #[test]
+    fn rejects_invalid_rules() {
+        let invalid_pattern = serde_json::from_value(json!([
+            {"name": "broken", "column": "(", "data_category": "Pii", "transformer": {"name": "Null"}}
+        ]))
+        .unwrap();
+        assert!(ColumnRules::new(invalid_pattern)
+            .unwrap_err()
+            .starts_with("rule broken has an invalid pattern"));
+
+        let invalid_args = serde_json::from_value(json!([
+            {"column": "status", "data_category": "General", "transformer": {"name": "Fixed"}}
+        ]))
+        .unwrap();
+        assert_eq!(
+            ColumnRules::new(invalid_args).unwrap_err(),
+            "rule 1 has invalid args for Fixed: missing field `value`"
+        );
+    }
+}
@@ -0,0 +1,156 @@
+use crate::parsers::data_row;
+use crate::parsers::transformer;
+use crate::parsers::transformer_args::ParsedTransformer;
+use crate::parsers::types::Type;
+use rand::rngs::SmallRng;
+use regex::Regex;
+use schemars::JsonSchema;
+use serde::Deserialize;
+use std::borrow::Cow;
+
+#[derive(Clone, Debug, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct ConditionalArgs {
+    /// Transformers to use when a condition on the row matches, the first match wins
+    pub cases: Vec<Case>,
+    /// The transformer to use when no case matches
+    pub default: Box<ParsedTransformer>,
+}
+
+/// The transformer to use when `when` matches. Cases are checked in order and the first match wins
+#[derive(Clone, Debug, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct Case {
+    pub when: Predicate,
+    pub transformer: ParsedTransformer,
+}
+
+#[derive(Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+struct PredicateInFile {
+    column: String,
+    #[serde(default)]
+    equals: Option<String>,
+    #[serde(default, rename = "in")]
+    one_of: Option<Vec<String>>,
+    #[serde(default)]
+    regex: Option<String>,
+    #[serde(default)]
+    is_null: Option<bool>,
+}
+
+/// A condition on another column in the row, with any regex compiled when the strategy file is
+/// loaded
+#[derive(Clone, Debug, Deserialize, JsonSchema)]
+#[serde(try_from = "PredicateInFile")]
+#[schemars(with = "PredicateInFile")]
+pub struct Predicate {
+    pub column: String,
+    condition: Condition,
+}
+
+#[derive(Clone, Debug)]
+enum Condition {
+    Equals(String),
+    OneOf(Vec<String>),
+    Regex(Regex),
+    IsNull(bool),
+}
+
+impl TryFrom<PredicateInFile> for Predicate {
+    type Error = String;
+    fn try_from(predicate: PredicateInFile) -> Result<Self, Self::Error> {
+        let condition = match (
+            predicate.equals,
+            predicate.one_of,
+            predicate.regex,
+            predicate.is_null,
+        ) {
+            (Some(equals), None, None, None) => Condition::Equals(equals),
+            (None, Some(one_of), None, None) => Condition::OneOf(one_of),
+            (None, None, Some(regex), None) => Regex::new(&regex)
+                .map(Condition::Regex)
+                .map_err(|e| format!("invalid regex '{}': {}", regex, e))?,
+            (None, None, None, Some(is_null)) => Condition::IsNull(is_null),
+            _ => {
+                return Err(format!(
+                "the condition on '{}' needs exactly one of 'equals', 'in', 'regex' or 'is_null'",
+                predicate.column
+            ))
+            }
+        };
+        Ok(Predicate {
+            column: predicate.column,
+            condition,
+        })
+    }
+}
+
+impl Predicate {
+    fn matches(&self, column_values: &[(String, String)]) -> bool {
+        let value = column_values
+            .iter()
+            .find(|(name, _)| *name == self.column)
+            .map(|(_, value)| value.as_str());
+        let is_null = value.map_or(true, |value| value == "\\N");
+
+        if let Condition::IsNull(should_be_null) = self.condition {
+            return is_null == should_be_null;
+        }
+        if is_null {
+            return false;
+        }
+
+        let value = data_row::decode(value.unwrap_or_default());
+        match &self.condition {
+            Condition::Equals(equals) => *equals == value,
+            Condition::OneOf(one_of) => one_of.iter().any(|option| *option == value),
+            Condition::Regex(regex) => regex.is_match(&value),
+            Condition::IsNull(_) => false,
+        }
+    }
+}
+
+/// Checks the nested transformers, and that every column referred to is in the table
+pub fn validate(args: &ConditionalArgs, column_names: &[&str]) -> Result<(), String> {
+    for case in args.cases.iter() {
+        if !column_names.contains(&case.when.column.as_str()) {
+            return Err(format!(
+                "condition refers to unknown column '{}'",
+                case.when.column
+            ));
+        }
+        transformer::validate_nested(&case.transformer, column_names)?;
+    }
+    transformer::validate_nested(&args.default, column_names)
+}
+
+pub fn transform<'value>(
+    rng: &mut SmallRng,
+    value: &'value str,
+    column_type: &Type,
+    args: &ConditionalArgs,
+    table_name: &str,
+    column_values: &[(String, String)],
+    global_salt: Option<&str>,
+) -> Cow<'value, str> {
+    let chosen = args
+        .cases
+        .iter()
+        .find(|case| case.when.matches(column_values))
+        .map(|case| &case.transformer)
+        .unwrap_or(&args.default);
+
+    Cow::from(
+        transformer::transform(
+            rng,
+            value,
+            column_type,
+            chosen,
+            table_name,
+            column_values,
+            global_salt,
+        )
+        .into_owned(),
+    )
+}
@@ -2,6 +2,7 @@ use crate::parsers::sanitiser;
 use crate::parsers::strategies::Strategies;
 use crate::parsers::strategies::TableStrategy;
 use crate::parsers::strategy_structs::ColumnInfo;
+use crate::parsers::template;
 use lazy_static::lazy_static;
 use regex::Regex;
 
@@ -10,6 +11,41 @@ pub struct CurrentTableTransforms {
     pub table_name: String,
     pub table_transformers: TableTransformers,
     pub salt: Option<String>,
+    /// The order to transform the columns in when the table has templates, which have to come
+    /// after the columns they refer to. `None` when the columns can be transformed as they are
+    pub template_order: Option<Vec<usize>>,
+}
+
+impl CurrentTableTransforms {
+    pub fn new(
+        table_name: String,
+        table_transformers: TableTransformers,
+        salt: Option<String>,
+    ) -> Self {
+        let template_order = match table_transformers {
+            TableTransformers::ColumnTransformer(ref columns)
+                if columns
+                    .iter()
+                    .any(|column| template::is_template(&column.transformer)) =>
+            {
+                Some(
+                    template::transform_order(columns).unwrap_or_else(|circular| {
+                        panic!(
+                            "Templates in table: '{}' depend on each other: {:?}",
+                            table_name, circular
+                        )
+                    }),
+                )
+            }
+            _ => None,
+        };
+        CurrentTableTransforms {
+            table_name,
+            table_transformers,
+            salt,
+            template_order,
+        }
+    }
 }
 
 #[derive(Clone, Debug, PartialEq, Eq)]
@@ -19,45 +55,34 @@ pub enum TableTransformers {
 }
 
 pub fn parse(copy_row: &str, strategies: &Strategies) -> CurrentTableTransforms {
-    lazy_static! {
-        static ref RE: Regex = Regex::new(r"COPY (?P<table>.*) \((?P<columns>.*)\)").unwrap();
-    }
+    match table_and_columns(copy_row) {
+        Some((table_name, column_name_list)) => {
+            let table_transformers = table_strategy(strategies, &table_name, &column_name_list);
+            let salt = strategies.salt_for_table(&table_name).map(String::from);
 
-    if let Some(cap) = RE.captures(copy_row) {
-        let some_columns = capture_to_item(&cap, "columns");
-        let some_table = capture_to_item(&cap, "table");
-        match (some_table, some_columns) {
-            (Some(table), Some(unsplit_columns)) => {
-                let mut current_table =
-                    get_current_table_information(table, unsplit_columns, strategies);
-                current_table.salt = strategies.salt_for_table(table).map(String::from);
-                current_table
-            }
-            (_, _) => panic!("Invalid Copy row format: {:?}", copy_row),
+            CurrentTableTransforms::new(table_name, table_transformers, salt)
         }
-    } else {
-        panic!("Invalid Copy row format: {:?}", copy_row);
+        None => panic!("Invalid Copy row format: {:?}", copy_row),
     }
 }
 
-fn get_current_table_information(
-    table: &str,
-    unsplit_columns: &str,
-    strategies: &Strategies,
-) -> CurrentTableTransforms {
-    let table_name = sanitiser::dequote_column_or_table_name_data(table);
-    let column_name_list: Vec<String> = unsplit_columns
-        .split(", ")
-        .map(sanitiser::dequote_column_or_table_name_data)
-        .collect();
-    let table_transformers = table_strategy(strategies, &table_name, &column_name_list);
-    let salt = strategies.salt_for_table(&table_name).map(String::from);
-
-    CurrentTableTransforms {
-        table_name,
-        table_transformers,
-        salt,
+/// The dequoted table name and column names of a COPY row
+/// e.g. `COPY public.users (id, "first_name") FROM stdin;` => ("public.users", ["id", "first_name"])
+pub fn table_and_columns(copy_row: &str) -> Option<(String, Vec<String>)> {
+    lazy_static! {
+        static ref RE: Regex = Regex::new(r"COPY (?P<table>.*) \((?P<columns>.*)\)").unwrap();
     }
+
+    let cap = RE.captures(copy_row)?;
+    let table = capture_to_item(&cap, "table")?;
+    let unsplit_columns = capture_to_item(&cap, "columns")?;
+    Some((
+        sanitiser::dequote_column_or_table_name_data(table),
+        unsplit_columns
+            .split(", ")
+            .map(sanitiser::dequote_column_or_table_name_data)
+            .collect(),
+    ))
 }
 
 fn table_strategy(
@@ -129,11 +154,11 @@ mod tests {
             &strategies,
         );
 
-        let expected = CurrentTableTransforms {
-            table_name: "public.users".to_string(),
-            table_transformers: TableTransformers::ColumnTransformer(columns),
-            salt: Some(test_salt),
-        };
+        let expected = CurrentTableTransforms::new(
+            "public.users".to_string(),
+            TableTransformers::ColumnTransformer(columns),
+            Some(test_salt),
+        );
 
         assert_eq!(expected.table_name, parsed_copy_row.table_name);
         assert_eq!(
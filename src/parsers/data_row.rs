@@ -1,8 +1,105 @@
 use core::str::Split;
+use std::borrow::Cow;
+
 pub fn split(line: &str) -> Split<'_, char> {
     line.strip_suffix('\n').unwrap_or(line).split('\t')
 }
 
+/// Turns a value from a COPY block back into the text it represents
+/// e.g. the two characters `\n` become a real newline
+/// See https://www.postgresql.org/docs/current/sql-copy.html#id-1.9.3.55.9.2
+pub fn decode(value: &str) -> Cow<'_, str> {
+    if !value.contains('\\') {
+        return Cow::from(value);
+    }
+
+    let mut decoded = String::with_capacity(value.len());
+    let mut chars = value.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            decoded.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('b') => decoded.push('\u{8}'),
+            Some('f') => decoded.push('\u{c}'),
+            Some('n') => decoded.push('\n'),
+            Some('r') => decoded.push('\r'),
+            Some('t') => decoded.push('\t'),
+            Some('v') => decoded.push('\u{b}'),
+            Some('x') => {
+                let hex = take_while_max(&mut chars, 2, |c| c.is_ascii_hexdigit());
+                push_code(
+                    &mut decoded,
+                    u32::from_str_radix(&hex, 16).ok(),
+                    "\\x",
+                    &hex,
+                );
+            }
+            Some(first_digit @ '0'..='7') => {
+                let rest = take_while_max(&mut chars, 2, |c| ('0'..='7').contains(&c));
+                let octal = format!("{}{}", first_digit, rest);
+                push_code(
+                    &mut decoded,
+                    u32::from_str_radix(&octal, 8).ok(),
+                    "\\",
+                    &octal,
+                );
+            }
+            Some(other) => decoded.push(other),
+            None => decoded.push('\\'),
+        }
+    }
+    Cow::from(decoded)
+}
+
+/// The reverse of `decode`, escaping anything that would otherwise break the COPY row
+pub fn encode(value: &str) -> Cow<'_, str> {
+    if !value.contains(['\\', '\n', '\r', '\t']) {
+        return Cow::from(value);
+    }
+
+    let mut encoded = String::with_capacity(value.len() + 8);
+    for c in value.chars() {
+        match c {
+            '\\' => encoded.push_str("\\\\"),
+            '\n' => encoded.push_str("\\n"),
+            '\r' => encoded.push_str("\\r"),
+            '\t' => encoded.push_str("\\t"),
+            other => encoded.push(other),
+        }
+    }
+    Cow::from(encoded)
+}
+
+fn take_while_max<I>(
+    chars: &mut std::iter::Peekable<I>,
+    max: usize,
+    predicate: fn(char) -> bool,
+) -> String
+where
+    I: Iterator<Item = char>,
+{
+    let mut taken = String::new();
+    while taken.len() < max {
+        match chars.next_if(|c| predicate(*c)) {
+            Some(c) => taken.push(c),
+            None => break,
+        }
+    }
+    taken
+}
+
+fn push_code(decoded: &mut String, code: Option<u32>, prefix: &str, digits: &str) {
+    match code.and_then(char::from_u32) {
+        Some(c) => decoded.push(c),
+        None => {
+            decoded.push_str(prefix);
+            decoded.push_str(digits);
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -20,4 +117,25 @@ mod tests {
         let result: Vec<&str> = split(line).collect();
         assert_eq!(result, vec!["1", "2", "3", ""]);
     }
+
+    #[test]
+    fn can_decode_escape_sequences() {
+        let value = r"line one\nline two\twith a tab and a \\ backslash \101\x42";
+        assert_eq!(
+            decode(value),
+            "line one\nline two\twith a tab and a \\ backslash AB"
+        );
+    }
+
+    #[test]
+    fn encode_reverses_decode() {
+        let value = r"line one\nline two\twith a tab and a \\ backslash";
+        assert_eq!(encode(&decode(value)), value);
+    }
+
+    #[test]
+    fn leaves_plain_values_alone() {
+        assert!(matches!(decode("plain"), Cow::Borrowed("plain")));
+        assert!(matches!(encode("plain"), Cow::Borrowed("plain")));
+    }
 }
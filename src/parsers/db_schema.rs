@@ -1,6 +1,6 @@
 use crate::parsers::strategy_structs::*;
 use postgres::GenericClient;
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 
 pub fn parse<T>(connection: &mut T) -> HashSet<SimpleColumn>
 where
@@ -34,6 +34,64 @@ where
     columns_from_db
 }
 
+/// The columns that can't be null, i.e. that a `Null` transformer can't be used on
+pub fn non_nullable_columns<T>(connection: &mut T) -> HashSet<SimpleColumn>
+where
+    T: GenericClient,
+{
+    connection
+        .query(
+            "
+            SELECT
+                concat(c.table_schema, '.', c.table_name) as table_name,
+                column_name as column_name
+            FROM information_schema.columns c
+            WHERE c.table_schema NOT IN ('information_schema', 'pg_catalog')
+            AND c.is_nullable = 'NO';",
+            &[],
+        )
+        .unwrap()
+        .iter()
+        .map(|row| SimpleColumn {
+            table_name: row.get("table_name"),
+            column_name: row.get("column_name"),
+        })
+        .collect()
+}
+
+/// The Postgres type of every column e.g. `character varying` or `timestamp with time zone`, which
+/// rules can use to classify new columns
+pub fn column_types<T>(connection: &mut T) -> HashMap<SimpleColumn, String>
+where
+    T: GenericClient,
+{
+    connection
+        .query(
+            "
+            SELECT
+                concat(c.table_schema, '.', c.table_name) as table_name,
+                column_name as column_name,
+                data_type as data_type
+            FROM information_schema.columns c
+            INNER JOIN information_schema.tables t on c.table_name = t.table_name and c.table_schema = t.table_schema
+            WHERE c.table_schema NOT IN ('information_schema', 'pg_catalog')
+            AND table_type = 'BASE TABLE';",
+            &[],
+        )
+        .unwrap()
+        .iter()
+        .map(|row| {
+            (
+                SimpleColumn {
+                    table_name: row.get("table_name"),
+                    column_name: row.get("column_name"),
+                },
+                row.get("data_type"),
+            )
+        })
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -80,6 +138,38 @@ mod tests {
         });
     }
 
+    #[test]
+    fn can_read_non_nullable_columns() {
+        run_test(|connection| {
+            let result = non_nullable_columns(connection);
+            assert!(result.contains(&SimpleColumn {
+                table_name: "public.person".to_string(),
+                column_name: "first_name".to_string()
+            }));
+            assert!(!result.contains(&SimpleColumn {
+                table_name: "second_schema.location".to_string(),
+                column_name: "post_code".to_string()
+            }));
+        });
+    }
+
+    #[test]
+    fn can_read_column_types() {
+        run_test(|connection| {
+            let result = column_types(connection);
+            let column_type = |table_name: &str, column_name: &str| {
+                result
+                    .get(&SimpleColumn {
+                        table_name: table_name.to_string(),
+                        column_name: column_name.to_string(),
+                    })
+                    .map(String::as_str)
+            };
+            assert_eq!(column_type("public.person", "id"), Some("integer"));
+            assert_eq!(column_type("public.person", "first_name"), Some("text"));
+        });
+    }
+
     fn run_test<T>(test: T)
     where
         T: Fn(&mut Transaction),
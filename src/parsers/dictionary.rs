@@ -0,0 +1,267 @@
+use crate::parsers::transformer_args::DictionaryArgs;
+use lazy_static::lazy_static;
+use rand::distributions::{Distribution, WeightedIndex};
+use rand::seq::SliceRandom;
+use rand::Rng;
+use std::collections::HashMap;
+use std::fs;
+use std::sync::{Arc, Mutex};
+
+/// The values of a dictionary file, and how likely each one is to be picked
+#[derive(Debug)]
+pub struct Dictionary {
+    values: Vec<String>,
+    weights: Option<WeightedIndex<f64>>,
+}
+
+lazy_static! {
+    /// Dictionaries are loaded once, when the strategy file is loaded, and shared by the columns
+    /// which use the same one
+    static ref DICTIONARIES: Mutex<HashMap<String, Arc<Dictionary>>> = Mutex::new(HashMap::new());
+}
+
+impl Dictionary {
+    /// Loads a dictionary from either a file with one value per line, or (if the file ends in
+    /// `.csv`) a CSV file with a header row. For CSV files `column` is the column holding the values
+    /// (the first one by default) and `weight_column` an optional column of relative weights
+    pub fn from_file(
+        path: &str,
+        column: Option<&str>,
+        weight_column: Option<&str>,
+    ) -> Result<Self, String> {
+        let content = fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read dictionary file '{}': {}", path, e))?;
+
+        let dictionary = if path.to_lowercase().ends_with(".csv") {
+            Self::from_csv(path, &content, column, weight_column)?
+        } else {
+            if column.is_some() || weight_column.is_some() {
+                return Err(format!(
+                    "'column' and 'weight_column' can only be used with a .csv dictionary, not '{}'",
+                    path
+                ));
+            }
+            Dictionary {
+                values: content
+                    .lines()
+                    .map(str::trim)
+                    .filter(|line| !line.is_empty() && !line.starts_with('#'))
+                    .map(String::from)
+                    .collect(),
+                weights: None,
+            }
+        };
+
+        if dictionary.values.is_empty() {
+            Err(format!("Dictionary file '{}' has no values", path))
+        } else {
+            Ok(dictionary)
+        }
+    }
+
+    fn from_csv(
+        path: &str,
+        content: &str,
+        column: Option<&str>,
+        weight_column: Option<&str>,
+    ) -> Result<Self, String> {
+        let mut lines = content.lines().filter(|line| !line.trim().is_empty());
+        let Some(header) = lines.next() else {
+            return Err(format!("Dictionary file '{}' has no values", path));
+        };
+        let header = split_csv_line(header);
+        let find_column = |name: &str| {
+            header
+                .iter()
+                .position(|heading| heading == name)
+                .ok_or_else(|| {
+                    format!(
+                        "Dictionary file '{}' has no column '{}', it has: {}",
+                        path,
+                        name,
+                        header.join(", ")
+                    )
+                })
+        };
+        let value_index = column.map(find_column).transpose()?.unwrap_or(0);
+        let weight_index = weight_column.map(find_column).transpose()?;
+
+        let mut values = Vec::new();
+        let mut weights = Vec::new();
+        for (line_number, line) in lines.enumerate() {
+            let fields = split_csv_line(line);
+            let field = |index: usize| {
+                fields.get(index).ok_or_else(|| {
+                    format!(
+                        "Line {} of dictionary file '{}' is missing column {}",
+                        line_number + 2,
+                        path,
+                        header[index]
+                    )
+                })
+            };
+            values.push(field(value_index)?.clone());
+            if let Some(weight_index) = weight_index {
+                let weight = field(weight_index)?;
+                weights.push(
+                    weight
+                        .trim()
+                        .parse::<f64>()
+                        .ok()
+                        .filter(|weight| weight.is_finite() && *weight >= 0.0)
+                        .ok_or_else(|| {
+                            format!(
+                                "Line {} of dictionary file '{}' has an invalid weight '{}'",
+                                line_number + 2,
+                                path,
+                                weight
+                            )
+                        })?,
+                );
+            }
+        }
+
+        let weights = if weight_index.is_some() && !values.is_empty() {
+            Some(WeightedIndex::new(&weights).map_err(|_| {
+                format!(
+                    "The weights in dictionary file '{}' must not all be 0",
+                    path
+                )
+            })?)
+        } else {
+            None
+        };
+        Ok(Dictionary { values, weights })
+    }
+
+    pub fn pick<R: Rng + ?Sized>(&self, rng: &mut R) -> &str {
+        match &self.weights {
+            Some(weights) => &self.values[weights.sample(rng)],
+            None => self.values.choose(rng).unwrap(),
+        }
+    }
+}
+
+/// Splits a line of CSV, where fields can be quoted with `"` (and quotes escaped with `""`).
+/// Quoted fields can't span lines
+fn split_csv_line(line: &str) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if in_quotes && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => in_quotes = !in_quotes,
+            ',' if !in_quotes => fields.push(std::mem::take(&mut field)),
+            c => field.push(c),
+        }
+    }
+    fields.push(field);
+    fields
+        .iter()
+        .map(|field| field.trim().to_string())
+        .collect()
+}
+
+/// The dictionary for the `file`, `column` and `weight_column` args, loading it the first time
+pub fn load(args: &DictionaryArgs) -> Result<Arc<Dictionary>, String> {
+    let path = &args.file;
+    let column = args.column.as_deref();
+    let weight_column = args.weight_column.as_deref();
+    let key = format!(
+        "{}\t{}\t{}",
+        path,
+        column.unwrap_or_default(),
+        weight_column.unwrap_or_default()
+    );
+
+    let mut dictionaries = DICTIONARIES.lock().unwrap();
+    if let Some(dictionary) = dictionaries.get(&key) {
+        return Ok(Arc::clone(dictionary));
+    }
+    let dictionary = Arc::new(Dictionary::from_file(path, column, weight_column)?);
+    dictionaries.insert(key, Arc::clone(&dictionary));
+    Ok(dictionary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::SmallRng;
+    use rand::SeedableRng;
+    use serde_json::json;
+    use std::io::Write;
+    use tempfile::NamedTempFile;
+
+    fn dictionary_file(suffix: &str, content: &str) -> NamedTempFile {
+        let mut file = tempfile::Builder::new().suffix(suffix).tempfile().unwrap();
+        file.write_all(content.as_bytes()).unwrap();
+        file
+    }
+
+    fn path(file: &NamedTempFile) -> &str {
+        file.path().to_str().unwrap()
+    }
+
+    #[test]
+    fn loads_one_value_per_line() {
+        let file = dictionary_file(
+            ".txt",
+            "# job titles\nSoftware Engineer\n\n  Data Analyst  \n",
+        );
+        let dictionary = Dictionary::from_file(path(&file), None, None).unwrap();
+        assert_eq!(dictionary.values, vec!["Software Engineer", "Data Analyst"]);
+    }
+
+    #[test]
+    fn loads_a_column_of_a_csv_with_weights() {
+        let file = dictionary_file(
+            ".csv",
+            "standard,level,weight\n\"Software Developer, Level 4\",4,1\nData Analyst,4,0\n",
+        );
+        let dictionary =
+            Dictionary::from_file(path(&file), Some("standard"), Some("weight")).unwrap();
+        assert_eq!(
+            dictionary.values,
+            vec!["Software Developer, Level 4", "Data Analyst"]
+        );
+
+        let mut rng = SmallRng::seed_from_u64(1);
+        for _ in 0..100 {
+            assert_eq!(dictionary.pick(&mut rng), "Software Developer, Level 4");
+        }
+    }
+
+    #[test]
+    fn rejects_empty_and_missing_files() {
+        let empty = dictionary_file(".txt", "\n# nothing here\n");
+        assert!(Dictionary::from_file(path(&empty), None, None)
+            .unwrap_err()
+            .ends_with("has no values"));
+        assert!(Dictionary::from_file("no_such_dictionary.txt", None, None)
+            .unwrap_err()
+            .starts_with("Failed to read dictionary file 'no_such_dictionary.txt'"));
+    }
+
+    #[test]
+    fn rejects_unknown_columns_and_bad_weights() {
+        let file = dictionary_file(".csv", "name,weight\nAcme,lots\n");
+        assert!(Dictionary::from_file(path(&file), Some("title"), None)
+            .unwrap_err()
+            .contains("has no column 'title', it has: name, weight"));
+        assert!(Dictionary::from_file(path(&file), None, Some("weight"))
+            .unwrap_err()
+            .ends_with("has an invalid weight 'lots'"));
+    }
+
+    #[test]
+    fn loads_each_dictionary_once() {
+        let file = dictionary_file(".txt", "Software Engineer\n");
+        let args: DictionaryArgs = serde_json::from_value(json!({"file": path(&file)})).unwrap();
+        assert!(Arc::ptr_eq(&load(&args).unwrap(), &load(&args).unwrap()));
+    }
+}
@@ -0,0 +1,263 @@
+use crate::parsers::transformer;
+use crate::parsers::transformer_args;
+use fake::faker::internet::en::FreeEmail;
+use fake::Fake;
+use schemars::JsonSchema;
+use serde::de::Error;
+use serde::{Deserialize, Deserializer};
+use sha2::{Digest, Sha256};
+
+/// How the domain and local part of a fake email are built. By default both come from a random
+/// free email address
+#[derive(Clone, Debug, Default, Deserialize, JsonSchema, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct EmailArgs {
+    /// Keep the original domain
+    #[serde(default, deserialize_with = "transformer_args::flag")]
+    #[schemars(schema_with = "transformer_args::flag_schema")]
+    pub keep_domain: bool,
+    /// A fixed domain to use instead of a random one e.g. `example.test`
+    #[serde(default, deserialize_with = "domain")]
+    pub domain: Option<String>,
+    /// Domains which are kept, all others are replaced
+    #[serde(default, deserialize_with = "keep_domains")]
+    pub keep_domains: Vec<String>,
+    /// Keep the `+tag` of the original local part
+    #[serde(default, deserialize_with = "transformer_args::flag")]
+    #[schemars(schema_with = "transformer_args::flag_schema")]
+    pub keep_plus_addressing: bool,
+    /// The longest the email can be, the local part is shortened to fit
+    #[serde(default, deserialize_with = "max_length")]
+    #[schemars(schema_with = "transformer_args::number_schema")]
+    pub max_length: Option<usize>,
+    /// Accepted as the README has always shown it, but the hash prefix already makes the fake
+    /// emails unique
+    #[serde(
+        default,
+        rename = "unique",
+        deserialize_with = "transformer_args::flag"
+    )]
+    #[schemars(schema_with = "transformer_args::flag_schema")]
+    _unique: bool,
+}
+
+/// The hex prefix made from the original email, which keeps the fake emails unique
+const HASH_PREFIX_LENGTH: usize = 12;
+
+pub fn domain<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<String>, D::Error> {
+    let domain = String::deserialize(deserializer)?;
+    if domain.is_empty() || domain.contains('@') {
+        Err(D::Error::custom(format!(
+            "'domain' must be a domain name, not '{}'",
+            domain
+        )))
+    } else {
+        Ok(Some(domain))
+    }
+}
+
+pub fn keep_domains<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<String>, D::Error> {
+    Vec::<String>::deserialize(deserializer)
+        .map(|domains| domains.iter().map(|domain| domain.to_lowercase()).collect())
+        .map_err(|_| D::Error::custom("'keep_domains' must be a list of domains"))
+}
+
+pub fn max_length<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<usize>, D::Error> {
+    let max_length: usize = transformer_args::number(deserializer)?;
+    if max_length > HASH_PREFIX_LENGTH + 1 {
+        Ok(Some(max_length))
+    } else {
+        Err(D::Error::custom(format!(
+            "'max_length' must be a number greater than {}, not {}",
+            HASH_PREFIX_LENGTH + 1,
+            max_length
+        )))
+    }
+}
+
+/// A fake email of the form `<hash-prefix>-<random local part>@<domain>`. The same email always
+/// gives the same fake one (for a given salt)
+pub fn fake(value: &str, args: &EmailArgs, global_salt: Option<&str>) -> String {
+    let mut seeded_rng = transformer::get_faker_rng(value, None, global_salt);
+    let new_email = FreeEmail().fake_with_rng::<String, _>(&mut seeded_rng);
+    let (fake_local, fake_domain) = new_email.split_once('@').unwrap_or((&new_email, ""));
+
+    let mut hasher = Sha256::new();
+    hasher.update(value.as_bytes());
+    let hash = hasher.finalize();
+    let prefix = base16::encode_lower(&hash[..HASH_PREFIX_LENGTH / 2]);
+
+    let (original_local, original_domain) = match value.rsplit_once('@') {
+        Some((local, domain)) => (local, Some(domain)),
+        None => (value, None),
+    };
+    let tag = original_local
+        .split_once('+')
+        .filter(|_| args.keep_plus_addressing)
+        .map(|(_, tag)| format!("+{}", tag))
+        .unwrap_or_default();
+
+    let domain = match original_domain {
+        Some(original)
+            if args.keep_domain || args.keep_domains.contains(&original.to_lowercase()) =>
+        {
+            original
+        }
+        _ => args.domain.as_deref().unwrap_or(fake_domain),
+    };
+
+    let local = format!("{}-{}", prefix, fake_local);
+    match args.max_length {
+        Some(max_length) => fit(&local, &tag, domain, max_length),
+        None => format!("{}{}@{}", local, tag, domain),
+    }
+}
+
+/// Shortens the local part to fit the email into `max_length`, dropping the tag if there isn't
+/// room for it and the hash prefix. A kept or random domain too long for even the hash prefix is
+/// shortened too, so the email is never longer than `max_length`
+fn fit(local: &str, tag: &str, domain: &str, max_length: usize) -> String {
+    let domain: String = domain
+        .chars()
+        .take(max_length - HASH_PREFIX_LENGTH - 1)
+        .collect();
+    let domain = domain.trim_end_matches('.');
+    let available = max_length - domain.chars().count() - 1;
+    let tag = if HASH_PREFIX_LENGTH + tag.chars().count() <= available {
+        tag
+    } else {
+        ""
+    };
+    let local: String = local
+        .chars()
+        .take(available - tag.chars().count())
+        .collect();
+    format!("{}{}@{}", local, tag, domain)
+}
+
+/// A fixed `domain` is known when the strategy file is loaded, so a `max_length` too short for it
+/// and the hash prefix is reported then
+pub fn validate(args: &EmailArgs) -> Result<(), String> {
+    match (&args.domain, args.max_length) {
+        (Some(domain), Some(max_length))
+            if HASH_PREFIX_LENGTH + 1 + domain.chars().count() > max_length =>
+        {
+            Err(format!(
+                "'max_length' of {} is too short for the {} character hash prefix and '@{}'",
+                max_length, HASH_PREFIX_LENGTH, domain
+            ))
+        }
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn email_args(args: serde_json::Value) -> EmailArgs {
+        args_from(args).unwrap()
+    }
+
+    fn args_from(args: serde_json::Value) -> Result<EmailArgs, String> {
+        serde_json::from_value(args).map_err(|e| e.to_string())
+    }
+
+    #[test]
+    fn the_default_is_a_free_email_with_a_hash_prefix() {
+        let email = fake("jo@multiverse.io", &EmailArgs::default(), None);
+        let (local, domain) = email.split_once('@').unwrap();
+        assert!(local.len() > HASH_PREFIX_LENGTH);
+        assert_eq!(&local[HASH_PREFIX_LENGTH..HASH_PREFIX_LENGTH + 1], "-");
+        assert_ne!(domain, "multiverse.io");
+    }
+
+    #[test]
+    fn can_keep_or_replace_the_domain() {
+        let kept = fake(
+            "jo@multiverse.io",
+            &email_args(json!({"keep_domain": true})),
+            None,
+        );
+        assert!(kept.ends_with("@multiverse.io"));
+
+        let fixed = fake(
+            "jo@multiverse.io",
+            &email_args(json!({"domain": "example.test"})),
+            None,
+        );
+        assert!(fixed.ends_with("@example.test"));
+    }
+
+    #[test]
+    fn only_keeps_allowed_domains() {
+        let args = email_args(json!({"keep_domains": ["Multiverse.io"], "domain": "example.test"}));
+        assert!(fake("jo@multiverse.io", &args, None).ends_with("@multiverse.io"));
+        assert!(fake("jo@gmail.com", &args, None).ends_with("@example.test"));
+    }
+
+    #[test]
+    fn can_keep_plus_addressing() {
+        let args = email_args(json!({"keep_plus_addressing": true}));
+        let email = fake("jo+staging@multiverse.io", &args, None);
+        assert!(email.split_once('@').unwrap().0.ends_with("+staging"));
+        assert!(!fake("jo+staging@multiverse.io", &EmailArgs::default(), None).contains('+'));
+    }
+
+    #[test]
+    fn shortens_the_local_part_to_fit_the_max_length() {
+        let args = email_args(json!({"max_length": 30, "domain": "example.test"}));
+        let email = fake("jo@multiverse.io", &args, None);
+        assert_eq!(email.chars().count(), 30);
+        assert!(email.ends_with("@example.test"));
+    }
+
+    #[test]
+    fn never_goes_over_the_max_length() {
+        let args = email_args(
+            json!({"max_length": 20, "keep_domain": true, "keep_plus_addressing": true}),
+        );
+        let email = fake(
+            "jo+staging@mail.a-very-long-company-name.co.uk",
+            &args,
+            None,
+        );
+        assert_eq!(email.chars().count(), 20, "{}", email);
+        assert!(email.contains("@mail.a"), "{}", email);
+        assert!(!email.contains('+'), "{}", email);
+
+        let args = email_args(
+            json!({"max_length": 30, "keep_domain": true, "keep_plus_addressing": true}),
+        );
+        let email = fake("jo+x@multiverse.io", &args, None);
+        assert!(email.ends_with("+x@multiverse.io"), "{}", email);
+        assert_eq!(email.chars().count(), 30, "{}", email);
+    }
+
+    #[test]
+    fn rejects_a_max_length_too_short_for_the_fixed_domain() {
+        let args = email_args(json!({"max_length": 20, "domain": "example.test"}));
+        assert_eq!(
+            validate(&args).unwrap_err(),
+            "'max_length' of 20 is too short for the 12 character hash prefix and '@example.test'"
+        );
+        assert!(validate(&email_args(
+            json!({"max_length": 25, "domain": "example.test"})
+        ))
+        .is_ok());
+    }
+
+    #[test]
+    fn rejects_invalid_args() {
+        assert_eq!(
+            args_from(json!({"domain": "jo@example.test"})).unwrap_err(),
+            "'domain' must be a domain name, not 'jo@example.test'"
+        );
+        assert_eq!(
+            args_from(json!({"keep_domains": "multiverse.io"})).unwrap_err(),
+            "'keep_domains' must be a list of domains"
+        );
+        assert!(args_from(json!({"max_length": 5})).is_err());
+    }
+}
@@ -0,0 +1,234 @@
+use crate::parsers::pii_patterns;
+use rand::Rng;
+
+/// The digits of the issuer identification number, which identifies the card's bank
+const BIN_LENGTH: usize = 6;
+const LAST_DIGITS_LENGTH: usize = 4;
+const DEFAULT_CARD_NUMBER: &str = "4000 0000 0000 0000";
+const DEFAULT_IBAN: &str = "GB00 AAAA 0000 0000 0000 00";
+
+/// A Luhn valid card number with the same number of digits as `value`, optionally keeping its BIN
+/// and last 4 digits (in which case another digit is changed to make the check digit work)
+pub fn card_number<R: Rng + ?Sized>(
+    value: &str,
+    keep_bin: bool,
+    keep_last_4: bool,
+    rng: &mut R,
+) -> String {
+    let template = if count_digits(value) < BIN_LENGTH + LAST_DIGITS_LENGTH + 1 {
+        DEFAULT_CARD_NUMBER
+    } else {
+        value
+    };
+    let original: Vec<u32> = template.chars().filter_map(|c| c.to_digit(10)).collect();
+    let length = original.len();
+    let kept_start = if keep_bin { BIN_LENGTH } else { 1 };
+    let kept_end = if keep_last_4 {
+        length - LAST_DIGITS_LENGTH
+    } else {
+        length
+    };
+
+    let mut digits: Vec<u32> = original
+        .iter()
+        .enumerate()
+        .map(|(i, digit)| {
+            if i < kept_start || i >= kept_end {
+                *digit
+            } else {
+                rng.gen_range(0..10)
+            }
+        })
+        .collect();
+
+    // The check digit is the last digit, unless that's being kept
+    let check_position = kept_end - 1;
+    digits[check_position] = (0..10)
+        .find(|candidate| {
+            let mut candidate_digits = digits.clone();
+            candidate_digits[check_position] = *candidate;
+            pii_patterns::is_luhn_valid(&to_string(&candidate_digits))
+        })
+        .unwrap();
+
+    replace_digits(template, &to_string(&digits))
+}
+
+/// An IBAN with valid check digits for the same country and of the same length as `value`, where
+/// each letter or digit of the account details is replaced with a random one of the same kind
+pub fn iban<R: Rng + ?Sized>(value: &str, rng: &mut R) -> String {
+    let compact: String = value
+        .chars()
+        .filter(char::is_ascii_alphanumeric)
+        .collect::<String>()
+        .to_uppercase();
+    let looks_like_iban =
+        compact.len() > 4 && compact[..2].chars().all(|c| c.is_ascii_alphabetic());
+    let template = if looks_like_iban { value } else { DEFAULT_IBAN };
+    let compact = if looks_like_iban {
+        compact
+    } else {
+        DEFAULT_IBAN.replace(' ', "")
+    };
+
+    let country = &compact[..2];
+    let bban: String = compact[4..]
+        .chars()
+        .map(|c| {
+            if c.is_ascii_digit() {
+                char::from(b'0' + rng.gen_range(0..10))
+            } else {
+                char::from(b'A' + rng.gen_range(0..26))
+            }
+        })
+        .collect();
+    let remainder =
+        pii_patterns::iban_remainder(&format!("{}{}00", bban, country)).unwrap_or_default();
+    let check_digits = 98 - remainder;
+
+    replace_alphanumerics(template, &format!("{}{:02}{}", country, check_digits, bban))
+}
+
+/// Random digits in place of the digits of `value`, keeping everything else, e.g. the dashes of a
+/// sort code or the `*`s of a masked account number
+pub fn digits<R: Rng + ?Sized>(value: &str, default: &str, rng: &mut R) -> String {
+    let template = if count_digits(value) == 0 {
+        default
+    } else {
+        value
+    };
+    template
+        .chars()
+        .map(|c| {
+            if c.is_ascii_digit() {
+                char::from(b'0' + rng.gen_range(0..10))
+            } else {
+                c
+            }
+        })
+        .collect()
+}
+
+fn count_digits(value: &str) -> usize {
+    value.chars().filter(char::is_ascii_digit).count()
+}
+
+fn to_string(digits: &[u32]) -> String {
+    digits
+        .iter()
+        .map(|digit| char::from_digit(*digit, 10).unwrap())
+        .collect()
+}
+
+/// Puts the digits of `replacement` in place of the digits of `original`, leaving any text around
+/// them (e.g. `Visa 4111 ...`) as it is
+fn replace_digits(original: &str, replacement: &str) -> String {
+    let mut replacements = replacement.chars();
+    original
+        .chars()
+        .map(|c| {
+            if c.is_ascii_digit() {
+                replacements.next().unwrap()
+            } else {
+                c
+            }
+        })
+        .collect()
+}
+
+/// Puts the characters of `replacement` in place of the letters and digits of `original`,
+/// keeping the case of letters
+fn replace_alphanumerics(original: &str, replacement: &str) -> String {
+    let mut replacements = replacement.chars();
+    original
+        .chars()
+        .map(|c| match c {
+            c if c.is_ascii_lowercase() => replacements.next().unwrap().to_ascii_lowercase(),
+            c if c.is_ascii_alphanumeric() => replacements.next().unwrap(),
+            c => c,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::SmallRng;
+    use rand::SeedableRng;
+
+    fn rng() -> SmallRng {
+        SmallRng::seed_from_u64(1)
+    }
+
+    #[test]
+    fn card_numbers_are_luhn_valid_and_keep_their_formatting() {
+        for seed in 0..50 {
+            let card = card_number(
+                "4111-1111-1111-1111",
+                false,
+                false,
+                &mut SmallRng::seed_from_u64(seed),
+            );
+            assert!(pii_patterns::is_luhn_valid(&card), "{}", card);
+            assert!(card.starts_with('4'));
+            assert_eq!(card.len(), 19);
+            assert_eq!(card.matches('-').count(), 3);
+        }
+    }
+
+    #[test]
+    fn card_numbers_can_keep_the_bin_and_last_4() {
+        for seed in 0..50 {
+            let card = card_number(
+                "5500 0055 5555 5559",
+                true,
+                true,
+                &mut SmallRng::seed_from_u64(seed),
+            );
+            assert!(pii_patterns::is_luhn_valid(&card), "{}", card);
+            assert!(card.starts_with("5500 00"));
+            assert!(card.ends_with(" 5559"));
+        }
+    }
+
+    #[test]
+    fn card_numbers_keep_any_text_around_them() {
+        for seed in 0..50 {
+            let card = card_number(
+                "Visa 4111 1111 1111 1111",
+                false,
+                false,
+                &mut SmallRng::seed_from_u64(seed),
+            );
+            assert!(card.starts_with("Visa 4"), "{}", card);
+            assert!(pii_patterns::is_luhn_valid(&card[5..]), "{}", card);
+            assert_eq!(card.len(), 24);
+        }
+    }
+
+    #[test]
+    fn ibans_have_valid_check_digits_for_the_same_country() {
+        for seed in 0..50 {
+            let iban = iban(
+                "DE89 3704 0044 0532 0130 00",
+                &mut SmallRng::seed_from_u64(seed),
+            );
+            assert!(pii_patterns::is_valid_iban(&iban), "{}", iban);
+            assert!(iban.starts_with("DE"));
+            assert_eq!(iban.len(), 27);
+        }
+        let gb = iban("GB29NWBK60161331926819", &mut rng());
+        assert!(pii_patterns::is_valid_iban(&gb));
+        assert!(gb[4..8].chars().all(|c| c.is_ascii_uppercase()));
+    }
+
+    #[test]
+    fn sort_codes_and_account_numbers_keep_their_formatting() {
+        let sort_code = digits("60-16-13", "00-00-00", &mut rng());
+        assert_eq!(sort_code.len(), 8);
+        assert_eq!(&sort_code[2..3], "-");
+        let masked = digits("****1234", "00000000", &mut rng());
+        assert!(masked.starts_with("****"));
+        assert_eq!(digits("", "00-00-00", &mut rng()).len(), 8);
+    }
+}
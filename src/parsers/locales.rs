@@ -0,0 +1,417 @@
+use crate::parsers::strategy_structs::Transformer;
+use fake::faker::address::raw::{BuildingNumber, CityName, StateName, StreetName};
+use fake::faker::company::raw::CompanyName;
+use fake::faker::name::raw::{FirstName, LastName};
+use fake::faker::phone_number::raw::PhoneNumber;
+use fake::locales::{Data, AR_SA, EN, FR_FR, JA_JP, PT_BR, ZH_CN, ZH_TW};
+use fake::Fake;
+use rand::seq::SliceRandom;
+use rand::Rng;
+use schemars::JsonSchema;
+use serde::Deserialize;
+use serde_json::{Map, Value};
+
+/// The locales fake data can be generated in. `En` (US English) is the default and is what the
+/// anonymiser has always used
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, JsonSchema)]
+#[serde(try_from = "String")]
+pub enum Locale {
+    En,
+    EnGb,
+    DeDe,
+    FrFr,
+    PtBr,
+    JaJp,
+    ZhCn,
+    ZhTw,
+    ArSa,
+}
+
+const LOCALE_NAMES: [(&str, Locale); 9] = [
+    ("en", Locale::En),
+    ("en_gb", Locale::EnGb),
+    ("de_de", Locale::DeDe),
+    ("fr_fr", Locale::FrFr),
+    ("pt_br", Locale::PtBr),
+    ("ja_jp", Locale::JaJp),
+    ("zh_cn", Locale::ZhCn),
+    ("zh_tw", Locale::ZhTw),
+    ("ar_sa", Locale::ArSa),
+];
+
+impl Locale {
+    /// Parses a locale name, ignoring case and accepting `-` as well as `_` e.g. `de-DE`
+    pub fn parse(name: &str) -> Result<Locale, String> {
+        let normalised = name.to_lowercase().replace('-', "_");
+        let normalised = if normalised == "en_us" {
+            "en".to_string()
+        } else {
+            normalised
+        };
+        LOCALE_NAMES
+            .iter()
+            .find(|(locale_name, _)| *locale_name == normalised)
+            .map(|(_, locale)| *locale)
+            .ok_or_else(|| {
+                format!(
+                    "unknown locale '{}', expected one of: {}",
+                    name,
+                    LOCALE_NAMES
+                        .iter()
+                        .map(|(locale_name, _)| *locale_name)
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                )
+            })
+    }
+
+    /// The name the locale is given in strategy files e.g. `de_de`
+    pub fn name(self) -> &'static str {
+        LOCALE_NAMES
+            .iter()
+            .find(|(_, locale)| *locale == self)
+            .map(|(name, _)| *name)
+            .unwrap()
+    }
+}
+
+impl TryFrom<String> for Locale {
+    type Error = String;
+
+    fn try_from(name: String) -> Result<Self, Self::Error> {
+        Locale::parse(&name)
+    }
+}
+
+const LOCALE_AWARE_TRANSFORMERS: [&str; 12] = [
+    "FakeCity",
+    "FakeCompanyName",
+    "FakeEmailOrPhone",
+    "FakeFirstName",
+    "FakeFullAddress",
+    "FakeFullName",
+    "FakeLastName",
+    "FakePhoneNumber",
+    "FakeState",
+    "FakeStreetAddress",
+    "Redact",
+    "Template",
+];
+
+/// Sets the `locale` arg of every locale aware transformer (including ones nested in pipelines
+/// and conditionals) which doesn't already have one, so the global locale is used unless a column
+/// says otherwise
+pub fn with_default_locale(transformer: Transformer, locale: &str) -> Transformer {
+    let mut value = serde_json::to_value(&transformer).unwrap();
+    set_default_locale(&mut value, locale);
+    serde_json::from_value(value).unwrap_or(transformer)
+}
+
+fn set_default_locale(value: &mut Value, locale: &str) {
+    match value {
+        Value::Array(items) => items
+            .iter_mut()
+            .for_each(|item| set_default_locale(item, locale)),
+        Value::Object(object) => {
+            let is_locale_aware = object
+                .get("name")
+                .and_then(Value::as_str)
+                .is_some_and(|name| LOCALE_AWARE_TRANSFORMERS.contains(&name));
+            if is_locale_aware {
+                let args = object
+                    .entry("args")
+                    .or_insert_with(|| Value::Object(Map::new()));
+                if args.is_null() {
+                    *args = Value::Object(Map::new());
+                }
+                if let Value::Object(args) = args {
+                    args.entry("locale")
+                        .or_insert_with(|| Value::String(locale.to_string()));
+                }
+            }
+            object
+                .values_mut()
+                .for_each(|nested| set_default_locale(nested, locale));
+        }
+        _ => (),
+    }
+}
+
+macro_rules! fake_in {
+    ($locale:expr, $faker:ident, $rng:expr) => {
+        match $locale {
+            Locale::En => $faker(EN).fake_with_rng::<String, _>($rng),
+            Locale::EnGb => $faker(EnGb).fake_with_rng::<String, _>($rng),
+            Locale::DeDe => $faker(DeDe).fake_with_rng::<String, _>($rng),
+            Locale::FrFr => $faker(FrFr).fake_with_rng::<String, _>($rng),
+            Locale::PtBr => $faker(PT_BR).fake_with_rng::<String, _>($rng),
+            Locale::JaJp => $faker(JA_JP).fake_with_rng::<String, _>($rng),
+            Locale::ZhCn => $faker(ZH_CN).fake_with_rng::<String, _>($rng),
+            Locale::ZhTw => $faker(ZH_TW).fake_with_rng::<String, _>($rng),
+            Locale::ArSa => $faker(AR_SA).fake_with_rng::<String, _>($rng),
+        }
+    };
+}
+
+pub fn first_name<R: Rng + ?Sized>(locale: Locale, rng: &mut R) -> String {
+    fake_in!(locale, FirstName, rng)
+}
+
+pub fn last_name<R: Rng + ?Sized>(locale: Locale, rng: &mut R) -> String {
+    fake_in!(locale, LastName, rng)
+}
+
+pub fn company_name<R: Rng + ?Sized>(locale: Locale, rng: &mut R) -> String {
+    fake_in!(locale, CompanyName, rng)
+}
+
+pub fn state<R: Rng + ?Sized>(locale: Locale, rng: &mut R) -> String {
+    fake_in!(locale, StateName, rng)
+}
+
+pub fn phone_number<R: Rng + ?Sized>(locale: Locale, rng: &mut R) -> String {
+    fake_in!(locale, PhoneNumber, rng)
+}
+
+/// The locales we have our own data for use a list of real cities, rather than faker's made up
+/// ones which are built from american surnames
+pub fn city<R: Rng + ?Sized>(locale: Locale, rng: &mut R) -> String {
+    let cities = match locale {
+        Locale::EnGb => EN_GB_CITIES,
+        Locale::DeDe => DE_DE_CITIES,
+        Locale::FrFr => FR_FR_CITIES,
+        _ => return fake_in!(locale, CityName, rng),
+    };
+    cities.choose(rng).unwrap().to_string()
+}
+
+/// A building number and street, in the order that is usual for the locale
+pub fn street_address<R: Rng + ?Sized>(locale: Locale, rng: &mut R) -> String {
+    let building = fake_in!(locale, BuildingNumber, rng);
+    let street = fake_in!(locale, StreetName, rng);
+    match locale {
+        Locale::DeDe => format!("{} {}", street, building),
+        _ => format!("{} {}", building, street),
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct EnGb;
+
+#[derive(Clone, Copy)]
+pub struct DeDe;
+
+#[derive(Clone, Copy)]
+pub struct FrFr;
+
+#[rustfmt::skip]
+impl Data for EnGb {
+    const NAME_FIRST_NAME: &'static [&'static str] = &["Oliver", "George", "Harry", "Jack", "Jacob", "Noah", "Charlie", "Thomas", "Oscar", "William", "James", "Alfie", "Joshua", "Freddie", "Archie", "Olivia", "Amelia", "Emily", "Isla", "Ava", "Jessica", "Isabella", "Lily", "Sophie", "Grace", "Mia", "Poppy", "Ella", "Evie", "Charlotte"];
+    const NAME_LAST_NAME: &'static [&'static str] = &["Smith", "Jones", "Taylor", "Brown", "Williams", "Wilson", "Johnson", "Davies", "Robinson", "Wright", "Thompson", "Evans", "Walker", "White", "Roberts", "Green", "Hall", "Wood", "Jackson", "Clarke", "Patel", "Khan", "Lewis", "Hughes", "Edwards", "Turner", "Hill", "Cooper", "Ward", "Morris"];
+    const ADDRESS_STATE: &'static [&'static str] = &["Bedfordshire", "Berkshire", "Cambridgeshire", "Cheshire", "Cornwall", "Cumbria", "Derbyshire", "Devon", "Dorset", "Durham", "Essex", "Gloucestershire", "Hampshire", "Hertfordshire", "Kent", "Lancashire", "Leicestershire", "Lincolnshire", "Norfolk", "Northumberland", "Oxfordshire", "Shropshire", "Somerset", "Suffolk", "Surrey", "Warwickshire", "Wiltshire", "Yorkshire"];
+    const ADDRESS_STREET_SUFFIX: &'static [&'static str] = &["Road", "Street", "Lane", "Avenue", "Close", "Drive", "Gardens", "Grove", "Place", "Way", "Crescent", "Terrace", "Mews", "Row"];
+    const ADDRESS_BUILDING_NUMBER_FORMATS: &'static [&'static str] = &["#", "##", "###"];
+    const COMPANY_SUFFIX: &'static [&'static str] = &["Ltd", "PLC", "LLP", "& Sons", "Group"];
+    const COMPANY_NAME_TPLS: &'static [&'static str] = &["{Name_1} {Suffix}", "{Name_1} and {Name_2} {Suffix}"];
+    // https://www.ofcom.org.uk/phones-telecoms-and-internet/information-for-industry/numbering/numbers-for-drama
+    const PHONE_NUMBER_FORMATS: &'static [&'static str] = &["+44 20 7946 0###", "+44 113 496 0###", "+44 161 496 0###", "+44 7700 900###"];
+}
+
+const EN_GB_CITIES: &[&str] = &[
+    "London",
+    "Birmingham",
+    "Manchester",
+    "Leeds",
+    "Glasgow",
+    "Liverpool",
+    "Bristol",
+    "Sheffield",
+    "Edinburgh",
+    "Cardiff",
+    "Leicester",
+    "Nottingham",
+    "Newcastle",
+    "Brighton",
+    "Southampton",
+    "Plymouth",
+    "Reading",
+    "York",
+    "Oxford",
+    "Cambridge",
+    "Norwich",
+    "Exeter",
+    "Belfast",
+];
+
+#[rustfmt::skip]
+impl Data for DeDe {
+    const NAME_FIRST_NAME: &'static [&'static str] = &["Lukas", "Leon", "Finn", "Jonas", "Paul", "Felix", "Elias", "Maximilian", "Noah", "Ben", "Luis", "Henry", "Emil", "Anton", "Moritz", "Emma", "Mia", "Hannah", "Sofia", "Lena", "Anna", "Marie", "Lea", "Clara", "Johanna", "Luisa", "Ella", "Lina", "Frieda", "Greta"];
+    const NAME_LAST_NAME: &'static [&'static str] = &["Müller", "Schmidt", "Schneider", "Fischer", "Weber", "Meyer", "Wagner", "Becker", "Schulz", "Hoffmann", "Schäfer", "Koch", "Bauer", "Richter", "Klein", "Wolf", "Schröder", "Neumann", "Schwarz", "Zimmermann", "Braun", "Krüger", "Hofmann", "Hartmann", "Lange", "Werner", "Krause", "Lehmann", "Köhler", "Maier"];
+    const ADDRESS_STATE: &'static [&'static str] = &["Baden-Württemberg", "Bayern", "Berlin", "Brandenburg", "Bremen", "Hamburg", "Hessen", "Mecklenburg-Vorpommern", "Niedersachsen", "Nordrhein-Westfalen", "Rheinland-Pfalz", "Saarland", "Sachsen", "Sachsen-Anhalt", "Schleswig-Holstein", "Thüringen"];
+    const ADDRESS_STREET_SUFFIX: &'static [&'static str] = &["straße", "weg", "allee", "platz", "gasse", "ring"];
+    const ADDRESS_STREET_TPL: &'static str = "{StreetName}{StreetSuffix}";
+    const ADDRESS_BUILDING_NUMBER_FORMATS: &'static [&'static str] = &["#", "##", "##a"];
+    const COMPANY_SUFFIX: &'static [&'static str] = &["GmbH", "AG", "KG", "GmbH & Co. KG", "OHG"];
+    const COMPANY_NAME_TPLS: &'static [&'static str] = &["{Name_1} {Suffix}", "{Name_1} und {Name_2} {Suffix}"];
+    // The blocks the Bundesnetzagentur reserves for use in fiction, in Berlin, Frankfurt, Hamburg, Cologne and Munich
+    const PHONE_NUMBER_FORMATS: &'static [&'static str] = &["+49 30 23125 ###", "+49 69 90009 ###", "+49 40 66969 ###", "+49 221 4710 ###", "+49 89 99998 ###"];
+}
+
+const DE_DE_CITIES: &[&str] = &[
+    "Berlin",
+    "Hamburg",
+    "München",
+    "Köln",
+    "Frankfurt am Main",
+    "Stuttgart",
+    "Düsseldorf",
+    "Leipzig",
+    "Dortmund",
+    "Essen",
+    "Bremen",
+    "Dresden",
+    "Hannover",
+    "Nürnberg",
+    "Duisburg",
+    "Bochum",
+    "Wuppertal",
+    "Bielefeld",
+    "Bonn",
+    "Münster",
+    "Mannheim",
+    "Karlsruhe",
+    "Augsburg",
+];
+
+#[rustfmt::skip]
+impl Data for FrFr {
+    const NAME_FIRST_NAME: &'static [&'static str] = <FR_FR as Data>::NAME_FIRST_NAME;
+    const NAME_LAST_NAME: &'static [&'static str] = <FR_FR as Data>::NAME_LAST_NAME;
+    const INTERNET_FREE_EMAIL_PROVIDER: &'static [&'static str] = <FR_FR as Data>::INTERNET_FREE_EMAIL_PROVIDER;
+    const ADDRESS_STATE: &'static [&'static str] = &["Auvergne-Rhône-Alpes", "Bourgogne-Franche-Comté", "Bretagne", "Centre-Val de Loire", "Corse", "Grand Est", "Hauts-de-France", "Île-de-France", "Normandie", "Nouvelle-Aquitaine", "Occitanie", "Pays de la Loire", "Provence-Alpes-Côte d'Azur"];
+    const ADDRESS_STREET_SUFFIX: &'static [&'static str] = &["rue", "avenue", "boulevard", "place", "allée", "chemin", "impasse", "quai"];
+    const ADDRESS_STREET_TPL: &'static str = "{StreetSuffix} {StreetName}";
+    const ADDRESS_BUILDING_NUMBER_FORMATS: &'static [&'static str] = &["#", "##", "###"];
+    const COMPANY_SUFFIX: &'static [&'static str] = &["SARL", "SA", "SAS", "et Fils", "Groupe"];
+    const COMPANY_NAME_TPLS: &'static [&'static str] = &["{Name_1} {Suffix}", "{Name_1} et {Name_2} {Suffix}"];
+    // The 01 99 00 and 06 39 98 ranges are reserved by ARCEP for use in fiction
+    const PHONE_NUMBER_FORMATS: &'static [&'static str] = &["+33 1 99 00 ## ##", "+33 6 39 98 ## ##"];
+}
+
+const FR_FR_CITIES: &[&str] = &[
+    "Paris",
+    "Marseille",
+    "Lyon",
+    "Toulouse",
+    "Nice",
+    "Nantes",
+    "Montpellier",
+    "Strasbourg",
+    "Bordeaux",
+    "Lille",
+    "Rennes",
+    "Reims",
+    "Toulon",
+    "Saint-Étienne",
+    "Le Havre",
+    "Grenoble",
+    "Dijon",
+    "Angers",
+    "Nîmes",
+    "Clermont-Ferrand",
+    "Tours",
+    "Limoges",
+    "Amiens",
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parsers::transformer;
+    use rand::rngs::SmallRng;
+    use rand::SeedableRng;
+
+    #[test]
+    fn parses_locale_names() {
+        assert_eq!(Locale::parse("de_DE").unwrap(), Locale::DeDe);
+        assert_eq!(Locale::parse("fr-fr").unwrap(), Locale::FrFr);
+        assert_eq!(Locale::parse("en_US").unwrap(), Locale::En);
+        assert!(Locale::parse("xx_XX")
+            .unwrap_err()
+            .starts_with("unknown locale 'xx_XX', expected one of: en, en_gb, de_de"));
+    }
+
+    #[test]
+    fn generates_names_from_the_locale() {
+        let mut rng = SmallRng::seed_from_u64(1);
+        let name = last_name(Locale::DeDe, &mut rng);
+        assert!(DeDe::NAME_LAST_NAME.contains(&name.as_str()));
+    }
+
+    #[test]
+    fn german_street_addresses_put_the_number_last() {
+        let mut rng = SmallRng::seed_from_u64(1);
+        let address = street_address(Locale::DeDe, &mut rng);
+        let street_suffixes = DeDe::ADDRESS_STREET_SUFFIX;
+        let (street, number) = address.rsplit_once(' ').unwrap();
+        assert!(street_suffixes
+            .iter()
+            .any(|suffix| street.ends_with(suffix)));
+        assert!(number.starts_with(|c: char| c.is_ascii_digit()));
+    }
+
+    #[test]
+    fn the_default_locale_is_set_on_nested_transformers_without_one() {
+        let transformer: Transformer = serde_json::from_value(serde_json::json!([
+            {"name": "FakeFirstName"},
+            {"name": "FakeCity", "args": {"locale": "fr_FR"}},
+            {"name": "Uppercase"}
+        ]))
+        .unwrap();
+        let steps = match with_default_locale(transformer, "de_DE").args {
+            Some(args) => {
+                serde_json::from_value::<Vec<Transformer>>(args["steps"].clone()).unwrap()
+            }
+            None => panic!("a pipeline has its steps in its args"),
+        };
+        assert_eq!(transformer::arg(&steps[0].args, "locale"), Some("de_DE"));
+        assert_eq!(transformer::arg(&steps[1].args, "locale"), Some("fr_FR"));
+        assert_eq!(steps[2].args, None);
+
+        let email_or_phone: Transformer =
+            serde_json::from_value(serde_json::json!({"name": "FakeEmailOrPhone"})).unwrap();
+        let email_or_phone = with_default_locale(email_or_phone, "de_DE");
+        assert_eq!(
+            transformer::arg(&email_or_phone.args, "locale"),
+            Some("de_DE")
+        );
+    }
+
+    #[test]
+    fn german_phone_numbers_come_from_the_blocks_reserved_for_fiction() {
+        let mut rng = SmallRng::seed_from_u64(1);
+        for _ in 0..20 {
+            let number = phone_number(Locale::DeDe, &mut rng);
+            assert!(
+                [
+                    "+49 30 23125 ",
+                    "+49 69 90009 ",
+                    "+49 40 66969 ",
+                    "+49 221 4710 ",
+                    "+49 89 99998 "
+                ]
+                .iter()
+                .any(|block| number.starts_with(block)),
+                "{}",
+                number
+            );
+        }
+    }
+
+    #[test]
+    fn same_seed_gives_same_name_in_the_same_locale() {
+        let first = first_name(Locale::FrFr, &mut SmallRng::seed_from_u64(42));
+        let second = first_name(Locale::FrFr, &mut SmallRng::seed_from_u64(42));
+        assert_eq!(first, second);
+    }
+}
@@ -1,9 +1,24 @@
+pub mod column_rules;
+pub mod conditional;
 pub mod copy_row;
 pub mod create_row;
 pub mod custom_classifications;
 pub mod data_row;
 pub mod db_schema;
+pub mod dictionary;
+pub mod email;
+pub mod financial;
+pub mod locales;
+pub mod national_identity_number;
 pub mod national_insurance_number;
+pub mod network;
+pub mod nulls;
+pub mod phone_number;
+pub mod pii_patterns;
+pub mod pipeline;
+pub mod postcode;
+pub mod redact;
+pub mod regex_replace;
 pub mod rng;
 pub mod row_parser;
 pub mod sanitiser;
@@ -11,6 +26,12 @@ pub mod state;
 pub mod strategies;
 pub mod strategy_errors;
 pub mod strategy_file;
+pub mod strategy_schema;
 pub mod strategy_structs;
+pub mod template;
+pub mod toml;
 pub mod transformer;
+pub mod transformer_args;
 pub mod types;
+pub mod unique;
+pub mod yaml;
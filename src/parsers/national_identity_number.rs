@@ -0,0 +1,204 @@
+use crate::parsers::national_insurance_number;
+use rand::Rng;
+use serde::de::Error;
+use serde::{Deserialize, Deserializer};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Format {
+    UkNationalInsurance,
+    UsSsn,
+    UsItin,
+}
+
+const FORMATS: [(&str, Format); 3] = [
+    ("uk_national_insurance", Format::UkNationalInsurance),
+    ("us_ssn", Format::UsSsn),
+    ("us_itin", Format::UsItin),
+];
+
+// SSNs have never been issued with an area number of 900-999, and ITINs (which do start with 9)
+// only use these group numbers, so 9xx-01-xxxx to 9xx-49-xxxx can't belong to anyone
+const AREAS: u64 = 100;
+const SSN_GROUPS: u64 = 49;
+const ITIN_GROUPS: [u64; 44] = [
+    50, 51, 52, 53, 54, 55, 56, 57, 58, 59, 60, 61, 62, 63, 64, 65, 70, 71, 72, 73, 74, 75, 76, 77,
+    78, 79, 80, 81, 82, 83, 84, 85, 86, 87, 88, 90, 91, 92, 94, 95, 96, 97, 98, 99,
+];
+const SERIALS: u64 = 9999;
+
+/// Multiplying by a prime spreads consecutive unique numbers over the whole range, rather than
+/// only changing the last digit
+const SPREAD: u128 = 2_654_435_761;
+
+/// The `format` arg, or `None` to work it out from each value
+pub fn format<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<Format>, D::Error> {
+    let name = String::deserialize(deserializer)?;
+    parse_format(&name).map_err(D::Error::custom)
+}
+
+fn parse_format(name: &str) -> Result<Option<Format>, String> {
+    match name {
+        "auto" => Ok(None),
+        name => FORMATS
+            .iter()
+            .find(|(format_name, _)| *format_name == name)
+            .map(|(_, format)| Some(*format))
+            .ok_or_else(|| {
+                format!(
+                    "unknown national identity number format '{}', expected one of: auto, {}",
+                    name,
+                    FORMATS.map(|(format_name, _)| format_name).join(", ")
+                )
+            }),
+    }
+}
+
+/// Nine digit values are US numbers (ITINs if they look like one), everything else is treated as a
+/// National Insurance number
+pub fn detect(value: &str) -> Format {
+    let characters: Vec<char> = value.chars().filter(char::is_ascii_alphanumeric).collect();
+    if characters.len() == 9 && characters.iter().all(char::is_ascii_digit) {
+        let group: u64 = characters[3..5].iter().collect::<String>().parse().unwrap();
+        if characters[0] == '9' && ITIN_GROUPS.contains(&group) {
+            Format::UsItin
+        } else {
+            Format::UsSsn
+        }
+    } else {
+        Format::UkNationalInsurance
+    }
+}
+
+/// Generates a number in the same format as `value`, keeping its spacing, dashes and letter case.
+/// With `unique` set, distinct values of it always give distinct numbers
+pub fn fake<R: Rng + ?Sized>(
+    value: &str,
+    format: Option<Format>,
+    unique: Option<usize>,
+    rng: &mut R,
+) -> String {
+    let format = format.unwrap_or_else(|| detect(value));
+    let count = count(format);
+    let n = match unique {
+        Some(unique) => ((unique as u128 * SPREAD) % count as u128) as u64,
+        None => rng.gen_range(0..count),
+    };
+    let number = nth(format, n);
+    reformat(value, &number, format).unwrap_or(number)
+}
+
+fn count(format: Format) -> u64 {
+    match format {
+        Format::UkNationalInsurance => national_insurance_number::count(),
+        Format::UsSsn => AREAS * SSN_GROUPS * SERIALS,
+        Format::UsItin => AREAS * ITIN_GROUPS.len() as u64 * SERIALS,
+    }
+}
+
+fn nth(format: Format, n: u64) -> String {
+    let groups = match format {
+        Format::UkNationalInsurance => return national_insurance_number::nth(n),
+        Format::UsSsn => SSN_GROUPS,
+        Format::UsItin => ITIN_GROUPS.len() as u64,
+    };
+    let serial = n % SERIALS + 1;
+    let n = n / SERIALS;
+    let group_index = n % groups;
+    let area = 900 + n / groups % AREAS;
+    let group = match format {
+        Format::UsItin => ITIN_GROUPS[group_index as usize],
+        _ => group_index + 1,
+    };
+    format!("{}{:02}{:04}", area, group, serial)
+}
+
+/// Puts the characters of `number` in place of the letters and digits of `original`. National
+/// Insurance numbers are sometimes stored without the suffix letter, so it is dropped to match
+fn reformat(original: &str, number: &str, format: Format) -> Option<String> {
+    let original_length = original.chars().filter(char::is_ascii_alphanumeric).count();
+    let number = match format {
+        Format::UkNationalInsurance if original_length == number.len() - 1 => {
+            &number[..number.len() - 1]
+        }
+        _ => number,
+    };
+    if original_length != number.len() {
+        return None;
+    }
+
+    let mut replacements = number.chars();
+    Some(
+        original
+            .chars()
+            .map(|c| match c {
+                c if c.is_ascii_lowercase() => replacements.next().unwrap().to_ascii_lowercase(),
+                c if c.is_ascii_alphanumeric() => replacements.next().unwrap(),
+                c => c,
+            })
+            .collect(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::SmallRng;
+    use rand::SeedableRng;
+    use regex::Regex;
+    use std::collections::HashSet;
+
+    fn fake_number(value: &str) -> String {
+        fake(value, None, None, &mut SmallRng::seed_from_u64(1))
+    }
+
+    #[test]
+    fn keeps_the_formatting_of_national_insurance_numbers() {
+        let spaced = Regex::new(r"^[A-Z]{2} \d{2} \d{2} \d{2} [A-D]$").unwrap();
+        assert!(spaced.is_match(&fake_number("JR 55 55 55 E")));
+        assert!(Regex::new(r"^[a-z]{2}\d{6}$")
+            .unwrap()
+            .is_match(&fake_number("ab123456")));
+    }
+
+    #[test]
+    fn us_numbers_are_never_issued_ssns() {
+        let ssn = fake_number("123-45-6789");
+        assert!(Regex::new(r"^9\d{2}-(0[1-9]|[1-4]\d)-\d{4}$")
+            .unwrap()
+            .is_match(&ssn));
+
+        let itin = fake_number("912 70 1234");
+        assert_eq!(detect(&itin), Format::UsItin);
+        assert!(itin.starts_with('9'));
+    }
+
+    #[test]
+    fn the_format_can_be_forced() {
+        let ssn = fake(
+            "not a number",
+            Some(Format::UsSsn),
+            None,
+            &mut SmallRng::seed_from_u64(1),
+        );
+        assert_eq!(detect(&ssn), Format::UsSsn);
+        assert_eq!(ssn.len(), 9);
+    }
+
+    #[test]
+    fn unique_numbers_do_not_collide() {
+        let mut rng = SmallRng::seed_from_u64(1);
+        for format in [Format::UkNationalInsurance, Format::UsSsn, Format::UsItin] {
+            let numbers: HashSet<String> = (0..10000)
+                .map(|unique| fake("", Some(format), Some(unique), &mut rng))
+                .collect();
+            assert_eq!(numbers.len(), 10000);
+        }
+    }
+
+    #[test]
+    fn rejects_unknown_formats() {
+        assert!(parse_format("ca_sin")
+            .unwrap_err()
+            .starts_with("unknown national identity number format 'ca_sin'"));
+    }
+}
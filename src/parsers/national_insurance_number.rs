@@ -1,1012 +1,63 @@
-use rand::seq::SliceRandom;
+use lazy_static::lazy_static;
 
-pub fn random() -> String {
-    NATIONAL_INSURANCE_NUMBERS
-        .choose(&mut rand::thread_rng())
-        .unwrap()
-        .to_string()
+// https://www.gov.uk/hmrc-internal-manuals/national-insurance-manual/nim39110
+const FIRST_LETTERS: &[u8] = b"ABCEGHJKLMNOPRSTWXYZ";
+const SECOND_LETTERS: &[u8] = b"ABCEGHJKLMNPRSTWXYZ";
+const UNALLOCATED_PREFIXES: [&str; 7] = ["BG", "GB", "KN", "NK", "NT", "TN", "ZZ"];
+const SUFFIXES: &[u8] = b"ABCD";
+const DIGITS: usize = 1_000_000;
+
+lazy_static! {
+    /// The prefixes that can appear in a National Insurance number
+    static ref PREFIXES: Vec<String> = FIRST_LETTERS
+        .iter()
+        .flat_map(|first| {
+            SECOND_LETTERS
+                .iter()
+                .map(move |second| format!("{}{}", char::from(*first), char::from(*second)))
+        })
+        .filter(|prefix| !UNALLOCATED_PREFIXES.contains(&prefix.as_str()))
+        .collect();
+}
+
+/// How many different National Insurance numbers there are
+pub fn count() -> u64 {
+    (PREFIXES.len() * DIGITS * SUFFIXES.len()) as u64
+}
+
+/// The nth National Insurance number, so distinct `n`s give distinct numbers
+pub fn nth(n: u64) -> String {
+    let n = n % count();
+    let suffix = SUFFIXES[(n % SUFFIXES.len() as u64) as usize];
+    let n = n / SUFFIXES.len() as u64;
+    let digits = n % DIGITS as u64;
+    let prefix = &PREFIXES[(n / DIGITS as u64) as usize];
+    format!("{}{:06}{}", prefix, digits, char::from(suffix))
 }
 
-pub const NATIONAL_INSURANCE_NUMBERS: [&str; 1001] = [
-    "MQ938548A",
-    "IG994441D",
-    "GJ050822A",
-    "MJ856000C",
-    "FI911014D",
-    "HQ257069D",
-    "BS521956A",
-    "ZC443091B",
-    "TA990531C",
-    "AR009026B",
-    "EC248990A",
-    "FA115007D",
-    "VK154689C",
-    "AF455812C",
-    "ZC115316D",
-    "SR605965B",
-    "TP928531D",
-    "ZD631808A",
-    "MD384821D",
-    "NX777262C",
-    "DS881750B",
-    "JV478977A",
-    "WT102690C",
-    "PO810098C",
-    "PS706219C",
-    "DO221235A",
-    "AO733856D",
-    "GL883587C",
-    "KM087173B",
-    "TE314217A",
-    "YC680261B",
-    "GG124789B",
-    "WC374410C",
-    "OR290660A",
-    "UY016841A",
-    "TW216157B",
-    "KI097047C",
-    "LT795414A",
-    "QR009307B",
-    "OH699593B",
-    "QT665601D",
-    "EQ654341D",
-    "HY444440A",
-    "NV777200B",
-    "HU124137A",
-    "WP041214B",
-    "BX061387D",
-    "WM979161B",
-    "WJ003736D",
-    "VZ684039B",
-    "ZC456866A",
-    "GA613413D",
-    "FX544788D",
-    "QZ340783C",
-    "RV233785B",
-    "JM864638D",
-    "RD767057A",
-    "BC869298C",
-    "JR025682A",
-    "WO841109D",
-    "EK749891B",
-    "AB425160D",
-    "KX694625D",
-    "TH764711B",
-    "UD268227B",
-    "YV135827A",
-    "DH280042D",
-    "ZU285394A",
-    "FO432784B",
-    "IJ517043C",
-    "ZC069373C",
-    "HA624621B",
-    "MM869373B",
-    "DH060779C",
-    "EQ966709D",
-    "QF496061D",
-    "UD828542B",
-    "MH121435C",
-    "KM589819B",
-    "VH338705D",
-    "MC368628C",
-    "UH521082D",
-    "RY986192D",
-    "AC045330C",
-    "DE392498A",
-    "EP871051B",
-    "SZ877418A",
-    "UG232119A",
-    "UM226488D",
-    "TJ838736A",
-    "YK526278C",
-    "PG236473B",
-    "SP957584C",
-    "EV975240B",
-    "VA568787D",
-    "BD034280C",
-    "LI373370A",
-    "BX290657B",
-    "OE151665D",
-    "PA792224C",
-    "VU713185A",
-    "FF105835B",
-    "PQ248993A",
-    "IA059711A",
-    "TG048220A",
-    "AT561869B",
-    "IV478395A",
-    "EO479278C",
-    "SU680659A",
-    "KQ666239A",
-    "OP767939B",
-    "IQ806202D",
-    "DN218663A",
-    "XT501449C",
-    "DB845639B",
-    "WR068392D",
-    "PY719168C",
-    "IL821147B",
-    "AB415825C",
-    "IG929016D",
-    "KZ117474A",
-    "RW298427C",
-    "FF256881D",
-    "WV520925C",
-    "XI468978C",
-    "KG995868C",
-    "XC668923A",
-    "QN621951D",
-    "JU547038A",
-    "ZW474684A",
-    "EH664462B",
-    "EA399002D",
-    "UW996925D",
-    "JK088311A",
-    "CF624861D",
-    "JV638196A",
-    "HJ309892D",
-    "NV058127A",
-    "GK302395D",
-    "PA430140D",
-    "UJ930760D",
-    "EC367629D",
-    "MH043725D",
-    "LO468131C",
-    "GX860938B",
-    "RI739918C",
-    "PU878604C",
-    "VR730950A",
-    "LI522004D",
-    "WH819511D",
-    "FH498665B",
-    "RK969952C",
-    "QT058796C",
-    "OK069432B",
-    "CG622051A",
-    "HV349827A",
-    "GL441217C",
-    "LU082287C",
-    "PK029214D",
-    "YM264919B",
-    "EJ077100D",
-    "WY649292C",
-    "BK841701D",
-    "QQ178600D",
-    "UG197552D",
-    "DB153579D",
-    "AF157729C",
-    "EH995795D",
-    "VW970072B",
-    "ZB887853B",
-    "IK650207A",
-    "ES073960C",
-    "FY463659B",
-    "SB219645C",
-    "SQ274161D",
-    "KP086867D",
-    "WN290243C",
-    "FM562819C",
-    "WZ786326C",
-    "AD769202C",
-    "QD445179B",
-    "MF906911B",
-    "KB762429C",
-    "SC673890B",
-    "HX270227A",
-    "RO351551C",
-    "IV548115B",
-    "HD201463B",
-    "UC275768A",
-    "GH553021D",
-    "QY728016A",
-    "RS281703A",
-    "WK812742B",
-    "EG171748A",
-    "JJ180190A",
-    "VA707987C",
-    "US769479B",
-    "OU758777D",
-    "HI009150C",
-    "HB538359B",
-    "DP210716B",
-    "RT035351C",
-    "IB541823D",
-    "ME908311B",
-    "PL272294B",
-    "AK470142D",
-    "VD784300B",
-    "HW692845A",
-    "MG352617C",
-    "FV844744D",
-    "ZK119003C",
-    "RY418731A",
-    "XJ641280C",
-    "AA533979B",
-    "HH489270C",
-    "EI912749D",
-    "LH499004C",
-    "VL026086A",
-    "UJ386741C",
-    "CV217621D",
-    "RS275508C",
-    "JZ093600B",
-    "ZZ616930B",
-    "FT924461C",
-    "NE377567C",
-    "MU377770C",
-    "XW282453B",
-    "ZD397148D",
-    "EV190978A",
-    "VW401819A",
-    "YL309784D",
-    "US590130B",
-    "DX895117D",
-    "OP306397A",
-    "HQ742443A",
-    "ET104843A",
-    "SH379203A",
-    "EK910769D",
-    "PV861589D",
-    "QP172463B",
-    "PJ867955A",
-    "BG418707C",
-    "QM041868B",
-    "IG077113B",
-    "GA508484D",
-    "RX499930D",
-    "RM171149D",
-    "MC660869D",
-    "HI405188B",
-    "UK531631A",
-    "OW268276A",
-    "NZ081079A",
-    "PJ179604A",
-    "DB833017D",
-    "MH389133C",
-    "DN524361B",
-    "JP705124B",
-    "SF464132A",
-    "EO281830A",
-    "GE686990C",
-    "MK712056C",
-    "UA333739C",
-    "UH425178D",
-    "IE106585D",
-    "RN210639D",
-    "NU897921B",
-    "JE999516D",
-    "PO222017D",
-    "YU912986B",
-    "HD700612A",
-    "UB876600A",
-    "PL208726D",
-    "YI544445B",
-    "MT312933A",
-    "IX931798D",
-    "ZG797830D",
-    "HE086488A",
-    "PU958530B",
-    "JD183370C",
-    "XM549437B",
-    "DB503595D",
-    "II642371C",
-    "TE917481B",
-    "FA995169D",
-    "SE875663A",
-    "LP039390B",
-    "VL515536A",
-    "AX157080B",
-    "ZD401002C",
-    "AM574778B",
-    "TQ726384D",
-    "ST316115B",
-    "ZP358972A",
-    "QR245189D",
-    "FT333395B",
-    "HX573058D",
-    "UH415906B",
-    "CI214152D",
-    "GQ020673D",
-    "FQ713286A",
-    "FU708901B",
-    "XQ548116B",
-    "TY928136C",
-    "XO244051D",
-    "WP968508D",
-    "RF855904C",
-    "FH955789A",
-    "DM780087B",
-    "WT076484C",
-    "KV985385D",
-    "GV666920C",
-    "ET219229A",
-    "AD634552A",
-    "LQ280652B",
-    "JN355192D",
-    "PY843013B",
-    "AY325826C",
-    "PX926967A",
-    "GZ651428C",
-    "BS575246C",
-    "AE718341D",
-    "JM260670A",
-    "OD381894B",
-    "ZN060110C",
-    "EA183842B",
-    "QQ206384D",
-    "OX080517C",
-    "ZD905051A",
-    "NU611754C",
-    "PR606791B",
-    "DA275452C",
-    "SX983500D",
-    "CI448029A",
-    "ZD544756D",
-    "YE275810C",
-    "MZ657870D",
-    "TP368555C",
-    "QD719983A",
-    "XU740404A",
-    "EU659177D",
-    "OS772750C",
-    "BB887183A",
-    "WX214568B",
-    "LR358113A",
-    "CH177772A",
-    "BI783668A",
-    "WH320668C",
-    "VN169746C",
-    "SF024063A",
-    "EB380494D",
-    "EQ069901D",
-    "XQ194117B",
-    "BA715383D",
-    "CW517041D",
-    "WJ300442B",
-    "OI594325B",
-    "SA061234B",
-    "KN864925D",
-    "TI475108C",
-    "NB631307D",
-    "PH966617C",
-    "FM281012B",
-    "KY586533C",
-    "GF925331B",
-    "MR439451A",
-    "CC516696D",
-    "LA169413A",
-    "DJ417100D",
-    "HK032895B",
-    "SL908374D",
-    "AJ721844A",
-    "MF509911A",
-    "IN991532D",
-    "TI335212A",
-    "BN383772B",
-    "VP431100B",
-    "WZ927935C",
-    "CM881178A",
-    "UT287946D",
-    "ST494670C",
-    "OB050591C",
-    "DJ020699D",
-    "VK476333D",
-    "NK706773C",
-    "QY400495C",
-    "DU514240A",
-    "WO952737D",
-    "JZ543971C",
-    "UE153711C",
-    "NO698082C",
-    "XE918299B",
-    "OB544751A",
-    "YX903737D",
-    "YV369968A",
-    "YK792156D",
-    "AQ269423D",
-    "ZT039355C",
-    "KA059869D",
-    "EZ004659D",
-    "AW749411C",
-    "LG758757B",
-    "HF964913D",
-    "VX429584A",
-    "ZH629079A",
-    "LP302911C",
-    "AJ871179A",
-    "MK459689A",
-    "VF835601C",
-    "EU430108D",
-    "EV076895C",
-    "XS562636D",
-    "GX390971D",
-    "YW661046B",
-    "MN855489C",
-    "KK220531C",
-    "IV475092A",
-    "BC517186C",
-    "NL683684A",
-    "EK939976C",
-    "BB994274A",
-    "WI810491C",
-    "SP639252B",
-    "WI980590C",
-    "FU471719C",
-    "ID786583B",
-    "NX124001A",
-    "FE576106A",
-    "OF971752D",
-    "LS995014D",
-    "TJ016048B",
-    "DK225703B",
-    "KP398104A",
-    "YB154037C",
-    "VK880837C",
-    "EM668500A",
-    "WP112793B",
-    "EG000191C",
-    "MS670030D",
-    "CR155502A",
-    "NG097319C",
-    "JV614486C",
-    "UX673221D",
-    "OJ507117C",
-    "CR732915B",
-    "OF714922D",
-    "FT817693B",
-    "RI923524D",
-    "MK945968C",
-    "US759517B",
-    "EZ175467A",
-    "FL399144B",
-    "PU525472D",
-    "LD362026B",
-    "CR117857D",
-    "YF679942A",
-    "XP545421D",
-    "HR052651B",
-    "AN401111B",
-    "EO855832D",
-    "IH470321C",
-    "RM097417D",
-    "SG202754B",
-    "YI660085C",
-    "EB722643D",
-    "AS974143C",
-    "CG253718A",
-    "MQ155888C",
-    "LZ389881D",
-    "KA441891C",
-    "DR259846A",
-    "WL150416B",
-    "PY025556D",
-    "NF792517C",
-    "UQ961741D",
-    "JC189496B",
-    "MD134075D",
-    "ZH027280B",
-    "UX125828A",
-    "ZO957304A",
-    "QH480665D",
-    "UV017983D",
-    "TC557748D",
-    "FJ377840A",
-    "UR310706C",
-    "FA256371B",
-    "JJ899702B",
-    "XZ092371A",
-    "HU662876D",
-    "ZP058056B",
-    "SR210812A",
-    "OP926016D",
-    "BB280792C",
-    "FP810751A",
-    "JQ210765C",
-    "TB436764A",
-    "IT779968D",
-    "MK730716A",
-    "GH899228B",
-    "HF878784C",
-    "AQ076158A",
-    "EN230174C",
-    "JW811871D",
-    "XT947246A",
-    "NU609829A",
-    "CZ553755B",
-    "SH701703B",
-    "SD916935A",
-    "BV681969A",
-    "MK421006A",
-    "ZZ523082A",
-    "ZN917183B",
-    "QD195137B",
-    "VV484782B",
-    "EZ940217B",
-    "VZ035546C",
-    "DQ206890C",
-    "TP202112C",
-    "TI898409C",
-    "IQ011118B",
-    "HE945013A",
-    "GT785013A",
-    "CR112628A",
-    "YU287661B",
-    "DG837932B",
-    "MA431347B",
-    "UN489424A",
-    "HE821957D",
-    "BY570734B",
-    "KU728134A",
-    "EB382414C",
-    "PW037439C",
-    "OL881793D",
-    "XV045040C",
-    "EA920500B",
-    "AR762270A",
-    "VX084278D",
-    "NB550257A",
-    "JL003733C",
-    "KL639715A",
-    "FE104085B",
-    "QZ695523C",
-    "GT572330B",
-    "LN764751D",
-    "DK028935A",
-    "FR469183D",
-    "BX123031A",
-    "WO767751C",
-    "ML391192C",
-    "VV936922C",
-    "YQ877500A",
-    "GT794414A",
-    "IR808205B",
-    "LE098585D",
-    "YA220512B",
-    "GL762976A",
-    "ZO396405B",
-    "DA438901C",
-    "FQ518847D",
-    "VM800865B",
-    "LQ884444A",
-    "VU808873B",
-    "QO073223C",
-    "IP368273A",
-    "UE198879A",
-    "OJ759010D",
-    "BS151124D",
-    "KT192659C",
-    "BH638541D",
-    "ET595996A",
-    "AF562357C",
-    "HZ508172D",
-    "QT950617D",
-    "NC067344C",
-    "KG231849D",
-    "LP545099B",
-    "ED608045B",
-    "MV104908C",
-    "LJ526211D",
-    "UR342037B",
-    "PX416440C",
-    "ME719481B",
-    "OT079103A",
-    "SV073582C",
-    "HF471113D",
-    "ZA532920D",
-    "OI724752C",
-    "IK210350D",
-    "KA246515A",
-    "KM621305C",
-    "PI925104C",
-    "NR325142C",
-    "TW297309B",
-    "ZQ774879B",
-    "TG746005A",
-    "DJ467429B",
-    "DE030188D",
-    "LM116926B",
-    "OE084913C",
-    "XV183258A",
-    "JV094652D",
-    "FV771713A",
-    "PH103111A",
-    "ZQ048716D",
-    "ZD350306B",
-    "AV060668C",
-    "YA265943B",
-    "MS088001A",
-    "VB621849C",
-    "VW502028C",
-    "KW550400A",
-    "YC601070C",
-    "LI508814A",
-    "XL563498A",
-    "GH104461D",
-    "WX558749C",
-    "DX180451C",
-    "HD135613D",
-    "NE622911C",
-    "GJ639085C",
-    "CF352866C",
-    "NZ939718A",
-    "QI440458D",
-    "XU844447A",
-    "FI001543D",
-    "OX293834A",
-    "VN006627A",
-    "NZ907981D",
-    "MV580793D",
-    "UZ334605C",
-    "GQ018910D",
-    "XG324304C",
-    "OR100982D",
-    "KR739587D",
-    "AF474284C",
-    "CC853530D",
-    "UN296520B",
-    "SO685778C",
-    "XT909668A",
-    "BO018312D",
-    "VD892838C",
-    "LB679166B",
-    "HP675142A",
-    "JC000103D",
-    "VP550463D",
-    "ZH343156B",
-    "VV742799D",
-    "AH960079C",
-    "QV125950D",
-    "UU385767D",
-    "EF615874B",
-    "RJ775146C",
-    "UT776936A",
-    "VM313181A",
-    "UT806164D",
-    "YO218773D",
-    "LM024281A",
-    "RA645986A",
-    "QJ292055C",
-    "FQ499880D",
-    "ZO391825B",
-    "GO965419A",
-    "NM783968D",
-    "VY330044D",
-    "QB616528A",
-    "CY953847D",
-    "MX797114A",
-    "HA675355D",
-    "WN498586D",
-    "JJ547701A",
-    "NL350840C",
-    "LC321989B",
-    "ME846818B",
-    "QO491430A",
-    "YO076726D",
-    "US290752B",
-    "LS236807A",
-    "ZJ118976D",
-    "TU636485B",
-    "KL615290B",
-    "CS008045C",
-    "WR184132A",
-    "QN332013D",
-    "JD360943C",
-    "NV637484B",
-    "VB088109C",
-    "CX264147B",
-    "IZ715039C",
-    "FF626183A",
-    "HY670773B",
-    "RY116947C",
-    "YO252637B",
-    "ZV084874C",
-    "UY693343C",
-    "YT593456B",
-    "LZ616086B",
-    "HU230071C",
-    "VT419427A",
-    "PZ425631D",
-    "PK042901B",
-    "EF850002C",
-    "NG832526C",
-    "EI777149C",
-    "EH368352A",
-    "II952156B",
-    "BI480801C",
-    "EU010294C",
-    "WS836810A",
-    "ZQ151850A",
-    "VG609713D",
-    "EA021900B",
-    "NN445952A",
-    "VX000107C",
-    "BE167709C",
-    "KJ645567A",
-    "TN179241A",
-    "OV664717B",
-    "OC426750C",
-    "BK483829A",
-    "BS781849A",
-    "FG549876A",
-    "LL065219D",
-    "LP197403A",
-    "ZL120521B",
-    "MY436179D",
-    "OM559762A",
-    "UA534704A",
-    "WH450912A",
-    "UM613059B",
-    "UO305637C",
-    "FE499371C",
-    "JD871717C",
-    "XE882674B",
-    "KL722116D",
-    "GK865557C",
-    "ZN432207B",
-    "QW750070B",
-    "HU643860A",
-    "JW738981C",
-    "NO419866B",
-    "KT880370C",
-    "BG098131D",
-    "WN456164D",
-    "KU132631D",
-    "WJ860023B",
-    "FC839926B",
-    "FD415424D",
-    "HA152450D",
-    "FN494560D",
-    "FM600206B",
-    "RZ812826C",
-    "MC417243B",
-    "HK602211B",
-    "OS588565C",
-    "RG382265B",
-    "LI854241C",
-    "ET046517B",
-    "UV324262A",
-    "RF823131D",
-    "OH263324B",
-    "FN028152D",
-    "KM324104A",
-    "WC360860C",
-    "AU416640D",
-    "TJ966710A",
-    "HA328757B",
-    "NB303922C",
-    "BR263462D",
-    "GN247130B",
-    "VL264494B",
-    "LU054594D",
-    "WG882999B",
-    "IS377454B",
-    "MJ465732C",
-    "IW081205B",
-    "VX800231B",
-    "WR311472B",
-    "GX170752D",
-    "GA148281C",
-    "FU557939D",
-    "UW963846C",
-    "HD851310D",
-    "DU896213C",
-    "LD568441D",
-    "TY982555C",
-    "RH275137D",
-    "NR594734A",
-    "OS381213C",
-    "IN229332D",
-    "TZ633107C",
-    "XJ622728C",
-    "CP192397D",
-    "FU208641A",
-    "RR047865A",
-    "PZ030802C",
-    "FA405637D",
-    "DP478076A",
-    "FI010356A",
-    "GU313538B",
-    "TY027500D",
-    "GK514991A",
-    "PN140821C",
-    "MB979739D",
-    "PX466534B",
-    "GK940409B",
-    "ZP032535C",
-    "BG562408B",
-    "MA311048A",
-    "VX264935B",
-    "VX188164C",
-    "QH231716A",
-    "JD238563D",
-    "KQ652539C",
-    "HT590397D",
-    "QO774050B",
-    "DF445097A",
-    "YQ285571D",
-    "OW178771A",
-    "EB025712D",
-    "LC441402C",
-    "EO593782D",
-    "WK642804C",
-    "CA360533D",
-    "MM901354C",
-    "NE230171A",
-    "XF347250A",
-    "TM013192A",
-    "LE567717A",
-    "IB149440D",
-    "JQ832104C",
-    "QD194133B",
-    "SD902607A",
-    "RQ647587C",
-    "PZ495067B",
-    "ER791694C",
-    "QJ303385D",
-    "SG687858C",
-    "LT065834C",
-    "QH102122B",
-    "VG982305B",
-    "AD518422B",
-    "UU681561D",
-    "IW074103C",
-    "AA014313B",
-    "IQ135427B",
-    "SF310383B",
-    "XZ847727D",
-    "MR746577B",
-    "NF662514A",
-    "XJ795951A",
-    "EL830350D",
-    "BJ519859C",
-    "CN578616C",
-    "CD690772D",
-    "UE995372A",
-    "NA998926A",
-    "NG930530D",
-    "LT757915A",
-    "PF171458C",
-    "OT461196B",
-    "GW194091A",
-    "PO247304A",
-    "SX360790B",
-    "GR672609B",
-    "XV295779D",
-    "IX935571D",
-    "KI462092B",
-    "AM904270D",
-    "RV982089B",
-    "IY885671C",
-    "UE817931D",
-    "EC745292B",
-    "JN863484A",
-    "DF138377D",
-    "QY406280C",
-    "MJ985022D",
-    "UV752042C",
-    "PH366006C",
-    "IU843820B",
-    "HV971499A",
-    "GY250103D",
-    "AU694891A",
-    "NN025532C",
-    "WR627521D",
-    "BW711916B",
-    "IC502980A",
-    "WG289796C",
-    "GG017491C",
-    "VA048026C",
-    "DF044372C",
-    "DB787688A",
-    "UF074349C",
-    "LJ153545D",
-    "HT725957A",
-    "ZS438901A",
-    "LW720356D",
-    "DW466922D",
-    "RD533856C",
-    "CS510433A",
-    "MG135046B",
-    "QH369445D",
-    "RE696741A",
-    "QS881887B",
-    "RY188922B",
-    "TY342697C",
-    "EN335206B",
-    "UJ779004A",
-    "SC975400A",
-    "BF824984B",
-    "YC410473C",
-    "BR401068C",
-    "GC645490D",
-    "FG117052C",
-    "AI334577B",
-    "MY729321B",
-    "DN527116A",
-    "LD694703B",
-    "PC126255C",
-    "DL308032D",
-    "HE510804D",
-    "AF890373B",
-    "IT605085D",
-    "LO260459B",
-    "JY724835C",
-    "TJ095904B",
-    "OU308805D",
-    "OG048691C",
-    "LD554885C",
-    "XK509789A",
-    "IZ935039A",
-    "MV465010D",
-    "AC142569C",
-    "PK643104B",
-    "OU078843B",
-    "SP635549D",
-    "GT691949A",
-    "HD012526B",
-    "LB666900D",
-    "EF331303A",
-    "YD045396B",
-    "LB947380B",
-    "RJ523623A",
-    "HA270968B",
-    "MD556612C",
-    "QF446031A",
-    "XJ215617D",
-    "CN978490C",
-    "LR013103C",
-    "JI689325C",
-    "QG911920B",
-    "YA546024B",
-    "UR791351C",
-    "JF536012C",
-    "TO449836C",
-    "YV745248C",
-    "EZ052176D",
-    "ZS214664D",
-    "DY046498B",
-    "IM449244A",
-    "WH343665D",
-    "ET000067D",
-    "GG882862C",
-    "IA492401C",
-    "QI032015A",
-    "AQ446283A",
-    "DI186195D",
-    "LO043194D",
-    "CE622778B",
-    "WH815702B",
-    "GM771341D",
-    "OP052300A",
-    "WI608051A",
-    "WS478514C",
-    "QS337188A",
-    "WN282052D",
-    "CH703909A",
-    "TM040964D",
-    "GZ381591B",
-    "FX191532C",
-    "AX598290C",
-    "PS655357D",
-    "GC157584D",
-    "VX089053C",
-    "YH009213A",
-    "NW053431B",
-    "HU993330A",
-    "HW532175A",
-    "OY964189A",
-    "BT318238D",
-    "QH263661B",
-    "GR830504C",
-    "ZP485850D",
-    "LE339261C",
-    "LE904637A",
-    "CK318285B",
-    "VL301020D",
-    "MW804757C",
-    "KH129083D",
-    "FJ455406C",
-    "VY772044B",
-    "UX105422A",
-    "OG121560D",
-];
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parsers::pii_patterns;
+    use rand::Rng;
+
+    #[test]
+    fn generates_valid_national_insurance_numbers() {
+        let pattern = pii_patterns::builtin("uk_national_insurance_number").unwrap();
+        let mut rng = crate::parsers::rng::get();
+        for _ in 0..1000 {
+            let number = nth(rng.gen_range(0..count()));
+            assert_eq!(pattern.find_iter(&number).count(), 1, "{}", number);
+            assert!(!UNALLOCATED_PREFIXES.contains(&&number[..2]));
+        }
+    }
+
+    #[test]
+    fn every_n_gives_a_different_number() {
+        assert_eq!(nth(0), "AA000000A");
+        assert_eq!(nth(1), "AA000000B");
+        assert_eq!(nth(4), "AA000001A");
+        assert_ne!(nth(count() - 1), nth(0));
+        assert_eq!(nth(count()), nth(0));
+    }
+}
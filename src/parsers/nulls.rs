@@ -0,0 +1,149 @@
+use crate::parsers::strategy_structs::TransformerType;
+use crate::parsers::transformer_args::{self, Args, ParsedTransformer, ProbabilisticNullArgs};
+use serde::de::Error;
+use serde::Deserializer;
+use sha2::{Digest, Sha256};
+
+pub const NULL: &str = "\\N";
+
+/// The `fraction` arg
+pub fn fraction<'de, D: Deserializer<'de>>(deserializer: D) -> Result<f64, D::Error> {
+    let fraction: f64 = transformer_args::number(deserializer)?;
+    if (0.0..=1.0).contains(&fraction) {
+        Ok(fraction)
+    } else {
+        Err(D::Error::custom(format!(
+            "'fraction' must be a number between 0 and 1, not {}",
+            fraction
+        )))
+    }
+}
+
+pub fn validate(args: &ProbabilisticNullArgs, column_names: &[&str]) -> Result<(), String> {
+    match args.id_column.as_deref() {
+        Some(id_column) if !column_names.contains(&id_column) => Err(format!(
+            "'id_column' refers to unknown column '{}'",
+            id_column
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// Whether a value should be nulled. This is decided by hashing the value (or the `id_column`
+/// value if there is one), so the same rows are nulled every time the dump is anonymised
+pub fn should_null(
+    value: &str,
+    args: &ProbabilisticNullArgs,
+    column_values: &[(String, String)],
+    global_salt: Option<&str>,
+) -> bool {
+    let key = args
+        .id_column
+        .as_deref()
+        .and_then(|id_column| {
+            column_values
+                .iter()
+                .find(|(column, _)| column == id_column)
+                .map(|(_, id)| id.as_str())
+        })
+        .unwrap_or(value);
+
+    let mut hasher = Sha256::new();
+    hasher.update(key.as_bytes());
+    if let Some(salt) = global_salt {
+        hasher.update(salt.as_bytes());
+    }
+    let hash = u64::from_le_bytes(hasher.finalize()[..8].try_into().unwrap());
+    (hash as f64 / u64::MAX as f64) < args.fraction
+}
+
+/// Whether the transformer can ever output a null, including through nested transformers or a
+/// `Fixed` value of `\N`
+pub fn can_produce_null(transformer: &ParsedTransformer) -> bool {
+    match &transformer.args {
+        _ if transformer.name == TransformerType::Null => true,
+        Args::ProbabilisticNull(_) => true,
+        Args::Fixed(args) => args.value == NULL,
+        Args::Pipeline(steps) => steps.iter().any(can_produce_null),
+        Args::Conditional(args) => args
+            .cases
+            .iter()
+            .map(|case| &case.transformer)
+            .chain([args.default.as_ref()])
+            .any(can_produce_null),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parsers::strategy_structs::Transformer;
+    use serde_json::json;
+    use std::collections::HashMap;
+
+    fn probabilistic_null(fraction: serde_json::Value) -> Transformer {
+        Transformer {
+            name: TransformerType::ProbabilisticNull,
+            args: Some(HashMap::from([("fraction".to_string(), fraction)])),
+        }
+    }
+
+    fn parsed(transformer: &Transformer) -> Result<ParsedTransformer, String> {
+        ParsedTransformer::parse(transformer)
+    }
+
+    fn fraction_args(fraction: serde_json::Value) -> ProbabilisticNullArgs {
+        serde_json::from_value(json!({ "fraction": fraction })).unwrap()
+    }
+
+    #[test]
+    fn nulls_roughly_the_given_fraction_of_values() {
+        let args = fraction_args(json!(0.25));
+        let nulled = (0..10000)
+            .filter(|i| should_null(&i.to_string(), &args, &[], None))
+            .count();
+        assert!((2300..2700).contains(&nulled), "nulled {}", nulled);
+    }
+
+    #[test]
+    fn nulls_the_same_values_every_time() {
+        let args = fraction_args(json!("0.5"));
+        let first: Vec<bool> = (0..100)
+            .map(|i| should_null(&i.to_string(), &args, &[], None))
+            .collect();
+        let second: Vec<bool> = (0..100)
+            .map(|i| should_null(&i.to_string(), &args, &[], None))
+            .collect();
+        assert_eq!(first, second);
+    }
+
+    #[test]
+    fn fraction_must_be_between_0_and_1() {
+        assert_eq!(
+            parsed(&probabilistic_null(json!(1.5))).unwrap_err(),
+            "invalid args for ProbabilisticNull: 'fraction' must be a number between 0 and 1, not 1.5"
+        );
+    }
+
+    #[test]
+    fn knows_which_transformers_can_produce_nulls() {
+        let fixed_null = Transformer {
+            name: TransformerType::Fixed,
+            args: Some(HashMap::from([("value".to_string(), json!("\\N"))])),
+        };
+        let scramble = Transformer {
+            name: TransformerType::Scramble,
+            args: None,
+        };
+        assert!(can_produce_null(&parsed(&fixed_null).unwrap()));
+        assert!(can_produce_null(
+            &parsed(&Transformer::pipeline(vec![
+                scramble.clone(),
+                probabilistic_null(json!(0.1))
+            ]))
+            .unwrap()
+        ));
+        assert!(!can_produce_null(&parsed(&scramble).unwrap()));
+    }
+}
@@ -0,0 +1,453 @@
+use crate::parsers::locales::Locale;
+use rand::seq::SliceRandom;
+use rand::Rng;
+
+/// A kind of number within a country's numbering plan, e.g. mobile or London landline
+struct NumberType {
+    /// Prefixes of the national number (without the trunk prefix) that identify the type
+    starts_with: &'static [&'static str],
+    /// Prefixes reserved for use in fiction, the rest of the number is filled with random digits.
+    /// A `*` keeps the original digit in that position (e.g. a US area code)
+    fictitious: &'static [&'static str],
+}
+
+struct Country {
+    calling_code: &'static str,
+    trunk_prefix: &'static str,
+    number_types: &'static [NumberType],
+}
+
+// https://www.ofcom.org.uk/phones-telecoms-and-internet/information-for-industry/numbering/numbers-for-drama
+const GB: Country = Country {
+    calling_code: "44",
+    trunk_prefix: "0",
+    number_types: &[
+        NumberType {
+            starts_with: &["7"],
+            fictitious: &["7700900"],
+        },
+        NumberType {
+            starts_with: &["20"],
+            fictitious: &["2079460"],
+        },
+        NumberType {
+            starts_with: &["28"],
+            fictitious: &["2890180"],
+        },
+        NumberType {
+            starts_with: &["29"],
+            fictitious: &["2920180"],
+        },
+        NumberType {
+            starts_with: &["1"],
+            fictitious: &[
+                "1134960", "1144960", "1154960", "1164960", "1174960", "1184960", "1214960",
+                "1314960", "1414960", "1514960", "1614960", "1914960",
+            ],
+        },
+        NumberType {
+            starts_with: &["3"],
+            fictitious: &["3069990"],
+        },
+        NumberType {
+            starts_with: &["80"],
+            fictitious: &["8081570"],
+        },
+        NumberType {
+            starts_with: &["9"],
+            fictitious: &["9098790"],
+        },
+    ],
+};
+
+// 555-0100 to 555-0199 are fictitious in every area code, so the area code is kept
+const NANP: Country = Country {
+    calling_code: "1",
+    trunk_prefix: "1",
+    number_types: &[NumberType {
+        starts_with: &[""],
+        fictitious: &["***55501"],
+    }],
+};
+
+// https://www.arcep.fr/la-regulation/grands-dossiers-reseaux-fixes/la-numerotation.html
+const FR: Country = Country {
+    calling_code: "33",
+    trunk_prefix: "0",
+    number_types: &[
+        NumberType {
+            starts_with: &["6", "7"],
+            fictitious: &["639980"],
+        },
+        NumberType {
+            starts_with: &["1"],
+            fictitious: &["19900"],
+        },
+        NumberType {
+            starts_with: &["2"],
+            fictitious: &["26191"],
+        },
+        NumberType {
+            starts_with: &["3"],
+            fictitious: &["35301"],
+        },
+        NumberType {
+            starts_with: &["4"],
+            fictitious: &["46571"],
+        },
+        NumberType {
+            starts_with: &["5"],
+            fictitious: &["53649"],
+        },
+    ],
+};
+
+// https://www.acma.gov.au/phone-numbers-use-tv-shows-films-and-creative-works
+const AU: Country = Country {
+    calling_code: "61",
+    trunk_prefix: "0",
+    number_types: &[
+        NumberType {
+            starts_with: &["4"],
+            fictitious: &["491570"],
+        },
+        NumberType {
+            starts_with: &["2"],
+            fictitious: &["25550"],
+        },
+        NumberType {
+            starts_with: &["3"],
+            fictitious: &["37010"],
+        },
+        NumberType {
+            starts_with: &["7"],
+            fictitious: &["75550"],
+        },
+        NumberType {
+            starts_with: &["8"],
+            fictitious: &["87010"],
+        },
+    ],
+};
+
+// https://www.bundesnetzagentur.de/DE/Fachthemen/Telekommunikation/Nummerierung/Rufnummern/start.html
+// Only landlines have reserved blocks, one each in Berlin, Frankfurt, Hamburg, Cologne and Munich,
+// so other numbers (including mobiles) get one of those
+const DE: Country = Country {
+    calling_code: "49",
+    trunk_prefix: "0",
+    number_types: &[
+        NumberType {
+            starts_with: &["30"],
+            fictitious: &["3023125"],
+        },
+        NumberType {
+            starts_with: &["69"],
+            fictitious: &["6990009"],
+        },
+        NumberType {
+            starts_with: &["40"],
+            fictitious: &["4066969"],
+        },
+        NumberType {
+            starts_with: &["221"],
+            fictitious: &["2214710"],
+        },
+        NumberType {
+            starts_with: &["89"],
+            fictitious: &["8999998"],
+        },
+    ],
+};
+
+/// The countries with numbers reserved for fiction. Numbers from other countries can't be faked,
+/// as randomising their digits could give someone's real number
+const COUNTRIES: [Country; 5] = [GB, NANP, FR, AU, DE];
+
+/// The fewest digits a national number can have for us to treat the value as a phone number
+const MIN_NATIONAL_DIGITS: usize = 4;
+
+/// Why a value wasn't given a fictitious number
+#[derive(Debug, PartialEq, Eq)]
+pub enum NotFaked {
+    /// The value doesn't look like a phone number, or is too short for any reserved range
+    NotANumber,
+    /// No numbers are reserved for fiction in the country with this calling code
+    NoFictitiousRange(String),
+}
+
+/// Checks that numbers without a country calling code, which are assumed to be from the locale's
+/// country, can be faked
+pub fn validate(locale: Locale) -> Result<(), String> {
+    let calling_code = locale_calling_code(locale);
+    if COUNTRIES
+        .iter()
+        .any(|country| country.calling_code == calling_code)
+    {
+        Ok(())
+    } else {
+        Err(format!(
+            "no +{} phone numbers are reserved for fiction, so fake ones can't be made for the '{}' locale",
+            calling_code,
+            locale.name()
+        ))
+    }
+}
+
+/// Generates a fictitious phone number of the same country, type and length as `value`, keeping
+/// its formatting (spaces, dashes, parentheses etc.). Numbers without a country calling code are
+/// assumed to be from the locale's country. Numbers of a type with no reserved range of its own
+/// get another of their country's reserved ranges
+pub fn fake<R: Rng + ?Sized>(value: &str, locale: Locale, rng: &mut R) -> Result<String, NotFaked> {
+    let trimmed = value.trim_start();
+    let digits: String = value.chars().filter(char::is_ascii_digit).collect();
+
+    let (international_prefix, calling_code, rest) = if trimmed.starts_with('+') {
+        let length = calling_code_length(&digits);
+        let calling_code = digits.get(..length).ok_or(NotFaked::NotANumber)?;
+        ("", calling_code, &digits[length..])
+    } else if trimmed.starts_with("00") {
+        let length = calling_code_length(&digits[2..]);
+        let calling_code = digits.get(2..2 + length).ok_or(NotFaked::NotANumber)?;
+        ("00", calling_code, &digits[2 + length..])
+    } else {
+        let calling_code = locale_calling_code(locale);
+        ("", calling_code, digits.as_str())
+    };
+    let international = trimmed.starts_with('+') || !international_prefix.is_empty();
+
+    let country = COUNTRIES
+        .iter()
+        .find(|country| country.calling_code == calling_code);
+    let trunk_prefix = match country {
+        Some(country)
+            if !international
+                && rest.starts_with(country.trunk_prefix)
+                && (country.calling_code != "1" || rest.len() == 11) =>
+        {
+            country.trunk_prefix
+        }
+        None if !international && rest.starts_with('0') => "0",
+        _ => "",
+    };
+    let national = &rest[trunk_prefix.len()..];
+    if national.len() < MIN_NATIONAL_DIGITS {
+        return Err(NotFaked::NotANumber);
+    }
+
+    let country = country.ok_or_else(|| NotFaked::NoFictitiousRange(calling_code.to_string()))?;
+    let new_national = fake_national(country, national, rng).ok_or(NotFaked::NotANumber)?;
+    let new_digits = if international {
+        format!("{}{}{}", international_prefix, calling_code, new_national)
+    } else {
+        format!("{}{}", trunk_prefix, new_national)
+    };
+    Ok(reformat(value, &new_digits))
+}
+
+/// The reserved ranges for the number's type, or all of the country's fixed ones if its type
+/// doesn't have any, filled up to the length of the original with random digits
+fn fake_national<R: Rng + ?Sized>(
+    country: &Country,
+    national: &str,
+    rng: &mut R,
+) -> Option<String> {
+    let fictitious: Vec<&str> = match country.number_types.iter().find(|number_type| {
+        number_type
+            .starts_with
+            .iter()
+            .any(|prefix| national.starts_with(prefix))
+    }) {
+        Some(number_type) => number_type.fictitious.to_vec(),
+        None => country
+            .number_types
+            .iter()
+            .flat_map(|number_type| number_type.fictitious.iter().copied())
+            .filter(|prefix| !prefix.contains('*'))
+            .collect(),
+    };
+    let prefix: String = fictitious
+        .into_iter()
+        .filter(|prefix| prefix.len() < national.len())
+        .collect::<Vec<_>>()
+        .choose(rng)?
+        .chars()
+        .zip(national.chars())
+        .map(|(reserved, original)| if reserved == '*' { original } else { reserved })
+        .collect();
+
+    Some(format!(
+        "{}{}",
+        prefix,
+        random_digits(national.len() - prefix.len(), rng)
+    ))
+}
+
+fn random_digits<R: Rng + ?Sized>(count: usize, rng: &mut R) -> String {
+    (0..count)
+        .map(|_| char::from(b'0' + rng.gen_range(0..10)))
+        .collect()
+}
+
+/// Puts the new digits into the positions of the original digits, so everything else is unchanged
+fn reformat(original: &str, new_digits: &str) -> String {
+    let mut new_digits = new_digits.chars();
+    original
+        .chars()
+        .map(|c| {
+            if c.is_ascii_digit() {
+                new_digits.next().unwrap_or(c)
+            } else {
+                c
+            }
+        })
+        .collect()
+}
+
+/// Country calling codes are prefix free, so the length can be worked out from the first digits
+/// https://en.wikipedia.org/wiki/List_of_country_calling_codes
+fn calling_code_length(digits: &str) -> usize {
+    const TWO_DIGIT_CODES: [&str; 45] = [
+        "20", "27", "30", "31", "32", "33", "34", "36", "39", "40", "41", "43", "44", "45", "46",
+        "47", "48", "49", "51", "52", "53", "54", "55", "56", "57", "58", "60", "61", "62", "63",
+        "64", "65", "66", "81", "82", "84", "86", "90", "91", "92", "93", "94", "95", "98", "99",
+    ];
+    if digits.starts_with('1') || digits.starts_with('7') {
+        1
+    } else if TWO_DIGIT_CODES.iter().any(|code| digits.starts_with(code)) {
+        2
+    } else {
+        3
+    }
+}
+
+fn locale_calling_code(locale: Locale) -> &'static str {
+    match locale {
+        Locale::En => "1",
+        Locale::EnGb => "44",
+        Locale::DeDe => "49",
+        Locale::FrFr => "33",
+        Locale::PtBr => "55",
+        Locale::JaJp => "81",
+        Locale::ZhCn => "86",
+        Locale::ZhTw => "886",
+        Locale::ArSa => "966",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::SmallRng;
+    use rand::SeedableRng;
+
+    fn fake_number(value: &str, locale: Locale) -> String {
+        fake(value, locale, &mut SmallRng::seed_from_u64(1)).unwrap()
+    }
+
+    fn is_german_block(number: &str) -> bool {
+        ["3023125", "6990009", "4066969", "2214710", "8999998"]
+            .iter()
+            .any(|block| number.starts_with(block))
+    }
+
+    #[test]
+    fn uk_numbers_use_the_drama_range_for_their_type() {
+        let mobile = fake_number("+447822222222", Locale::En);
+        assert!(mobile.starts_with("+447700900"), "{}", mobile);
+        assert_eq!(mobile.len(), 13);
+
+        let london = fake_number("+44 20 7123 4567", Locale::En);
+        assert!(london.starts_with("+44 20 7946 0"), "{}", london);
+        assert_eq!(london.len(), 16);
+    }
+
+    #[test]
+    fn national_numbers_use_the_locales_country_and_keep_the_trunk_prefix() {
+        let mobile = fake_number("07822 222222", Locale::EnGb);
+        assert!(mobile.starts_with("07700 900"), "{}", mobile);
+
+        let paris = fake_number("01-42-68-53-00", Locale::FrFr);
+        assert!(paris.starts_with("01-99-00-"), "{}", paris);
+        assert_eq!(paris.len(), 14);
+    }
+
+    #[test]
+    fn us_numbers_keep_the_area_code_and_use_555_01xx() {
+        let number = fake_number("+1 (650) 513-0514", Locale::En);
+        assert!(number.starts_with("+1 (650) 555-01"), "{}", number);
+        assert_eq!(number.len(), 17);
+        assert!(fake_number("(650) 513-0514", Locale::En).starts_with("(650) 555-01"));
+    }
+
+    #[test]
+    fn german_landlines_use_the_reserved_blocks() {
+        let berlin = fake_number("+49 30 12345678", Locale::En);
+        assert!(berlin.starts_with("+49 30 23125"), "{}", berlin);
+        assert_eq!(berlin.len(), 15);
+
+        let international_prefix = fake_number("0049 30 12345678", Locale::En);
+        assert!(international_prefix.starts_with("0049 30 23125"));
+
+        let cologne = fake_number("0221 98765432", Locale::DeDe);
+        assert!(cologne.starts_with("0221 4710"), "{}", cologne);
+
+        let dresden = fake_number("0351 1234567", Locale::DeDe);
+        assert!(
+            is_german_block(&dresden.replace(' ', "")[1..]),
+            "{}",
+            dresden
+        );
+    }
+
+    #[test]
+    fn numbers_of_a_type_without_a_reserved_range_keep_their_country_and_format() {
+        let mobile = fake_number("+49 151 23456789", Locale::En);
+        assert!(mobile.starts_with("+49 "), "{}", mobile);
+        assert_eq!(mobile.len(), "+49 151 23456789".len());
+        assert_eq!(mobile.find(' '), Some(3));
+        assert_eq!(mobile.rfind(' '), Some(7));
+        assert!(is_german_block(&mobile.replace(' ', "")[3..]), "{}", mobile);
+    }
+
+    #[test]
+    fn numbers_from_countries_without_a_reserved_range_are_not_faked() {
+        for (value, locale) in [
+            ("+81 90-1234-5678", Locale::EnGb),
+            ("090-1234-5678", Locale::JaJp),
+        ] {
+            assert_eq!(
+                fake(value, locale, &mut SmallRng::seed_from_u64(1)),
+                Err(NotFaked::NoFictitiousRange("81".to_string()))
+            );
+        }
+    }
+
+    #[test]
+    fn only_locales_with_a_reserved_range_are_valid() {
+        assert!(validate(Locale::DeDe).is_ok());
+        assert_eq!(
+            validate(Locale::JaJp).unwrap_err(),
+            "no +81 phone numbers are reserved for fiction, so fake ones can't be made for the 'ja_jp' locale"
+        );
+    }
+
+    #[test]
+    fn values_which_are_not_numbers_are_not_faked() {
+        assert_eq!(
+            fake("n/a", Locale::En, &mut SmallRng::seed_from_u64(1)),
+            Err(NotFaked::NotANumber)
+        );
+        assert_eq!(
+            fake("+44", Locale::En, &mut SmallRng::seed_from_u64(1)),
+            Err(NotFaked::NotANumber)
+        );
+    }
+
+    #[test]
+    fn works_out_calling_code_lengths() {
+        assert_eq!(calling_code_length("16505130514"), 1);
+        assert_eq!(calling_code_length("447822222222"), 2);
+        assert_eq!(calling_code_length("353861234567"), 3);
+    }
+}
@@ -0,0 +1,217 @@
+use lazy_static::lazy_static;
+use regex::{Match, Regex};
+
+/// A named regex for a common kind of PII, with an optional check that a match really is what it
+/// looks like (e.g. that a long run of digits passes the Luhn check before calling it a card number)
+#[derive(Debug)]
+pub struct PiiPattern {
+    pub name: &'static str,
+    pub regex: Regex,
+    validator: Option<fn(&str) -> bool>,
+}
+
+impl PiiPattern {
+    fn new(name: &'static str, pattern: &str, validator: Option<fn(&str) -> bool>) -> Self {
+        PiiPattern {
+            name,
+            regex: Regex::new(pattern).unwrap(),
+            validator,
+        }
+    }
+
+    pub fn find_iter<'a>(&'a self, text: &'a str) -> impl Iterator<Item = Match<'a>> + 'a {
+        self.regex
+            .find_iter(text)
+            .filter(move |m| self.validator.map_or(true, |is_valid| is_valid(m.as_str())))
+    }
+}
+
+// Card numbers and IBANs come before phone numbers, so that long runs of digits aren't mistaken
+// for a phone number when patterns are applied in this order
+lazy_static! {
+    static ref PATTERNS: Vec<PiiPattern> = vec![
+        PiiPattern::new(
+            "email",
+            r"[A-Za-z0-9._%+-]+@[A-Za-z0-9-]+(?:\.[A-Za-z0-9-]+)*\.[A-Za-z]{2,}",
+            None,
+        ),
+        PiiPattern::new(
+            "card_number",
+            r"\b\d(?:[ -]?\d){12,18}\b",
+            Some(is_luhn_valid),
+        ),
+        PiiPattern::new(
+            "iban",
+            r"\b[A-Z]{2}\d{2}(?: ?[A-Z0-9]{4}){2,7}(?: ?[A-Z0-9]{1,3})?\b",
+            Some(is_valid_iban),
+        ),
+        PiiPattern::new(
+            "uk_national_insurance_number",
+            r"(?i)\b[A-CEGHJ-PR-TW-Z][A-CEGHJ-NPR-TW-Z] ?\d{2} ?\d{2} ?\d{2} ?[A-D]\b",
+            None,
+        ),
+        PiiPattern::new(
+            "phone_number",
+            r"(?:\+\d{1,3}[ .-]?(?:\(0\)[ .-]?)?|\b0)\d{2,5}[ .-]?\d{3,4}[ .-]?\d{3,4}\b|\(\d{3}\) ?\d{3}[ .-]\d{4}\b",
+            None,
+        ),
+        PiiPattern::new(
+            "uk_postcode",
+            r"(?i)\b(?:[A-Z]{1,2}\d[A-Z\d]?|GIR) ?\d[ABD-HJLNP-UW-Z]{2}\b",
+            None,
+        ),
+        PiiPattern::new(
+            "url_with_query",
+            r"(?i)\bhttps?://[^\s/?#]+[^\s?#]*\?[^\s#]*[^\s#.,;:!?)\]]",
+            None,
+        ),
+    ];
+}
+
+pub fn builtin(name: &str) -> Option<&'static PiiPattern> {
+    PATTERNS.iter().find(|pattern| pattern.name == name)
+}
+
+pub fn builtin_names() -> Vec<&'static str> {
+    PATTERNS.iter().map(|pattern| pattern.name).collect()
+}
+
+pub fn is_luhn_valid(value: &str) -> bool {
+    let digits: Vec<u32> = value.chars().filter_map(|c| c.to_digit(10)).collect();
+    if digits.len() < 2 {
+        return false;
+    }
+    let sum: u32 = digits
+        .iter()
+        .rev()
+        .enumerate()
+        .map(|(i, digit)| {
+            if i % 2 == 1 {
+                let doubled = digit * 2;
+                if doubled > 9 {
+                    doubled - 9
+                } else {
+                    doubled
+                }
+            } else {
+                *digit
+            }
+        })
+        .sum();
+    sum % 10 == 0
+}
+
+pub fn is_valid_iban(value: &str) -> bool {
+    let compact: String = value.chars().filter(|c| !c.is_whitespace()).collect();
+    if compact.len() < 15 || compact.len() > 34 || !compact.is_ascii() {
+        return false;
+    }
+    let (country_and_check, bban) = compact.split_at(4);
+    iban_remainder(&format!("{}{}", bban, country_and_check)) == Some(1)
+}
+
+/// The ISO 7064 mod 97-10 remainder used for IBAN check digits, with letters expanded to 10-35
+pub fn iban_remainder(rearranged: &str) -> Option<u32> {
+    rearranged.chars().try_fold(0u32, |remainder, c| {
+        let value = c.to_digit(36)?;
+        let remainder = if value > 9 {
+            remainder * 100 + value
+        } else {
+            remainder * 10 + value
+        };
+        Some(remainder % 97)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matches(name: &str, text: &str) -> Vec<String> {
+        builtin(name)
+            .unwrap()
+            .find_iter(text)
+            .map(|m| m.as_str().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn finds_emails() {
+        assert_eq!(
+            matches(
+                "email",
+                "contact peter.parker+spidey@daily-bugle.co.uk today"
+            ),
+            vec!["peter.parker+spidey@daily-bugle.co.uk"]
+        );
+    }
+
+    #[test]
+    fn finds_phone_numbers() {
+        assert_eq!(
+            matches(
+                "phone_number",
+                "call +44 7700 900123 or 020 7946 0123 or (650) 513-0514"
+            ),
+            vec!["+44 7700 900123", "020 7946 0123", "(650) 513-0514"]
+        );
+    }
+
+    #[test]
+    fn finds_national_insurance_numbers() {
+        assert_eq!(
+            matches(
+                "uk_national_insurance_number",
+                "NI: JR 55 55 55 E? no, AB123456C"
+            ),
+            vec!["AB123456C"]
+        );
+    }
+
+    #[test]
+    fn finds_uk_postcodes() {
+        assert_eq!(
+            matches("uk_postcode", "lives at NW5 3QQ and works in sw1a1aa"),
+            vec!["NW5 3QQ", "sw1a1aa"]
+        );
+    }
+
+    #[test]
+    fn finds_urls_with_query_strings() {
+        assert_eq!(
+            matches(
+                "url_with_query",
+                "reset at https://example.com/reset?token=abc123. Or https://example.com/help"
+            ),
+            vec!["https://example.com/reset?token=abc123"]
+        );
+    }
+
+    #[test]
+    fn only_finds_card_numbers_that_pass_the_luhn_check() {
+        assert_eq!(
+            matches(
+                "card_number",
+                "paid with 4242 4242 4242 4242, order 4242 4242 4242 4243"
+            ),
+            vec!["4242 4242 4242 4242"]
+        );
+    }
+
+    #[test]
+    fn only_finds_ibans_with_valid_check_digits() {
+        assert_eq!(
+            matches(
+                "iban",
+                "pay GB82 WEST 1234 5698 7654 32 not GB83 WEST 1234 5698 7654 32"
+            ),
+            vec!["GB82 WEST 1234 5698 7654 32"]
+        );
+    }
+
+    #[test]
+    fn luhn_check() {
+        assert!(is_luhn_valid("79927398713"));
+        assert!(!is_luhn_valid("79927398710"));
+    }
+}
@@ -0,0 +1,300 @@
+use crate::parsers::data_row;
+use crate::parsers::regex_replace::Replacement;
+use crate::parsers::strategy_structs::TransformerType;
+use crate::parsers::transformer;
+use crate::parsers::transformer_args::{Args, PadSide, ParsedTransformer};
+use crate::parsers::types::Type;
+use rand::rngs::SmallRng;
+use std::borrow::Cow;
+
+pub const STRING_POST_PROCESSORS: [TransformerType; 7] = [
+    TransformerType::Lowercase,
+    TransformerType::Pad,
+    TransformerType::Prefix,
+    TransformerType::Replace,
+    TransformerType::Suffix,
+    TransformerType::Truncate,
+    TransformerType::Uppercase,
+];
+
+pub fn is_string_post_processor(transformer: &ParsedTransformer) -> bool {
+    STRING_POST_PROCESSORS.contains(&transformer.name)
+}
+
+/// The string post processors only make sense for text columns, e.g. adding a prefix to an
+/// integer column would make the dump impossible to restore. `data_type` is the column's type as
+/// given by `db_schema::column_types`, and steps nested in `Conditional` and `RegexReplace` are
+/// checked as well as those of a pipeline
+pub fn check_column_type(transformer: &ParsedTransformer, data_type: &str) -> Result<(), String> {
+    match post_processors(transformer).first() {
+        Some(step) if !is_text(data_type) => Err(format!(
+            "{:?} can only be used on text columns, not {}",
+            step.name, data_type
+        )),
+        _ => Ok(()),
+    }
+}
+
+fn post_processors(transformer: &ParsedTransformer) -> Vec<&ParsedTransformer> {
+    let nested: Vec<&ParsedTransformer> = match &transformer.args {
+        Args::Pipeline(steps) => steps.iter().collect(),
+        Args::Conditional(args) => args
+            .cases
+            .iter()
+            .map(|case| &case.transformer)
+            .chain([args.default.as_ref()])
+            .collect(),
+        Args::RegexReplace(args) => args
+            .patterns
+            .iter()
+            .filter_map(|rule| match &rule.replace_with {
+                Replacement::Transformer(nested) => Some(nested),
+                _ => None,
+            })
+            .collect(),
+        _ if is_string_post_processor(transformer) => return vec![transformer],
+        _ => Vec::new(),
+    };
+    nested.into_iter().flat_map(post_processors).collect()
+}
+
+/// Arrays and user defined types (e.g. `citext`) can't be told apart from their `data_type`, so
+/// are let through
+fn is_text(data_type: &str) -> bool {
+    [
+        "text",
+        "character varying",
+        "character",
+        "ARRAY",
+        "USER-DEFINED",
+    ]
+    .contains(&data_type)
+}
+
+pub fn transform<'value>(
+    rng: &mut SmallRng,
+    value: &'value str,
+    column_type: &Type,
+    steps: &[ParsedTransformer],
+    table_name: &str,
+    column_values: &[(String, String)],
+    global_salt: Option<&str>,
+) -> Cow<'value, str> {
+    let mut current = value.to_string();
+    for step in steps.iter() {
+        current = transformer::transform(
+            rng,
+            &current,
+            column_type,
+            step,
+            table_name,
+            column_values,
+            global_salt,
+        )
+        .into_owned();
+    }
+    Cow::from(current)
+}
+
+/// Applies one of the string post processors to a COPY encoded value
+pub fn post_process(value: &str, transformer: &ParsedTransformer, table_name: &str) -> String {
+    let text = data_row::decode(value);
+    let processed = apply(&text, transformer)
+        .unwrap_or_else(|e| panic!("Transformer in table: '{}' has {}", table_name, e));
+    data_row::encode(&processed).into_owned()
+}
+
+fn apply(text: &str, transformer: &ParsedTransformer) -> Result<String, String> {
+    let processed = match (&transformer.name, &transformer.args) {
+        (TransformerType::Lowercase, _) => text.to_lowercase(),
+        (TransformerType::Uppercase, _) => text.to_uppercase(),
+        (TransformerType::Truncate, Args::Truncate(args)) => {
+            text.chars().take(args.length).collect()
+        }
+        (TransformerType::Pad, Args::Pad(args)) => {
+            let padding: String = std::iter::repeat(args.with)
+                .take(args.length.saturating_sub(text.chars().count()))
+                .collect();
+            match args.side {
+                PadSide::Left => format!("{}{}", padding, text),
+                PadSide::Right => format!("{}{}", text, padding),
+            }
+        }
+        (TransformerType::Prefix, Args::Affix(args)) => format!("{}{}", args.value, text),
+        (TransformerType::Suffix, Args::Affix(args)) => format!("{}{}", text, args.value),
+        (TransformerType::Replace, Args::Replace(args)) => text.replace(&args.from, &args.to),
+        (other, _) => return Err(format!("{:?} is not a post processor", other)),
+    };
+    Ok(processed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parsers::strategy_structs::Transformer;
+    use crate::parsers::types::SubType;
+    use serde_json::json;
+    use std::collections::HashMap;
+
+    fn post_processor(name: TransformerType, args: serde_json::Value) -> Transformer {
+        Transformer {
+            name,
+            args: serde_json::from_value::<Option<HashMap<_, _>>>(args).unwrap(),
+        }
+    }
+
+    fn parsed(transformer: &Transformer) -> ParsedTransformer {
+        ParsedTransformer::parse(transformer).unwrap()
+    }
+
+    #[test]
+    fn post_processors() {
+        let cases = [
+            (TransformerType::Uppercase, json!(null), "Peter", "PETER"),
+            (TransformerType::Lowercase, json!(null), "Peter", "peter"),
+            (
+                TransformerType::Truncate,
+                json!({"length": 3}),
+                "Peter",
+                "Pet",
+            ),
+            (
+                TransformerType::Pad,
+                json!({"length": 6, "with": "0"}),
+                "123",
+                "000123",
+            ),
+            (
+                TransformerType::Pad,
+                json!({"length": 6, "side": "right", "with": "."}),
+                "abc",
+                "abc...",
+            ),
+            (
+                TransformerType::Prefix,
+                json!({"value": "x-"}),
+                "abc",
+                "x-abc",
+            ),
+            (
+                TransformerType::Suffix,
+                json!({"value": "-x"}),
+                "abc",
+                "abc-x",
+            ),
+            (
+                TransformerType::Replace,
+                json!({"from": "a", "to": "o"}),
+                "banana",
+                "bonono",
+            ),
+        ];
+
+        for (name, args, value, expected) in cases {
+            let transformer = parsed(&post_processor(name, args));
+            assert_eq!(post_process(value, &transformer, "public.users"), expected);
+        }
+    }
+
+    #[test]
+    fn post_processors_work_on_decoded_text() {
+        let transformer = parsed(&post_processor(TransformerType::Uppercase, json!(null)));
+        assert_eq!(
+            post_process(r"line one\nline two", &transformer, "public.users"),
+            r"LINE ONE\nLINE TWO"
+        );
+    }
+
+    #[test]
+    fn string_post_processors_are_only_allowed_on_text_columns() {
+        let pipeline = parsed(&Transformer::pipeline(vec![
+            Transformer {
+                name: TransformerType::Scramble,
+                args: None,
+            },
+            post_processor(TransformerType::Truncate, json!({"length": 3})),
+        ]));
+        assert!(check_column_type(&pipeline, "character varying").is_ok());
+        assert!(check_column_type(&pipeline, "text").is_ok());
+        assert_eq!(
+            check_column_type(&pipeline, "integer").unwrap_err(),
+            "Truncate can only be used on text columns, not integer"
+        );
+        let scramble = parsed(&Transformer {
+            name: TransformerType::Scramble,
+            args: None,
+        });
+        assert!(check_column_type(&scramble, "integer").is_ok());
+    }
+
+    #[test]
+    fn post_processors_nested_in_other_transformers_are_checked() {
+        let conditional = parsed(&post_processor(
+            TransformerType::Conditional,
+            json!({
+                "cases": [{
+                    "when": {"column": "kind", "equals": "a"},
+                    "transformer": [{"name": "Scramble"}, {"name": "Uppercase"}]
+                }],
+                "default": {"name": "Identity"}
+            }),
+        ));
+        assert_eq!(
+            check_column_type(&conditional, "jsonb").unwrap_err(),
+            "Uppercase can only be used on text columns, not jsonb"
+        );
+
+        let regex_replace = parsed(&post_processor(
+            TransformerType::RegexReplace,
+            json!({"patterns": [{
+                "regex": "[0-9]+",
+                "replace_with": {"transformer": {"name": "Prefix", "args": {"value": "x"}}}
+            }]}),
+        ));
+        assert_eq!(
+            check_column_type(&regex_replace, "bigint").unwrap_err(),
+            "Prefix can only be used on text columns, not bigint"
+        );
+    }
+
+    #[test]
+    fn a_list_of_transformers_is_read_as_a_pipeline_and_written_back_as_a_list() {
+        let in_file = json!([
+            {"name": "FakeEmail"},
+            {"name": "Truncate", "args": {"length": 10}}
+        ]);
+        let transformer: Transformer = serde_json::from_value(in_file.clone()).unwrap();
+        assert_eq!(transformer.name, TransformerType::Pipeline);
+        assert_eq!(
+            transformer,
+            Transformer::pipeline(vec![
+                Transformer {
+                    name: TransformerType::FakeEmail,
+                    args: None
+                },
+                post_processor(TransformerType::Truncate, json!({"length": 10})),
+            ])
+        );
+        assert_eq!(serde_json::to_value(&transformer).unwrap(), in_file);
+    }
+
+    #[test]
+    fn pipelines_apply_each_step_in_order() {
+        let pipeline = parsed(&Transformer::pipeline(vec![
+            post_processor(TransformerType::Truncate, json!({"length": 3})),
+            post_processor(TransformerType::Uppercase, json!(null)),
+            post_processor(TransformerType::Suffix, json!({"value": "!"})),
+        ]));
+        let mut rng = crate::parsers::rng::get();
+        let transformed = transformer::transform(
+            &mut rng,
+            "peter",
+            &Type::single_value(SubType::Character),
+            &pipeline,
+            "public.users",
+            &[],
+            None,
+        );
+        assert_eq!(transformed, "PET!");
+    }
+}
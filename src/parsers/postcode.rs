@@ -0,0 +1,202 @@
+use lazy_static::lazy_static;
+use rand::seq::SliceRandom;
+use rand::Rng;
+use regex::Regex;
+use schemars::JsonSchema;
+use serde::Deserialize;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, JsonSchema)]
+#[serde(try_from = "String")]
+pub enum Mode {
+    /// The postcode district e.g. `SW1A 1AA` becomes `SW1`, which isn't a whole postcode
+    District,
+    /// The outward code with a random inward code e.g. `SW1A 1AA` becomes `SW1A 7XN`
+    #[default]
+    KeepOutward,
+    /// A random postcode in a valid format
+    Fake,
+    /// The first three digits of a US ZIP code, with the rest randomised
+    UsZip,
+}
+
+const MODES: [(&str, Mode); 4] = [
+    ("district", Mode::District),
+    ("keep_outward", Mode::KeepOutward),
+    ("fake", Mode::Fake),
+    ("us_zip", Mode::UsZip),
+];
+
+// The letters which can be used in an inward code
+const INWARD_LETTERS: &[u8] = b"ABDEFGHJLNPQRSTUWXYZ";
+
+const AREAS: [&str; 24] = [
+    "AB", "B", "BS", "CB", "CF", "E", "EC", "EH", "G", "L", "LS", "M", "N", "NE", "NG", "NW", "OX",
+    "S", "SE", "SO", "SW", "W", "WC", "YO",
+];
+
+lazy_static! {
+    static ref UK_POSTCODE: Regex =
+        Regex::new(r"^([A-Z]{1,2}[0-9][A-Z0-9]?)(\s*)([0-9][A-Z]{2})$").unwrap();
+}
+
+/// The `mode` arg
+impl TryFrom<String> for Mode {
+    type Error = String;
+
+    fn try_from(name: String) -> Result<Self, Self::Error> {
+        MODES
+            .iter()
+            .find(|(mode_name, _)| *mode_name == name)
+            .map(|(_, mode)| *mode)
+            .ok_or_else(|| {
+                format!(
+                    "unknown postcode mode '{}', expected one of: {}",
+                    name,
+                    MODES.map(|(mode_name, _)| mode_name).join(", ")
+                )
+            })
+    }
+}
+
+/// Values which aren't postcodes get a random one (or the district of one), so nothing of the
+/// original is kept
+pub fn fake<R: Rng + ?Sized>(value: &str, mode: Mode, rng: &mut R) -> String {
+    let normalised = value.trim().to_uppercase();
+    let parsed = UK_POSTCODE
+        .captures(&normalised)
+        .map(|captures| (captures[1].to_string(), !captures[2].is_empty()));
+
+    match (mode, parsed) {
+        (Mode::UsZip, _) => us_zip(value, rng),
+        (Mode::District, Some((outward, _))) => district(&outward),
+        (Mode::District, None) => random_outward(rng),
+        (Mode::KeepOutward, Some((outward, has_space))) => with_inward(&outward, has_space, rng),
+        (Mode::KeepOutward, None) | (Mode::Fake, None) => random_postcode(true, rng),
+        (Mode::Fake, Some((_, has_space))) => random_postcode(has_space, rng),
+    }
+}
+
+fn district(outward: &str) -> String {
+    // A trailing letter is the sub-district e.g. the `A` of `SW1A`
+    match outward.strip_suffix(|c: char| c.is_ascii_alphabetic()) {
+        Some(district) if outward.len() > 2 => district.to_string(),
+        _ => outward.to_string(),
+    }
+}
+
+fn with_inward<R: Rng + ?Sized>(outward: &str, has_space: bool, rng: &mut R) -> String {
+    let inward = format!(
+        "{}{}{}",
+        rng.gen_range(0..10),
+        char::from(*INWARD_LETTERS.choose(rng).unwrap()),
+        char::from(*INWARD_LETTERS.choose(rng).unwrap())
+    );
+    let separator = if has_space { " " } else { "" };
+    format!("{}{}{}", outward, separator, inward)
+}
+
+fn random_postcode<R: Rng + ?Sized>(has_space: bool, rng: &mut R) -> String {
+    let outward = random_outward(rng);
+    with_inward(&outward, has_space, rng)
+}
+
+fn random_outward<R: Rng + ?Sized>(rng: &mut R) -> String {
+    format!("{}{}", AREAS.choose(rng).unwrap(), rng.gen_range(1..10))
+}
+
+/// Keeps the first three digits (which identify a region) and randomises the rest, including any
+/// ZIP+4 extension, keeping everything that isn't a digit
+fn us_zip<R: Rng + ?Sized>(value: &str, rng: &mut R) -> String {
+    let mut seen = 0;
+    value
+        .chars()
+        .map(|c| {
+            if !c.is_ascii_digit() {
+                return c;
+            }
+            seen += 1;
+            if seen <= 3 {
+                c
+            } else {
+                char::from(b'0' + rng.gen_range(0..10))
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::SmallRng;
+    use rand::SeedableRng;
+
+    fn fake_postcode(value: &str, mode: Mode) -> String {
+        fake(value, mode, &mut SmallRng::seed_from_u64(1))
+    }
+
+    #[test]
+    fn district_mode_drops_the_inward_code_and_sub_district() {
+        assert_eq!(fake_postcode("NW5 3QQ", Mode::District), "NW5");
+        assert_eq!(fake_postcode("sw1a 1aa", Mode::District), "SW1");
+        assert_eq!(fake_postcode("M11AE", Mode::District), "M1");
+        assert_eq!(fake_postcode("NW10 6TB", Mode::District), "NW10");
+    }
+
+    #[test]
+    fn values_that_are_not_postcodes_get_a_fake_one() {
+        let district = fake_postcode("Ürümqi 830000", Mode::District);
+        assert!(
+            AREAS
+                .iter()
+                .any(|area| district.len() == area.len() + 1 && district.starts_with(area)),
+            "{}",
+            district
+        );
+        for mode in [Mode::KeepOutward, Mode::Fake] {
+            let postcode = fake_postcode("Ürümqi 830000", mode);
+            assert!(UK_POSTCODE.is_match(&postcode), "{}", postcode);
+        }
+    }
+
+    #[test]
+    fn the_default_mode_gives_a_whole_postcode_in_the_same_area() {
+        let postcode = fake_postcode("NW10 6TB", Mode::default());
+        assert!(postcode.starts_with("NW10 "), "{}", postcode);
+        assert!(UK_POSTCODE.is_match(&postcode));
+    }
+
+    #[test]
+    fn keep_outward_mode_randomises_the_inward_code() {
+        let postcode = fake_postcode("SW1A 1AA", Mode::KeepOutward);
+        assert!(postcode.starts_with("SW1A "), "{}", postcode);
+        assert!(UK_POSTCODE.is_match(&postcode));
+        assert!(!fake_postcode("SW1A1AA", Mode::KeepOutward).contains(' '));
+    }
+
+    #[test]
+    fn fake_mode_generates_valid_postcodes() {
+        for seed in 0..100 {
+            let postcode = fake("NW5 3QQ", Mode::Fake, &mut SmallRng::seed_from_u64(seed));
+            assert!(UK_POSTCODE.is_match(&postcode), "{}", postcode);
+            assert!(!postcode[postcode.len() - 2..]
+                .chars()
+                .any(|c| "CIKMOV".contains(c)));
+        }
+    }
+
+    #[test]
+    fn us_zip_mode_keeps_the_first_three_digits() {
+        let zip = fake_postcode("94107-1234", Mode::UsZip);
+        assert!(zip.starts_with("941"), "{}", zip);
+        assert_eq!(zip.len(), 10);
+        assert_eq!(&zip[5..6], "-");
+    }
+
+    #[test]
+    fn rejects_unknown_modes() {
+        assert_eq!(
+            Mode::try_from("area".to_string()).unwrap_err(),
+            "unknown postcode mode 'area', expected one of: district, keep_outward, fake, us_zip"
+        );
+    }
+}
@@ -0,0 +1,358 @@
+use crate::parsers::data_row;
+use crate::parsers::email::{self, EmailArgs};
+use crate::parsers::financial;
+use crate::parsers::locales::{self, Locale};
+use crate::parsers::national_identity_number::{self, Format};
+use crate::parsers::network;
+use crate::parsers::phone_number::{self, NotFaked};
+use crate::parsers::pii_patterns::{self, PiiPattern};
+use crate::parsers::postcode::{self, Mode};
+use crate::parsers::regex_replace;
+use crate::parsers::transformer;
+use crate::parsers::transformer_args;
+use rand::rngs::SmallRng;
+use rand::{Rng, SeedableRng};
+use schemars::JsonSchema;
+use serde::de::Error;
+use serde::{Deserialize, Deserializer};
+use std::ops::Range;
+
+/// The built in patterns looked for by default, in the order they are tried. URLs come first so
+/// that the emails and numbers in their query strings are replaced along with them
+const DEFAULT_DETECTORS: [&str; 7] = [
+    "url_with_query",
+    "email",
+    "card_number",
+    "iban",
+    "uk_national_insurance_number",
+    "phone_number",
+    "uk_postcode",
+];
+
+const DEFAULT_MARKER: &str = "[REDACTED]";
+
+/// Names shorter than this aren't looked for, as they'd match initials and parts of other words
+const MIN_NAME_LENGTH: usize = 2;
+
+#[derive(Clone, Debug, Default, Deserialize, JsonSchema, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ReplaceWith {
+    /// Replace what was found with the `marker` e.g. `[REDACTED]`
+    #[default]
+    Marker,
+    /// Replace what was found with a fake value of the same kind
+    Fake,
+}
+
+#[derive(Clone, Debug, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct RedactArgs {
+    /// The built in patterns to look for, looked up when the strategy file is loaded
+    #[serde(default = "default_detectors", deserialize_with = "detectors")]
+    #[schemars(with = "Vec<String>")]
+    pub detectors: Vec<&'static PiiPattern>,
+    /// Names to look for in every row
+    #[serde(default)]
+    pub names: Vec<String>,
+    /// Columns of the same row holding names to look for, e.g. `first_name`
+    #[serde(default)]
+    pub name_columns: Vec<String>,
+    /// Whether what is found is replaced with the `marker` or a fake value
+    #[serde(default)]
+    pub replace_with: ReplaceWith,
+    /// The text to replace what is found with
+    #[serde(default = "default_marker")]
+    pub marker: String,
+    /// Always give the same fake values for the same text
+    #[serde(default, deserialize_with = "transformer_args::flag")]
+    #[schemars(schema_with = "transformer_args::flag_schema")]
+    pub deterministic: bool,
+    /// The locale of the fake values
+    pub locale: Option<Locale>,
+}
+
+fn default_detectors() -> Vec<&'static PiiPattern> {
+    DEFAULT_DETECTORS
+        .iter()
+        .filter_map(|name| pii_patterns::builtin(name))
+        .collect()
+}
+
+fn default_marker() -> String {
+    DEFAULT_MARKER.to_string()
+}
+
+fn detectors<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Vec<&'static PiiPattern>, D::Error> {
+    Vec::<String>::deserialize(deserializer)?
+        .iter()
+        .map(|detector| {
+            pii_patterns::builtin(detector).ok_or_else(|| {
+                D::Error::custom(format!(
+                    "unknown detector '{}', expected one of: {}",
+                    detector,
+                    pii_patterns::builtin_names().join(", ")
+                ))
+            })
+        })
+        .collect()
+}
+
+/// Checks that the name columns are in the table
+pub fn validate(args: &RedactArgs, column_names: &[&str]) -> Result<(), String> {
+    match args
+        .name_columns
+        .iter()
+        .find(|column| !column_names.contains(&column.as_str()))
+    {
+        Some(column) => Err(format!("'name_columns' has unknown column '{}'", column)),
+        None => Ok(()),
+    }
+}
+
+/// Replaces the PII found in free text, leaving the rest of the text as it is
+pub fn redact(
+    rng: &mut SmallRng,
+    value: &str,
+    args: &RedactArgs,
+    column_values: &[(String, String)],
+    global_salt: Option<&str>,
+) -> String {
+    let text = data_row::decode(value);
+    let mut spans: Vec<(Range<usize>, usize)> = Vec::new();
+    for (index, detector) in args.detectors.iter().enumerate() {
+        regex_replace::add_spans(
+            &mut spans,
+            detector.find_iter(&text).map(|m| m.range()),
+            index,
+        );
+    }
+    // Names are looked for last, so a name that is part of an email is replaced with the email
+    let names_index = args.detectors.len();
+    for name in names(args, column_values) {
+        regex_replace::add_spans(&mut spans, find_name(&text, &name), names_index);
+    }
+    if spans.is_empty() {
+        return value.to_string();
+    }
+    spans.sort_by_key(|(range, _)| range.start);
+
+    let locale = args.locale.unwrap_or(Locale::En);
+    let mut redacted = String::with_capacity(text.len());
+    let mut last_end = 0;
+    for (range, index) in spans {
+        redacted.push_str(&text[last_end..range.start]);
+        let found = &text[range.clone()];
+        match args.replace_with {
+            ReplaceWith::Marker => redacted.push_str(&args.marker),
+            ReplaceWith::Fake => {
+                // Deterministic fakes are seeded from what was found, so the same email or name
+                // gets the same fake wherever it appears
+                let mut found_rng = if args.deterministic {
+                    transformer::get_faker_rng(found, None, global_salt)
+                } else {
+                    SmallRng::seed_from_u64(rng.gen())
+                };
+                let detector = args.detectors.get(index).map(|detector| detector.name);
+                match fake(detector, found, locale, global_salt, &mut found_rng) {
+                    Some(fake) => redacted.push_str(&fake),
+                    None => redacted.push_str(&args.marker),
+                }
+            }
+        }
+        last_end = range.end;
+    }
+    redacted.push_str(&text[last_end..]);
+
+    data_row::encode(&redacted).into_owned()
+}
+
+/// The names to look for, from the `names` arg and the original values of the `name_columns`.
+/// Full names are split so that e.g. the first name on its own is found too, and longer names
+/// come first so that the full name is replaced in one go
+fn names(args: &RedactArgs, column_values: &[(String, String)]) -> Vec<String> {
+    let column_names = column_values
+        .iter()
+        .filter(|(column, value)| args.name_columns.contains(column) && value != "\\N")
+        .map(|(_, value)| data_row::decode(value).into_owned());
+
+    let mut names: Vec<String> = args
+        .names
+        .iter()
+        .cloned()
+        .chain(column_names)
+        .flat_map(|name| {
+            let mut parts: Vec<String> = name.split_whitespace().map(String::from).collect();
+            if parts.len() > 1 {
+                parts.push(name.trim().to_string());
+            }
+            parts
+        })
+        .filter(|name| name.chars().count() >= MIN_NAME_LENGTH)
+        .collect();
+    names.sort_by(|a, b| b.len().cmp(&a.len()).then_with(|| a.cmp(b)));
+    names.dedup();
+    names
+}
+
+/// Where `name` appears in `text` as a whole word, ignoring case
+fn find_name(text: &str, name: &str) -> Vec<Range<usize>> {
+    let is_word_char = |c: Option<char>| c.is_some_and(char::is_alphanumeric);
+    text.char_indices()
+        .map(|(start, _)| start..start + name.len())
+        .filter(|range| {
+            text.get(range.clone())
+                .is_some_and(|candidate| candidate.eq_ignore_ascii_case(name))
+                && !is_word_char(text[..range.start].chars().next_back())
+                && !is_word_char(text[range.end..].chars().next())
+        })
+        .collect()
+}
+
+/// A fake value of the same kind as what was found, or a fake name when there's no detector.
+/// `None` for phone numbers from a country without numbers reserved for fiction, which get the
+/// marker instead
+fn fake(
+    detector: Option<&str>,
+    found: &str,
+    locale: Locale,
+    global_salt: Option<&str>,
+    rng: &mut SmallRng,
+) -> Option<String> {
+    let fake = match detector {
+        Some("url_with_query") => network::url(found, rng),
+        Some("email") => email::fake(found, &EmailArgs::default(), global_salt),
+        Some("card_number") => financial::card_number(found, false, false, rng),
+        Some("iban") => financial::iban(found, rng),
+        Some("uk_national_insurance_number") => {
+            national_identity_number::fake(found, Some(Format::UkNationalInsurance), None, rng)
+        }
+        Some("phone_number") => match phone_number::fake(found, locale, rng) {
+            Ok(number) => number,
+            Err(NotFaked::NotANumber) => financial::digits(found, found, rng),
+            Err(NotFaked::NoFictitiousRange(_)) => return None,
+        },
+        Some("uk_postcode") => postcode::fake(found, Mode::Fake, rng),
+        _ if found.contains(char::is_whitespace) => format!(
+            "{} {}",
+            locales::first_name(locale, rng),
+            locales::last_name(locale, rng)
+        ),
+        _ => locales::first_name(locale, rng),
+    };
+    Some(fake)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parsers::strategy_structs::{Transformer, TransformerType};
+    use crate::parsers::transformer_args::ParsedTransformer;
+    use serde_json::json;
+
+    fn redact_args(args: serde_json::Value) -> Result<RedactArgs, String> {
+        serde_json::from_value(args).map_err(|e| e.to_string())
+    }
+
+    fn redact_text(text: &str, args: serde_json::Value, column_values: &[(&str, &str)]) -> String {
+        let column_values: Vec<(String, String)> = column_values
+            .iter()
+            .map(|(column, value)| (column.to_string(), value.to_string()))
+            .collect();
+        redact(
+            &mut SmallRng::seed_from_u64(1),
+            text,
+            &redact_args(args).unwrap(),
+            &column_values,
+            None,
+        )
+    }
+
+    #[test]
+    fn redacts_pii_and_leaves_the_rest_of_the_text() {
+        assert_eq!(
+            redact_text(
+                "Emailed peter@example.com, call 020 7946 0123. Lives in NW5 3QQ, NI AB123456C",
+                json!({}),
+                &[]
+            ),
+            "Emailed [REDACTED], call [REDACTED]. Lives in [REDACTED], NI [REDACTED]"
+        );
+    }
+
+    #[test]
+    fn redacts_names_from_the_row() {
+        assert_eq!(
+            redact_text(
+                "Peter Parker missed the session, peter said he'd catch up. Petersfield is fine",
+                json!({"name_columns": ["first_name", "last_name"], "names": ["Mary Jane"], "marker": "[NAME]"}),
+                &[
+                    ("first_name", "Peter"),
+                    ("last_name", "Parker"),
+                    ("id", "1")
+                ]
+            ),
+            "[NAME] [NAME] missed the session, [NAME] said he'd catch up. Petersfield is fine"
+        );
+    }
+
+    #[test]
+    fn can_replace_with_fakes_of_the_same_kind() {
+        let redacted = redact_text(
+            "Reset via https://example.com/reset?token=abc123 or email peter@example.com",
+            json!({"replace_with": "fake", "deterministic": true}),
+            &[],
+        );
+        assert!(redacted.starts_with("Reset via https://"), "{}", redacted);
+        assert!(!redacted.contains("token=abc123"));
+        assert!(!redacted.contains("peter@example.com"));
+        assert!(redacted.contains(" or email "));
+        assert!(redacted.contains('@'));
+    }
+
+    #[test]
+    fn uses_the_marker_for_numbers_which_cant_be_faked() {
+        let redacted = redact_text(
+            "Call +81 90 1234 5678 or +44 7822 222222",
+            json!({"replace_with": "fake", "detectors": ["phone_number"]}),
+            &[],
+        );
+        assert!(
+            redacted.starts_with("Call [REDACTED] or +44 7700 900"),
+            "{}",
+            redacted
+        );
+    }
+
+    #[test]
+    fn only_uses_the_chosen_detectors() {
+        assert_eq!(
+            redact_text(
+                "peter@example.com 020 7946 0123",
+                json!({"detectors": ["phone_number"]}),
+                &[]
+            ),
+            "peter@example.com [REDACTED]"
+        );
+    }
+
+    #[test]
+    fn validates_detectors_and_name_columns() {
+        assert!(redact_args(json!({"detectors": ["passport"]}))
+            .unwrap_err()
+            .starts_with("unknown detector 'passport'"));
+
+        let unknown_column = redact_args(json!({"name_columns": ["surname"]})).unwrap();
+        assert_eq!(
+            validate(&unknown_column, &["first_name"]).unwrap_err(),
+            "'name_columns' has unknown column 'surname'"
+        );
+
+        let bad_replacement = ParsedTransformer::parse(&Transformer {
+            name: TransformerType::Redact,
+            args: Some(serde_json::from_value(json!({"replace_with": "mask"})).unwrap()),
+        });
+        assert!(bad_replacement.unwrap_err().starts_with("invalid args"));
+    }
+}
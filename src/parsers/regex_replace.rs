@@ -0,0 +1,164 @@
+use crate::parsers::data_row;
+use crate::parsers::pii_patterns::{self, PiiPattern};
+use crate::parsers::transformer;
+use crate::parsers::transformer_args::{ParsedTransformer, RegexReplaceArgs};
+use crate::parsers::types::{SubType, Type};
+use rand::rngs::SmallRng;
+use regex::Regex;
+use schemars::JsonSchema;
+use serde::Deserialize;
+use std::ops::Range;
+
+/// One entry in the `patterns` arg of a `RegexReplace` transformer.
+/// Either `pattern` (the name of a built in pattern) or `regex` (a custom regex) must be given
+#[derive(Clone, Debug, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+struct RuleInFile {
+    #[serde(default)]
+    pattern: Option<String>,
+    #[serde(default)]
+    regex: Option<String>,
+    replace_with: Replacement,
+}
+
+/// A rule with its regex looked up or compiled, which is done once when the strategy file is loaded
+#[derive(Clone, Debug, Deserialize, JsonSchema)]
+#[serde(try_from = "RuleInFile")]
+#[schemars(with = "RuleInFile")]
+pub struct ReplaceRule {
+    matcher: Matcher,
+    pub replace_with: Replacement,
+}
+
+#[derive(Clone, Debug)]
+enum Matcher {
+    Builtin(&'static PiiPattern),
+    Custom(Regex),
+}
+
+impl TryFrom<RuleInFile> for ReplaceRule {
+    type Error = String;
+    fn try_from(rule: RuleInFile) -> Result<Self, Self::Error> {
+        let matcher = match (rule.pattern, rule.regex) {
+            (Some(name), None) => pii_patterns::builtin(&name)
+                .map(Matcher::Builtin)
+                .ok_or_else(|| {
+                    format!(
+                        "unknown pattern '{}', expected one of: {}",
+                        name,
+                        pii_patterns::builtin_names().join(", ")
+                    )
+                })?,
+            (None, Some(regex)) => Regex::new(&regex)
+                .map(Matcher::Custom)
+                .map_err(|e| format!("invalid regex '{}': {}", regex, e))?,
+            _ => return Err("each pattern needs exactly one of 'pattern' or 'regex'".to_string()),
+        };
+        Ok(ReplaceRule {
+            matcher,
+            replace_with: rule.replace_with,
+        })
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case", deny_unknown_fields)]
+pub enum Replacement {
+    /// Replace the match with this exact text
+    Fixed(String),
+    /// Replace every letter and digit in the match with this character, keeping punctuation
+    Mask(char),
+    /// Pass the match through another transformer e.g. `FakeEmail`
+    Transformer(ParsedTransformer),
+}
+
+pub fn validate(args: &RegexReplaceArgs, column_names: &[&str]) -> Result<(), String> {
+    for rule in args.patterns.iter() {
+        if let Replacement::Transformer(nested) = &rule.replace_with {
+            transformer::validate_nested(nested, column_names)?;
+        }
+    }
+    Ok(())
+}
+
+/// Finds the spans matched by each rule in order. Earlier rules win where matches overlap.
+fn matched_spans(text: &str, rules: &[ReplaceRule]) -> Vec<(Range<usize>, usize)> {
+    let mut spans: Vec<(Range<usize>, usize)> = Vec::new();
+
+    for (rule_index, rule) in rules.iter().enumerate() {
+        let ranges: Vec<Range<usize>> = match &rule.matcher {
+            Matcher::Builtin(pattern) => pattern.find_iter(text).map(|m| m.range()).collect(),
+            Matcher::Custom(regex) => regex.find_iter(text).map(|m| m.range()).collect(),
+        };
+
+        add_spans(&mut spans, ranges, rule_index);
+    }
+
+    spans.sort_by_key(|(range, _)| range.start);
+    spans
+}
+
+/// Adds the ranges that don't overlap a span already found, tagged with the index of what found
+/// them
+pub fn add_spans<I: IntoIterator<Item = Range<usize>>>(
+    spans: &mut Vec<(Range<usize>, usize)>,
+    ranges: I,
+    index: usize,
+) {
+    for range in ranges {
+        let overlaps = spans
+            .iter()
+            .any(|(existing, _)| range.start < existing.end && existing.start < range.end);
+        if !overlaps && !range.is_empty() {
+            spans.push((range, index));
+        }
+    }
+}
+
+fn mask(matched: &str, mask_char: char) -> String {
+    matched
+        .chars()
+        .map(|c| if c.is_alphanumeric() { mask_char } else { c })
+        .collect()
+}
+
+pub fn replace(
+    rng: &mut SmallRng,
+    value: &str,
+    args: &RegexReplaceArgs,
+    table_name: &str,
+    column_values: &[(String, String)],
+    global_salt: Option<&str>,
+) -> String {
+    let rules = &args.patterns;
+    let text = data_row::decode(value);
+    let spans = matched_spans(&text, rules);
+    if spans.is_empty() {
+        return value.to_string();
+    }
+
+    let character_type = Type::single_value(SubType::Character);
+    let mut replaced = String::with_capacity(text.len());
+    let mut last_end = 0;
+    for (range, rule_index) in spans {
+        replaced.push_str(&text[last_end..range.start]);
+        let matched = &text[range.clone()];
+        match &rules[rule_index].replace_with {
+            Replacement::Fixed(fixed) => replaced.push_str(fixed),
+            Replacement::Mask(mask_char) => replaced.push_str(&mask(matched, *mask_char)),
+            Replacement::Transformer(nested) => replaced.push_str(&transformer::transform(
+                rng,
+                matched,
+                &character_type,
+                nested,
+                table_name,
+                column_values,
+                global_salt,
+            )),
+        }
+        last_end = range.end;
+    }
+    replaced.push_str(&text[last_end..]);
+
+    data_row::encode(&replaced).into_owned()
+}
@@ -4,9 +4,11 @@ use crate::parsers::sanitiser;
 use crate::parsers::state::*;
 use crate::parsers::strategies::Strategies;
 use crate::parsers::strategy_structs::ColumnInfo;
+use crate::parsers::template;
 use crate::parsers::transformer;
 use crate::parsers::types;
 use crate::parsers::types::Column;
+use crate::parsers::unique::{self, UniqueValues};
 use crate::parsers::{copy_row, data_row};
 use itertools::Itertools;
 use rand::rngs::SmallRng;
@@ -84,7 +86,13 @@ pub fn parse<'line>(
             Cow::from(line)
         }
         (RowType::CopyBlockRow, Position::InCopy { ref current_table }) => {
-            Cow::from(transform_row(rng, line, current_table, &state.types))
+            Cow::from(transform_row(
+                rng,
+                line,
+                current_table,
+                &state.types,
+                &mut state.unique_values,
+            ))
         }
 
         (RowType::Normal, Position::Normal) => Cow::from(line),
@@ -102,16 +110,12 @@ fn transform_row(
     line: &str,
     current_table: &CurrentTableTransforms,
     types: &Types,
+    unique_values: &mut UniqueValues,
 ) -> String {
     match current_table.table_transformers {
-        TableTransformers::ColumnTransformer(ref columns) => transform_row_with_columns(
-            rng,
-            line,
-            &current_table.table_name,
-            columns,
-            types,
-            current_table.salt.as_deref(),
-        ),
+        TableTransformers::ColumnTransformer(ref columns) => {
+            transform_row_with_columns(rng, line, current_table, columns, types, unique_values)
+        }
 
         TableTransformers::Truncator => "".to_string(),
     }
@@ -120,50 +124,125 @@ fn transform_row(
 fn transform_row_with_columns(
     rng: &mut SmallRng,
     line: &str,
-    table_name: &str,
+    current_table: &CurrentTableTransforms,
     columns: &[ColumnInfo],
     types: &Types,
-    salt: Option<&str>,
+    unique_values: &mut UniqueValues,
 ) -> String {
-    let column_values: Vec<String> = data_row::split(line).map(|s| s.to_string()).collect();
+    let column_values: Vec<&str> = data_row::split(line).collect();
 
     // Create a vector of (column_name, value) pairs
     let column_name_values: Vec<(String, String)> = columns
         .iter()
         .zip(column_values.iter())
-        .map(|(col, val)| (col.name.clone(), val.clone()))
+        .map(|(col, val)| (col.name.clone(), val.to_string()))
         .collect();
 
-    let mut transformed = column_values.iter().enumerate().map(|(i, value)| {
-        let current_column = &columns[i];
-        let column_type = types
-            //TODO this lookup, we do a double hashmap lookup for every column... already know the
-            //table, so we shouldnt need to do both... can we cache the current tables columns
-            //hashmap?
-            .lookup(table_name, &current_column.name)
-            .unwrap_or_else(|| {
-                panic!(
-                    "No type found for {}.{}\nI did find these for the table: {:?}",
-                    table_name,
-                    current_column.name,
-                    types.for_table(table_name)
+    let mut joined = match current_table.template_order {
+        None => column_values
+            .iter()
+            .zip(columns)
+            .map(|(value, column)| {
+                transform_value(
+                    rng,
+                    value,
+                    column,
+                    current_table,
+                    types,
+                    unique_values,
+                    &column_name_values,
                 )
-            });
+            })
+            .join("\t"),
+        // Templates are built from the already transformed values of the rest of the row, so
+        // that e.g. an email made from first and last names agrees with the fake names. Those
+        // columns have to be transformed after the ones they refer to
+        Some(ref order) => {
+            let mut transformed: Vec<Option<String>> = vec![None; column_values.len()];
+            let mut transformed_name_values: Vec<(String, String)> = Vec::new();
+            for &i in order.iter() {
+                let column = &columns[i];
+                let values_for_transformer = if template::is_template(&column.transformer) {
+                    &transformed_name_values
+                } else {
+                    &column_name_values
+                };
+                let new_value = transform_value(
+                    rng,
+                    column_values[i],
+                    column,
+                    current_table,
+                    types,
+                    unique_values,
+                    values_for_transformer,
+                )
+                .into_owned();
+                transformed_name_values.push((column.name.clone(), new_value.clone()));
+                transformed[i] = Some(new_value);
+            }
+            transformed.into_iter().flatten().join("\t")
+        }
+    };
+    joined.push('\n');
+    joined
+}
 
+fn transform_value<'line>(
+    rng: &mut SmallRng,
+    value: &'line str,
+    column: &'line ColumnInfo,
+    current_table: &CurrentTableTransforms,
+    types: &Types,
+    unique_values: &mut UniqueValues,
+    column_values: &[(String, String)],
+) -> Cow<'line, str> {
+    let table_name = current_table.table_name.as_str();
+    let salt = current_table.salt.as_deref();
+    let column_type = types
+        //TODO this lookup, we do a double hashmap lookup for every column... already know the
+        //table, so we shouldnt need to do both... can we cache the current tables columns
+        //hashmap?
+        .lookup(table_name, &column.name)
+        .unwrap_or_else(|| {
+            panic!(
+                "No type found for {}.{}\nI did find these for the table: {:?}",
+                table_name,
+                column.name,
+                types.for_table(table_name)
+            )
+        });
+
+    if unique::is_unique(&column.transformer) {
+        Cow::from(unique_values.transform(
+            table_name,
+            &column.name,
+            &column.transformer,
+            value,
+            salt,
+            |salt| {
+                transformer::transform(
+                    rng,
+                    value,
+                    column_type,
+                    &column.transformer,
+                    table_name,
+                    column_values,
+                    salt,
+                )
+                .into_owned()
+            },
+        ))
+    } else {
         transformer::transform(
             rng,
             value,
             column_type,
-            &current_column.transformer,
+            &column.transformer,
             table_name,
-            &column_name_values,
+            column_values,
             salt,
         )
-    });
-
-    let mut joined = transformed.join("\t");
-    joined.push('\n');
-    joined
+    }
 }
 
 fn add_create_table_row_to_types(line: &str, mut current_types: Vec<Column>) -> Vec<Column> {
@@ -180,7 +259,7 @@ mod tests {
     use super::*;
     use crate::parsers::copy_row::TableTransformers;
     use crate::parsers::rng;
-    use crate::parsers::strategy_structs::{ColumnInfo, DataCategory, TransformerType};
+    use crate::parsers::strategy_structs::{DataCategory, TransformerType};
     use crate::parsers::types::{SubType, Type};
     use std::collections::HashMap;
 
@@ -234,6 +313,7 @@ mod tests {
                 }],
             },
             types: Types::new(HashMap::default()),
+            unique_values: UniqueValues::default(),
         };
         let mut rng = rng::get();
         let transformed_row = parse(&mut rng, create_table_row, &mut state, &strategies);
@@ -268,6 +348,7 @@ mod tests {
                 types: vec![],
             },
             types: Types::new(HashMap::default()),
+            unique_values: UniqueValues::default(),
         };
         let mut rng = rng::get();
         let transformed_row = parse(&mut rng, create_table_row, &mut state, &strategies);
@@ -296,6 +377,7 @@ mod tests {
                 }],
             },
             types: Types::new(HashMap::default()),
+            unique_values: UniqueValues::default(),
         };
         let mut rng = rng::get();
         let transformed_row = parse(&mut rng, create_table_row, &mut state, &strategies);
@@ -360,6 +442,7 @@ mod tests {
         let state = State {
             position: Position::Normal,
             types: Types::new(HashMap::default()),
+            unique_values: UniqueValues::default(),
         };
         let row_type = row_type(not_a_copy_row, &state.position);
         assert_eq!(RowType::Normal, row_type);
@@ -422,21 +505,22 @@ mod tests {
 
         let mut state = State {
             position: Position::InCopy {
-                current_table: CurrentTableTransforms {
-                    table_name: "public.users".to_string(),
-                    table_transformers: TableTransformers::ColumnTransformer(vec![
+                current_table: CurrentTableTransforms::new(
+                    "public.users".to_string(),
+                    TableTransformers::ColumnTransformer(vec![
                         ColumnInfo::builder().with_name("column_1").build(),
                         ColumnInfo::builder().with_name("column_2").build(),
                         ColumnInfo::builder().with_name("column_3").build(),
                     ]),
-                    salt: None,
-                },
+                    None,
+                ),
             },
             types: Types::builder()
                 .add_type("public.users", "column_1", SubType::Character)
                 .add_type("public.users", "column_2", SubType::Character)
                 .add_type("public.users", "column_3", SubType::Character)
                 .build(),
+            unique_values: UniqueValues::default(),
         };
         let mut rng = rng::get();
         let transformed_row = parse(&mut rng, table_data_row, &mut state, &strategies);
@@ -450,39 +534,40 @@ mod tests {
 
         let mut state = State {
             position: Position::InCopy {
-                current_table: CurrentTableTransforms {
-                    table_name: "public.users".to_string(),
-                    table_transformers: TableTransformers::ColumnTransformer(vec![
+                current_table: CurrentTableTransforms::new(
+                    "public.users".to_string(),
+                    TableTransformers::ColumnTransformer(vec![
                         ColumnInfo::builder()
                             .with_name("column_1")
                             .with_transformer(
                                 TransformerType::Fixed,
-                                Some(HashMap::from([("value".to_string(), "first".to_string())])),
+                                Some(HashMap::from([("value".to_string(), "first".into())])),
                             )
                             .build(),
                         ColumnInfo::builder()
                             .with_name("column_2")
                             .with_transformer(
                                 TransformerType::Fixed,
-                                Some(HashMap::from([("value".to_string(), "second".to_string())])),
+                                Some(HashMap::from([("value".to_string(), "second".into())])),
                             )
                             .build(),
                         ColumnInfo::builder()
                             .with_name("column_3")
                             .with_transformer(
                                 TransformerType::Fixed,
-                                Some(HashMap::from([("value".to_string(), "third".to_string())])),
+                                Some(HashMap::from([("value".to_string(), "third".into())])),
                             )
                             .build(),
                     ]),
-                    salt: None,
-                },
+                    None,
+                ),
             },
             types: Types::builder()
                 .add_type("public.users", "column_1", SubType::Character)
                 .add_type("public.users", "column_2", SubType::Character)
                 .add_type("public.users", "column_3", SubType::Character)
                 .build(),
+            unique_values: UniqueValues::default(),
         };
         let mut rng = rng::get();
         let transformed_row = parse(&mut rng, table_data_row, &mut state, &strategies);
@@ -495,9 +580,9 @@ mod tests {
 
         let mut state = State {
             position: Position::InCopy {
-                current_table: CurrentTableTransforms {
-                    table_name: "public.users".to_string(),
-                    table_transformers: TableTransformers::ColumnTransformer(vec![
+                current_table: CurrentTableTransforms::new(
+                    "public.users".to_string(),
+                    TableTransformers::ColumnTransformer(vec![
                         ColumnInfo::builder()
                             .with_name("column_1")
                             .with_transformer(TransformerType::Identity, None)
@@ -511,14 +596,15 @@ mod tests {
                             .with_transformer(TransformerType::Identity, None)
                             .build(),
                     ]),
-                    salt: None,
-                },
+                    None,
+                ),
             },
             types: Types::builder()
                 .add_type("public.users", "column_1", SubType::Character)
                 .add_type("public.users", "column_2", SubType::Character)
                 .add_type("public.users", "column_3", SubType::Character)
                 .build(),
+            unique_values: UniqueValues::default(),
         };
         let mut rng = rng::get();
         let transformed_row = parse(&mut rng, table_data_row, &mut state, &strategies);
@@ -532,23 +618,72 @@ mod tests {
 
         let mut state = State {
             position: Position::InCopy {
-                current_table: CurrentTableTransforms {
-                    table_name: "public.users".to_string(),
-                    table_transformers: TableTransformers::ColumnTransformer(vec![
-                        ColumnInfo::builder()
-                            .with_name("column_1")
-                            .with_transformer(TransformerType::Scramble, None)
-                            .build(),
-                    ]),
-                    salt: None,
-                },
+                current_table: CurrentTableTransforms::new(
+                    "public.users".to_string(),
+                    TableTransformers::ColumnTransformer(vec![ColumnInfo::builder()
+                        .with_name("column_1")
+                        .with_transformer(TransformerType::Scramble, None)
+                        .build()]),
+                    None,
+                ),
             },
             types: Types::builder()
                 .add_array_type("public.users", "column_1", SubType::Character)
                 .build(),
+            unique_values: UniqueValues::default(),
         };
         let mut rng = rng::get();
         let processed_row = parse(&mut rng, table_data_row, &mut state, &strategies);
         assert!(table_data_row != processed_row);
     }
+
+    #[test]
+    fn templates_use_the_transformed_values_of_other_columns() {
+        let table_data_row = "peter@example.com\tPeter\tPuckleberry\n";
+        let strategies = Strategies::new_from("public.users".to_string(), HashMap::new());
+
+        let mut state = State {
+            position: Position::InCopy {
+                current_table: CurrentTableTransforms::new(
+                    "public.users".to_string(),
+                    TableTransformers::ColumnTransformer(vec![
+                        ColumnInfo::builder()
+                            .with_name("email")
+                            .with_transformer(
+                                TransformerType::Template,
+                                Some(HashMap::from([(
+                                    "template".to_string(),
+                                    "{first_name}.{last_name}@example.test".into(),
+                                )])),
+                            )
+                            .build(),
+                        ColumnInfo::builder()
+                            .with_name("first_name")
+                            .with_transformer(
+                                TransformerType::Fixed,
+                                Some(HashMap::from([("value".to_string(), "Jane".into())])),
+                            )
+                            .build(),
+                        ColumnInfo::builder()
+                            .with_name("last_name")
+                            .with_transformer(
+                                TransformerType::Fixed,
+                                Some(HashMap::from([("value".to_string(), "Smith".into())])),
+                            )
+                            .build(),
+                    ]),
+                    None,
+                ),
+            },
+            types: Types::builder()
+                .add_type("public.users", "email", SubType::Character)
+                .add_type("public.users", "first_name", SubType::Character)
+                .add_type("public.users", "last_name", SubType::Character)
+                .build(),
+            unique_values: UniqueValues::default(),
+        };
+        let mut rng = rng::get();
+        let transformed_row = parse(&mut rng, table_data_row, &mut state, &strategies);
+        assert_eq!("Jane.Smith@example.test\tJane\tSmith\n", transformed_row);
+    }
 }
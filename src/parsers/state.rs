@@ -1,6 +1,7 @@
 use crate::parsers::copy_row::CurrentTableTransforms;
 use crate::parsers::types::Column;
 use crate::parsers::types::Type;
+use crate::parsers::unique::UniqueValues;
 use std::collections::HashMap;
 
 #[derive(Clone, Debug, PartialEq, Eq)]
@@ -31,6 +32,7 @@ impl Types {
 pub struct State {
     pub position: Position,
     pub types: Types,
+    pub unique_values: UniqueValues,
 }
 
 #[derive(Clone, Debug, PartialEq, Eq)]
@@ -50,6 +52,7 @@ impl State {
         State {
             position: Position::Normal,
             types: Types::new(HashMap::default()),
+            unique_values: UniqueValues::default(),
         }
     }
 
@@ -71,6 +74,12 @@ impl State {
             );
         }
 
+        if let (Position::InCopy { current_table }, Position::Normal) =
+            (&self.position, &new_position)
+        {
+            self.unique_values.finish_table(&current_table.table_name);
+        }
+
         self.position = new_position
     }
 }
@@ -93,11 +102,11 @@ mod tests {
     fn update_position_modifies_position() {
         let mut state = State::new();
         let new_position = Position::InCopy {
-            current_table: CurrentTableTransforms {
-                table_name: "table-mc-tableface".to_string(),
-                table_transformers: TableTransformers::ColumnTransformer(vec![]),
-                salt: None,
-            },
+            current_table: CurrentTableTransforms::new(
+                "table-mc-tableface".to_string(),
+                TableTransformers::ColumnTransformer(vec![]),
+                None,
+            ),
         };
 
         state.update_position(new_position.clone());
@@ -122,6 +131,7 @@ mod tests {
                 ],
             },
             types: Types::new(HashMap::default()),
+            unique_values: UniqueValues::default(),
         };
 
         state.update_position(Position::Normal);
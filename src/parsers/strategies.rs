@@ -1,6 +1,12 @@
 use crate::parsers::custom_classifications::ClassificationConfig;
+use crate::parsers::locales;
+use crate::parsers::nulls;
+use crate::parsers::pipeline;
 use crate::parsers::strategy_errors::{DbErrors, ValidationErrors};
 use crate::parsers::strategy_structs::*;
+use crate::parsers::template;
+use crate::parsers::transformer;
+use crate::parsers::transformer_args::ParsedTransformer;
 use itertools::{Either, Itertools};
 use std::collections::HashMap;
 use std::collections::HashSet;
@@ -36,12 +42,12 @@ impl Strategies {
         let mut errors = ValidationErrors::new();
 
         // Check if the first item is a salt configuration
+        let mut global_locale = None;
         if let Some(first) = strategies_in_file.first() {
-            transformed_strategies.salt = if first.table_name.is_empty() {
-                first.salt.clone()
-            } else {
-                None
-            };
+            if first.table_name.is_empty() {
+                transformed_strategies.salt = first.salt.clone();
+                global_locale = first.locale.clone();
+            }
         }
 
         for strategy in strategies_in_file {
@@ -68,7 +74,11 @@ impl Strategies {
 
                     if (column.data_category == DataCategory::PotentialPii
                         || column.data_category == DataCategory::Pii)
-                        && column.transformer.name == TransformerType::Identity
+                        && [
+                            TransformerType::Identity,
+                            TransformerType::ProbabilisticNull,
+                        ]
+                        .contains(&column.transformer.name)
                     {
                         errors
                             .unanonymised_pii
@@ -84,12 +94,27 @@ impl Strategies {
                             .error_transformer_types
                             .push(create_simple_column(&strategy.table_name, &column.name));
                     }
+                    let name = column.name.clone();
+                    let data_category = column.data_category.clone();
+                    let transformer = match ParsedTransformer::parse(&transformer(
+                        column,
+                        transformer_overrides,
+                        global_locale.as_deref(),
+                    )) {
+                        Ok(transformer) => transformer,
+                        Err(reason) => {
+                            errors
+                                .invalid_transformer_args
+                                .push((create_simple_column(&strategy.table_name, &name), reason));
+                            continue;
+                        }
+                    };
                     let result = columns.insert(
-                        column.name.clone(),
+                        name.clone(),
                         ColumnInfo {
-                            data_category: column.data_category.clone(),
-                            name: column.name.clone(),
-                            transformer: transformer(column, transformer_overrides),
+                            data_category,
+                            name,
+                            transformer,
                         },
                     );
                     if let Some(dupe) = result {
@@ -100,6 +125,8 @@ impl Strategies {
                     }
                 }
 
+                validate_transformer_args(&strategy.table_name, &columns, &mut errors);
+
                 let result = transformed_strategies.insert(strategy.table_name.clone(), columns);
                 if result.is_some() {
                     errors.duplicate_tables.push(strategy.table_name);
@@ -168,6 +195,7 @@ impl Strategies {
                 .difference(&columns_from_db_without_truncate)
                 .cloned()
                 .collect(),
+            nulled_non_nullable_columns: Vec::new(),
         };
 
         if DbErrors::is_empty(&errors) {
@@ -181,6 +209,90 @@ impl Strategies {
         }
     }
 
+    pub fn validate_nullability(
+        &self,
+        non_nullable_columns: &HashSet<SimpleColumn>,
+    ) -> Result<(), DbErrors> {
+        let mut nulled_non_nullable_columns: Vec<SimpleColumn> = self
+            .tables
+            .iter()
+            .flat_map(|(table_name, table_strategy)| match table_strategy {
+                TableStrategy::Columns(columns) => columns
+                    .values()
+                    .filter(|column| nulls::can_produce_null(&column.transformer))
+                    .map(|column| create_simple_column(table_name, &column.name))
+                    .collect(),
+                TableStrategy::Truncate => Vec::new(),
+            })
+            .filter(|column| non_nullable_columns.contains(column))
+            .collect();
+
+        if nulled_non_nullable_columns.is_empty() {
+            Ok(())
+        } else {
+            nulled_non_nullable_columns.sort();
+            Err(DbErrors {
+                missing_from_strategy_file: Vec::new(),
+                missing_from_db: Vec::new(),
+                nulled_non_nullable_columns,
+            })
+        }
+    }
+
+    /// Checks each column's transformer can output values of the column's type in the database,
+    /// e.g. that a string post processor isn't used on an integer column
+    pub fn validate_column_types(
+        &self,
+        column_types: &HashMap<SimpleColumn, String>,
+    ) -> Result<(), Box<ValidationErrors>> {
+        let mut incompatible_column_types: Vec<(SimpleColumn, String)> = self
+            .tables
+            .iter()
+            .flat_map(|(table_name, table_strategy)| match table_strategy {
+                TableStrategy::Columns(columns) => columns
+                    .values()
+                    .filter_map(|column| {
+                        let simple_column = create_simple_column(table_name, &column.name);
+                        let data_type = column_types.get(&simple_column)?;
+                        pipeline::check_column_type(&column.transformer, data_type)
+                            .err()
+                            .map(|reason| (simple_column, reason))
+                    })
+                    .collect(),
+                TableStrategy::Truncate => Vec::new(),
+            })
+            .collect();
+
+        if incompatible_column_types.is_empty() {
+            Ok(())
+        } else {
+            incompatible_column_types.sort();
+            let mut errors = ValidationErrors::new();
+            errors.incompatible_column_types = incompatible_column_types;
+            Err(Box::new(errors))
+        }
+    }
+
+    /// The tables with a column in each `unique_group`, so the group's values can be dropped once
+    /// they've all been written
+    pub fn unique_groups(&self) -> HashMap<String, HashSet<String>> {
+        let mut groups: HashMap<String, HashSet<String>> = HashMap::new();
+        for (table_name, table_strategy) in self.tables.iter() {
+            if let TableStrategy::Columns(columns) = table_strategy {
+                for group in columns
+                    .values()
+                    .filter_map(|column| column.transformer.unique.unique_group.as_ref())
+                {
+                    groups
+                        .entry(group.clone())
+                        .or_default()
+                        .insert(table_name.clone());
+                }
+            }
+        }
+        groups
+    }
+
     #[allow(dead_code)] //This is used in tests for convenience
     pub fn transformer_for_column<'a>(
         &self,
@@ -193,7 +305,7 @@ impl Strategies {
                 TableStrategy::Columns(columns) => columns.get(column_name),
                 TableStrategy::Truncate => None,
             })
-            .map(|column| column.transformer.clone())
+            .map(|column| column.transformer.source.clone())
     }
 
     #[allow(dead_code)] //This is used in tests for convenience
@@ -258,32 +370,78 @@ fn apply_transformer_overrides(
     }
 }
 
-fn transformer(column: ColumnInFile, overrides: &TransformerOverrides) -> Transformer {
-    apply_transformer_overrides(column.data_category, overrides, column.transformer)
+fn transformer(
+    column: ColumnInFile,
+    overrides: &TransformerOverrides,
+    global_locale: Option<&str>,
+) -> Transformer {
+    let transformer =
+        apply_transformer_overrides(column.data_category, overrides, column.transformer);
+    match global_locale {
+        Some(locale) => locales::with_default_locale(transformer, locale),
+        None => transformer,
+    }
 }
 
-/// Validates transformers with deterministic=true have an id_column (except FakeUUID transformer).
+/// Validates transformers with deterministic=true have an id_column (except FakeUUID, Dictionary
+/// and Redact, which can be deterministic on the value alone).
 fn validate_deterministic_settings(strategy: &StrategyInFile, errors: &mut ValidationErrors) {
     strategy
         .columns
         .iter()
-        .filter(|column| column.transformer.name != TransformerType::FakeUUID)
+        .filter(|column| {
+            ![
+                TransformerType::Dictionary,
+                TransformerType::FakeUUID,
+                TransformerType::Redact,
+                TransformerType::Template,
+            ]
+            .contains(&column.transformer.name)
+        })
         .filter_map(|column| {
-            column.transformer.args.as_ref().and_then(|args| {
-                if args.get("deterministic") == Some(&"true".to_string())
-                    && !args.contains_key("id_column")
-                {
-                    Some(create_simple_column(&strategy.table_name, &column.name))
-                } else {
-                    None
-                }
-            })
+            let args = &column.transformer.args;
+            if transformer::arg_is_true(args, "deterministic")
+                && transformer::arg(args, "id_column").is_none()
+            {
+                Some(create_simple_column(&strategy.table_name, &column.name))
+            } else {
+                None
+            }
         })
         .for_each(|simple_column| {
             errors.deterministic_without_id.push(simple_column);
         });
 }
 
+fn validate_transformer_args(
+    table_name: &str,
+    columns: &ColumnNamesToInfo,
+    errors: &mut ValidationErrors,
+) {
+    let column_names: Vec<&str> = columns.keys().map(String::as_str).collect();
+    let mut arg_errors: Vec<(SimpleColumn, String)> = columns
+        .values()
+        .filter_map(|column| {
+            transformer::validate_args(&column.transformer, &column_names)
+                .err()
+                .map(|reason| (create_simple_column(table_name, &column.name), reason))
+        })
+        .collect();
+
+    if arg_errors.is_empty() {
+        let columns: Vec<ColumnInfo> = columns.values().cloned().collect();
+        if let Err(circular) = template::transform_order(&columns) {
+            arg_errors.extend(circular.iter().map(|column_name| {
+                (
+                    create_simple_column(table_name, column_name),
+                    "template depends on itself through other templates".to_string(),
+                )
+            }));
+        }
+    }
+    errors.invalid_transformer_args.extend(arg_errors);
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -375,6 +533,54 @@ mod tests {
             vec!(create_simple_column("public.person", "first_name"))
         );
     }
+    #[test]
+    fn validate_column_types_returns_columns_with_transformers_that_dont_match_their_type() {
+        let strategies = create_strategy(
+            "public.person",
+            [
+                create_column_with_data_and_transformer_type(
+                    "name",
+                    DataCategory::Pii,
+                    TransformerType::Uppercase,
+                ),
+                create_column_with_data_and_transformer_type(
+                    "age",
+                    DataCategory::General,
+                    TransformerType::Lowercase,
+                ),
+                create_column_with_data_and_transformer_type(
+                    "id",
+                    DataCategory::General,
+                    TransformerType::Identity,
+                ),
+            ]
+            .into_iter(),
+        );
+        let column_types = HashMap::from([
+            (
+                create_simple_column("public.person", "name"),
+                "character varying".to_string(),
+            ),
+            (
+                create_simple_column("public.person", "age"),
+                "integer".to_string(),
+            ),
+            (
+                create_simple_column("public.person", "id"),
+                "integer".to_string(),
+            ),
+        ]);
+
+        let error = strategies.validate_column_types(&column_types).unwrap_err();
+        assert_eq!(
+            error.incompatible_column_types,
+            vec![(
+                create_simple_column("public.person", "age"),
+                "Lowercase can only be used on text columns, not integer".to_string()
+            )]
+        );
+    }
+
     #[test]
     fn validates_truncate() {
         let mut strategies = Strategies::new();
@@ -413,6 +619,7 @@ mod tests {
             description: "description".to_string(),
             truncate: false,
             salt: None,
+            locale: None,
             columns: vec![column_in_file(
                 DataCategory::Pii,
                 column_name,
@@ -452,6 +659,7 @@ mod tests {
                 description: String::default(),
                 truncate: false,
                 salt: Some(salt.clone()),
+                locale: None,
                 columns: Vec::default(),
             },
             // Actual table strategy
@@ -460,6 +668,7 @@ mod tests {
                 description: "description".to_string(),
                 truncate: false,
                 salt: None,
+                locale: None,
                 columns: vec![column_in_file(
                     DataCategory::Pii,
                     column_name,
@@ -489,6 +698,76 @@ mod tests {
         assert_eq!(expected, parsed);
     }
 
+    #[test]
+    fn from_strategies_in_file_uses_the_global_locale_unless_a_column_sets_its_own() {
+        let mut french_city = column_in_file(DataCategory::Pii, "city", TransformerType::FakeCity);
+        french_city.transformer.args =
+            Some(HashMap::from([("locale".to_string(), "fr_FR".into())]));
+
+        let strategies = vec![
+            StrategyInFile {
+                table_name: String::default(),
+                description: String::default(),
+                truncate: false,
+                salt: None,
+                locale: Some("de_DE".to_string()),
+                columns: Vec::default(),
+            },
+            StrategyInFile {
+                table_name: TABLE_NAME.to_string(),
+                description: "description".to_string(),
+                truncate: false,
+                salt: None,
+                locale: None,
+                columns: vec![
+                    column_in_file(DataCategory::Pii, "name", TransformerType::FakeFirstName),
+                    french_city,
+                    column_in_file(DataCategory::Pii, "bio", TransformerType::Scramble),
+                ],
+            },
+        ];
+
+        let parsed = Strategies::from_strategies_in_file(
+            strategies,
+            &TransformerOverrides::none(),
+            &ClassificationConfig::default(),
+        )
+        .unwrap();
+        let Some(TableStrategy::Columns(columns)) = parsed.for_table(TABLE_NAME) else {
+            panic!("expected columns for {}", TABLE_NAME);
+        };
+        let locale =
+            |column: &str| transformer::arg(&columns[column].transformer.source.args, "locale");
+        assert_eq!(locale("name"), Some("de_DE"));
+        assert_eq!(locale("city"), Some("fr_FR"));
+        assert_eq!(locale("bio"), None);
+    }
+
+    #[test]
+    fn from_strategies_in_file_returns_errors_for_unknown_locales() {
+        let mut column = column_in_file(DataCategory::Pii, "name", TransformerType::FakeLastName);
+        column.transformer.args = Some(HashMap::from([("locale".to_string(), "xx".into())]));
+        let strategies = vec![StrategyInFile {
+            table_name: TABLE_NAME.to_string(),
+            description: "description".to_string(),
+            truncate: false,
+            salt: None,
+            locale: None,
+            columns: vec![column],
+        }];
+
+        let errors = Strategies::from_strategies_in_file(
+            strategies,
+            &TransformerOverrides::none(),
+            &ClassificationConfig::default(),
+        )
+        .unwrap_err();
+        assert_eq!(errors.invalid_transformer_args.len(), 1);
+        assert!(errors.invalid_transformer_args[0]
+            .1
+            .starts_with("invalid args for FakeLastName: unknown locale 'xx'"));
+    }
+
     #[test]
     fn from_strategies_in_file_returns_errors_for_duplicate_table_and_column_definitions() {
         let table2_name = "daps";
@@ -502,6 +781,7 @@ mod tests {
                 description: "description".to_string(),
                 truncate: false,
                 salt: None,
+                locale: None,
                 columns: vec![],
             },
             StrategyInFile {
@@ -509,6 +789,7 @@ mod tests {
                 description: "description".to_string(),
                 truncate: false,
                 salt: None,
+                locale: None,
                 columns: vec![],
             },
             StrategyInFile {
@@ -516,6 +797,7 @@ mod tests {
                 description: "description".to_string(),
                 truncate: false,
                 salt: None,
+                locale: None,
                 columns: vec![duplicated_column.clone(), duplicated_column],
             },
         ];
@@ -541,6 +823,7 @@ mod tests {
             description: "description".to_string(),
             truncate: false,
             salt: None,
+            locale: None,
             columns: vec![column_in_file(
                 DataCategory::Unknown,
                 "first_name",
@@ -568,6 +851,7 @@ mod tests {
             description: "description".to_string(),
             truncate: false,
             salt: None,
+            locale: None,
             columns: vec![column_in_file(
                 DataCategory::General,
                 "first_name",
@@ -595,6 +879,7 @@ mod tests {
             description: "description".to_string(),
             truncate: false,
             salt: None,
+            locale: None,
             columns: vec![
                 column_in_file(DataCategory::Pii, "first_name", TransformerType::Identity),
                 column_in_file(
@@ -629,6 +914,7 @@ mod tests {
             description: "description".to_string(),
             truncate: false,
             salt: None,
+            locale: None,
             columns: vec![
                 column_in_file(
                     DataCategory::PotentialPii,
@@ -674,6 +960,7 @@ mod tests {
             description: "description".to_string(),
             truncate: false,
             salt: None,
+            locale: None,
             columns: vec![
                 column_in_file(
                     DataCategory::PotentialPii,
@@ -720,6 +1007,7 @@ mod tests {
             description: "description".to_string(),
             truncate: false,
             salt: None,
+            locale: None,
             columns: vec![column_in_file(
                 DataCategory::General,
                 SCRAMBLED_COLUMN_NAME,
@@ -749,6 +1037,7 @@ mod tests {
             description: "description".to_string(),
             truncate: false,
             salt: None,
+            locale: None,
             columns: vec![
                 column_in_file(
                     DataCategory::PotentialPii,
@@ -790,20 +1079,21 @@ mod tests {
     #[test]
     fn from_strategies_in_file_returns_errors_for_deterministic_without_id_column() {
         let mut transformer = Transformer {
-            name: TransformerType::Scramble,
+            name: TransformerType::FakeFirstName,
             args: Some(HashMap::new()),
         };
         transformer
             .args
             .as_mut()
             .unwrap()
-            .insert("deterministic".to_string(), "true".to_string());
+            .insert("deterministic".to_string(), "true".into());
 
         let strategies = vec![StrategyInFile {
             table_name: "public.person".to_string(),
             description: "description".to_string(),
             truncate: false,
             salt: None,
+            locale: None,
             columns: vec![ColumnInFile {
                 data_category: DataCategory::General,
                 description: "first_name".to_string(),
@@ -828,25 +1118,26 @@ mod tests {
     #[test]
     fn from_strategies_in_file_accepts_deterministic_with_id_column() {
         let mut transformer = Transformer {
-            name: TransformerType::Scramble,
+            name: TransformerType::FakeFirstName,
             args: Some(HashMap::new()),
         };
         transformer
             .args
             .as_mut()
             .unwrap()
-            .insert("deterministic".to_string(), "true".to_string());
+            .insert("deterministic".to_string(), "true".into());
         transformer
             .args
             .as_mut()
             .unwrap()
-            .insert("id_column".to_string(), "user_id".to_string());
+            .insert("id_column".to_string(), "user_id".into());
 
         let strategies = vec![StrategyInFile {
             table_name: "public.person".to_string(),
             description: "description".to_string(),
             truncate: false,
             salt: None,
+            locale: None,
             columns: vec![ColumnInFile {
                 data_category: DataCategory::General,
                 description: "first_name".to_string(),
@@ -875,13 +1166,14 @@ mod tests {
             .args
             .as_mut()
             .unwrap()
-            .insert("deterministic".to_string(), "true".to_string());
+            .insert("deterministic".to_string(), "true".into());
 
         let strategies = vec![StrategyInFile {
             table_name: "public.person".to_string(),
             description: "description".to_string(),
             truncate: false,
             salt: None,
+            locale: None,
             columns: vec![ColumnInFile {
                 data_category: DataCategory::General,
                 description: "user_id".to_string(),
@@ -905,6 +1197,7 @@ mod tests {
             description: "description".to_string(),
             truncate: false,
             salt: None,
+            locale: None,
             columns: vec![column_in_file(
                 DataCategory::Custom("InvalidCustomType".to_string()),
                 "custom_column",
@@ -947,6 +1240,7 @@ mod tests {
             description: "description".to_string(),
             truncate: false,
             salt: None,
+            locale: None,
             columns: vec![column_in_file(
                 DataCategory::Custom(custom_type.to_string()),
                 "custom_column",
@@ -1050,4 +1344,146 @@ mod tests {
                 .build(),
         )
     }
+
+    #[test]
+    fn from_strategies_in_file_returns_errors_for_invalid_transformer_args() {
+        let template = |template: &str| Transformer {
+            name: TransformerType::Template,
+            args: Some(HashMap::from([("template".to_string(), template.into())])),
+        };
+        let strategies_in_file = vec![StrategyInFile {
+            table_name: "public.users".to_string(),
+            description: "description".to_string(),
+            truncate: false,
+            salt: None,
+            locale: None,
+            columns: vec![
+                ColumnInFile {
+                    data_category: DataCategory::Pii,
+                    description: "email".to_string(),
+                    name: "email".to_string(),
+                    transformer: template("{first_name}@example.test"),
+                },
+                ColumnInFile {
+                    data_category: DataCategory::Pii,
+                    description: "username".to_string(),
+                    name: "username".to_string(),
+                    transformer: template("{fake:shoe_size}"),
+                },
+            ],
+        }];
+
+        let error = Strategies::from_strategies_in_file(
+            strategies_in_file,
+            &TransformerOverrides::none(),
+            &ClassificationConfig::default(),
+        )
+        .unwrap_err();
+
+        let mut invalid_transformer_args = error.invalid_transformer_args.clone();
+        invalid_transformer_args.sort();
+        assert_eq!(
+            invalid_transformer_args,
+            vec![
+                (
+                    create_simple_column("public.users", "email"),
+                    "template refers to unknown column 'first_name'".to_string()
+                ),
+                (
+                    create_simple_column("public.users", "username"),
+                    "unknown fake 'shoe_size', expected one of: city, company_name, domain_suffix, first_name, full_name, last_name, username, word".to_string()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn from_strategies_in_file_returns_errors_for_unknown_and_missing_transformer_args() {
+        let column = |name: &str, transformer: Transformer| ColumnInFile {
+            data_category: DataCategory::Pii,
+            description: name.to_string(),
+            name: name.to_string(),
+            transformer,
+        };
+        let strategies_in_file = vec![StrategyInFile {
+            table_name: "public.users".to_string(),
+            description: "description".to_string(),
+            truncate: false,
+            salt: None,
+            locale: None,
+            columns: vec![
+                column(
+                    "first_name",
+                    Transformer {
+                        name: TransformerType::FakeFirstName,
+                        args: Some(HashMap::from([("determinstic".to_string(), "true".into())])),
+                    },
+                ),
+                column(
+                    "status",
+                    Transformer {
+                        name: TransformerType::Fixed,
+                        args: None,
+                    },
+                ),
+            ],
+        }];
+
+        let error = Strategies::from_strategies_in_file(
+            strategies_in_file,
+            &TransformerOverrides::none(),
+            &ClassificationConfig::default(),
+        )
+        .unwrap_err();
+
+        let mut invalid_transformer_args = error.invalid_transformer_args.clone();
+        invalid_transformer_args.sort();
+        assert_eq!(
+            invalid_transformer_args,
+            vec![
+                (
+                    create_simple_column("public.users", "first_name"),
+                    "invalid args for FakeFirstName: unknown field `determinstic`, expected one of `deterministic`, `id_column`, `locale`".to_string()
+                ),
+                (
+                    create_simple_column("public.users", "status"),
+                    "invalid args for Fixed: missing field `value`".to_string()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn validate_nullability_returns_error_for_nulled_non_nullable_columns() {
+        let strategies = create_strategy(
+            "public.person",
+            [
+                create_column_with_data_and_transformer_type(
+                    "middle_name",
+                    DataCategory::Pii,
+                    TransformerType::Null,
+                ),
+                create_column_with_data_and_transformer_type(
+                    "last_name",
+                    DataCategory::Pii,
+                    TransformerType::Null,
+                ),
+                create_column("id"),
+            ]
+            .into_iter(),
+        );
+
+        let non_nullable_columns = HashSet::from([
+            create_simple_column("public.person", "last_name"),
+            create_simple_column("public.person", "id"),
+        ]);
+
+        let error = strategies
+            .validate_nullability(&non_nullable_columns)
+            .unwrap_err();
+        assert_eq!(
+            error.nulled_non_nullable_columns,
+            vec![create_simple_column("public.person", "last_name")]
+        );
+    }
 }
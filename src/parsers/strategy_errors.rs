@@ -17,6 +17,36 @@ impl fmt::Display for StrategyFileError {
     }
 }
 
+impl StrategyFileError {
+    pub fn problems(&self) -> Vec<Problem> {
+        match self {
+            StrategyFileError::ValidationError(error) => error.problems(),
+            StrategyFileError::DbMismatchError(error) => error.problems(),
+        }
+    }
+}
+
+/// A single problem with a column (or a table) of the strategy file, so that each can be logged
+/// with the table and column as fields
+#[derive(Clone, Debug, PartialEq)]
+pub struct Problem {
+    pub table_name: String,
+    pub column_name: Option<String>,
+    pub description: String,
+}
+
+fn column_problems(columns: &[SimpleColumn], description: &str) -> Vec<Problem> {
+    columns
+        .iter()
+        .sorted()
+        .map(|c| Problem {
+            table_name: c.table_name.clone(),
+            column_name: Some(c.column_name.clone()),
+            description: description.to_string(),
+        })
+        .collect()
+}
+
 impl From<ValidationErrors> for StrategyFileError {
     fn from(err: ValidationErrors) -> Self {
         StrategyFileError::ValidationError(Box::new(err))
@@ -33,10 +63,33 @@ impl From<DbErrors> for StrategyFileError {
 pub struct DbErrors {
     pub missing_from_strategy_file: Vec<SimpleColumn>,
     pub missing_from_db: Vec<SimpleColumn>,
+    pub nulled_non_nullable_columns: Vec<SimpleColumn>,
 }
 impl DbErrors {
     pub fn is_empty(to_check: &DbErrors) -> bool {
-        to_check.missing_from_strategy_file.is_empty() && to_check.missing_from_db.is_empty()
+        to_check.missing_from_strategy_file.is_empty()
+            && to_check.missing_from_db.is_empty()
+            && to_check.nulled_non_nullable_columns.is_empty()
+    }
+}
+
+impl DbErrors {
+    pub fn problems(&self) -> Vec<Problem> {
+        [
+            column_problems(
+                &self.missing_from_db,
+                "In the strategy file but not the database",
+            ),
+            column_problems(
+                &self.missing_from_strategy_file,
+                "Missing from the strategy file",
+            ),
+            column_problems(
+                &self.nulled_non_nullable_columns,
+                "Has a transformer that can set it to null, but is NOT NULL in the database",
+            ),
+        ]
+        .concat()
     }
 }
 
@@ -62,6 +115,16 @@ impl fmt::Display for DbErrors {
             )
             .unwrap()
         }
+
+        if !self.nulled_non_nullable_columns.is_empty() {
+            let nulled_list = column_to_message(&self.nulled_non_nullable_columns);
+            write!(
+                message,
+                "Some fields have transformers that can set them to null, but are NOT NULL in the database\n\t{}\n",
+                nulled_list
+            )
+            .unwrap()
+        }
         write!(f, "{}", message)
     }
 }
@@ -75,6 +138,8 @@ pub struct ValidationErrors {
     pub duplicate_tables: Vec<String>,
     pub deterministic_without_id: Vec<SimpleColumn>,
     pub invalid_custom_classifications: Vec<SimpleColumn>,
+    pub invalid_transformer_args: Vec<(SimpleColumn, String)>,
+    pub incompatible_column_types: Vec<(SimpleColumn, String)>,
 }
 
 impl fmt::Display for ValidationErrors {
@@ -122,6 +187,16 @@ impl fmt::Display for ValidationErrors {
             .unwrap()
         }
 
+        if !self.deterministic_without_id.is_empty() {
+            let missing_list = column_to_message(&self.deterministic_without_id);
+            write!(
+                message,
+                "Deterministic transformers without an 'id_column' arg found in strategy file\n\t{}\n\n",
+                missing_list
+            )
+            .unwrap()
+        }
+
         if !self.invalid_custom_classifications.is_empty() {
             let missing_list = column_to_message(&self.invalid_custom_classifications);
             write!(
@@ -132,9 +207,35 @@ impl fmt::Display for ValidationErrors {
             .unwrap()
         }
 
+        if !self.invalid_transformer_args.is_empty() {
+            write!(
+                message,
+                "Invalid transformer args found in strategy file\n\t{}\n\n",
+                reasons_to_message(&self.invalid_transformer_args)
+            )
+            .unwrap()
+        }
+
+        if !self.incompatible_column_types.is_empty() {
+            write!(
+                message,
+                "Transformers which don't match the column's type in the database found in strategy file\n\t{}\n\n",
+                reasons_to_message(&self.incompatible_column_types)
+            )
+            .unwrap()
+        }
+
         write!(f, "{}", message)
     }
 }
+fn reasons_to_message(reasons: &[(SimpleColumn, String)]) -> String {
+    reasons
+        .iter()
+        .map(|(c, reason)| format!("{} => {}: {}", &c.table_name, &c.column_name, reason))
+        .sorted()
+        .join("\n\t")
+}
+
 fn column_to_message(column: &[SimpleColumn]) -> String {
     column
         .iter()
@@ -153,8 +254,70 @@ impl ValidationErrors {
             duplicate_tables: Vec::new(),
             deterministic_without_id: Vec::new(),
             invalid_custom_classifications: Vec::new(),
+            invalid_transformer_args: Vec::new(),
+            incompatible_column_types: Vec::new(),
         }
     }
+    pub fn problems(&self) -> Vec<Problem> {
+        let duplicate_tables = self
+            .duplicate_tables
+            .iter()
+            .sorted()
+            .map(|table_name| Problem {
+                table_name: table_name.clone(),
+                column_name: None,
+                description: "Table defined more than once".to_string(),
+            });
+        let invalid_transformer_args =
+            self.invalid_transformer_args
+                .iter()
+                .sorted()
+                .map(|(c, reason)| Problem {
+                    table_name: c.table_name.clone(),
+                    column_name: Some(c.column_name.clone()),
+                    description: format!("Invalid transformer args: {}", reason),
+                });
+        let incompatible_column_types =
+            self.incompatible_column_types
+                .iter()
+                .sorted()
+                .map(|(c, reason)| Problem {
+                    table_name: c.table_name.clone(),
+                    column_name: Some(c.column_name.clone()),
+                    description: format!("Transformer doesn't match the column's type: {}", reason),
+                });
+
+        [
+            column_problems(
+                &self.unanonymised_pii,
+                "Tagged as being PII but doesn't have an anonymising transformer",
+            ),
+            column_problems(
+                &self.error_transformer_types,
+                "Still has the 'Error' transformer",
+            ),
+            column_problems(
+                &self.unknown_data_categories,
+                "Still has the 'Unknown' data category",
+            ),
+            column_problems(&self.duplicate_columns, "Column defined more than once"),
+            column_problems(
+                &self.deterministic_without_id,
+                "Deterministic transformer without an 'id_column' arg",
+            ),
+            column_problems(
+                &self.invalid_custom_classifications,
+                "Has an invalid custom classification",
+            ),
+        ]
+        .concat()
+        .into_iter()
+        .chain(duplicate_tables)
+        .chain(invalid_transformer_args)
+        .chain(incompatible_column_types)
+        .collect()
+    }
+
     pub fn is_empty(to_check: &ValidationErrors) -> bool {
         to_check.unknown_data_categories.is_empty()
             && to_check.error_transformer_types.is_empty()
@@ -163,5 +326,73 @@ impl ValidationErrors {
             && to_check.duplicate_tables.is_empty()
             && to_check.deterministic_without_id.is_empty()
             && to_check.invalid_custom_classifications.is_empty()
+            && to_check.invalid_transformer_args.is_empty()
+            && to_check.incompatible_column_types.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn column(table_name: &str, column_name: &str) -> SimpleColumn {
+        SimpleColumn {
+            table_name: table_name.to_string(),
+            column_name: column_name.to_string(),
+        }
+    }
+
+    #[test]
+    fn lists_each_problem_with_its_table_and_column() {
+        let mut errors = ValidationErrors::new();
+        errors.error_transformer_types = vec![
+            column("public.users", "last_name"),
+            column("public.users", "email"),
+        ];
+        errors.deterministic_without_id = vec![column("public.users", "first_name")];
+        errors.duplicate_tables = vec!["public.orders".to_string()];
+        errors.invalid_transformer_args = vec![(
+            column("public.users", "phone"),
+            "unknown arg 'format'".to_string(),
+        )];
+
+        let problems: Vec<(String, Option<String>, String)> = StrategyFileError::from(errors)
+            .problems()
+            .into_iter()
+            .map(|p| (p.table_name, p.column_name, p.description))
+            .collect();
+        let problem = |table: &str, column: Option<&str>, description: &str| {
+            (
+                table.to_string(),
+                column.map(String::from),
+                description.to_string(),
+            )
+        };
+        assert_eq!(
+            problems,
+            vec![
+                problem(
+                    "public.users",
+                    Some("email"),
+                    "Still has the 'Error' transformer"
+                ),
+                problem(
+                    "public.users",
+                    Some("last_name"),
+                    "Still has the 'Error' transformer"
+                ),
+                problem(
+                    "public.users",
+                    Some("first_name"),
+                    "Deterministic transformer without an 'id_column' arg"
+                ),
+                problem("public.orders", None, "Table defined more than once"),
+                problem(
+                    "public.users",
+                    Some("phone"),
+                    "Invalid transformer args: unknown arg 'format'"
+                ),
+            ]
+        );
     }
 }
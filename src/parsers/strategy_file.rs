@@ -1,43 +1,415 @@
 use crate::parsers::custom_classifications::ClassificationConfig;
 use crate::parsers::strategy_structs::*;
+use crate::parsers::{strategy_schema, toml, yaml};
 use itertools::sorted;
 use itertools::Itertools;
-use serde_json;
+use schemars::JsonSchema;
+use serde::Serialize;
+use serde_json::{self, Value};
+use std::borrow::Cow;
+use std::collections::HashMap;
 use std::fs;
 use std::io::Write;
+use std::path::{Path, PathBuf};
 
-pub fn read(file_name: &str) -> Result<Vec<StrategyInFile>, std::io::Error> {
-    let result = fs::read_to_string(file_name).map(|file_contents| {
-        serde_json::from_str::<Vec<StrategyInFile>>(&file_contents).unwrap_or_else(|e| {
+/// The format of a strategy file, chosen by its extension. YAML and TOML files can have comments,
+/// which are kept (along with the order of keys) when the file is rewritten
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Format {
+    Json,
+    Yaml,
+    Toml,
+}
+
+impl Format {
+    fn from_file_name(file_name: &str) -> Format {
+        let extension = Path::new(file_name)
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .map(str::to_lowercase);
+        match extension.as_deref() {
+            Some("yaml") | Some("yml") => Format::Yaml,
+            Some("toml") => Format::Toml,
+            _ => Format::Json,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Format::Json => "json",
+            Format::Yaml => "yaml",
+            Format::Toml => "toml",
+        }
+    }
+}
+
+/// The key of an entry that includes another strategy file (or a directory of them), relative to
+/// the file it is in
+const INCLUDE_KEY: &str = "include";
+
+/// A file making up the strategy file, with the files it includes and its own tables
+struct Source {
+    file_name: String,
+    includes: Vec<String>,
+    strategies: Vec<StrategyInFile>,
+}
+
+/// An entry of a strategy file, which is either a table or an include
+#[derive(Serialize, JsonSchema)]
+#[serde(untagged)]
+#[schemars(deny_unknown_fields)]
+pub enum Entry<'a> {
+    Include {
+        /// A strategy file or directory to include, relative to this file
+        include: &'a str,
+    },
+    Strategy(&'a StrategyInFile),
+}
+
+fn from_str(
+    format: Format,
+    file_contents: &str,
+) -> Result<(Vec<String>, Vec<StrategyInFile>), String> {
+    let value = match format {
+        Format::Json => serde_json::from_str(file_contents).map_err(|e| format!("{:#}", e))?,
+        Format::Yaml => serde_yaml_ng::from_str(file_contents).map_err(|e| e.to_string())?,
+        Format::Toml => match toml::parse(file_contents)? {
+            Value::Object(mut tables) => tables
+                .remove(toml::TABLES_KEY)
+                .unwrap_or_else(|| Value::Array(Vec::new())),
+            _ => Value::Array(Vec::new()),
+        },
+    };
+    strategy_schema::validate(&value)?;
+    if let Format::Json = format {
+        // Anything the schema misses is reported with where in the file it is
+        serde_json::from_str::<Vec<StrategyInFile>>(file_contents)
+            .map_err(|e| format!("{:#}", e))?;
+    }
+    let mut entries: Vec<Value> = serde_json::from_value(value).map_err(|e| format!("{:#}", e))?;
+    let includes = entries
+        .iter()
+        .filter_map(|entry| entry.get(INCLUDE_KEY))
+        .map(|include| {
+            include
+                .as_str()
+                .map(String::from)
+                .ok_or_else(|| format!("include must be a path, not {}", include))
+        })
+        .collect::<Result<Vec<String>, String>>()?;
+    entries.retain(|entry| entry.get(INCLUDE_KEY).is_none());
+    let strategies =
+        serde_json::from_value(Value::Array(entries)).map_err(|e| format!("{:#}", e))?;
+    Ok((includes, strategies))
+}
+
+/// Writes the includes and strategies in `format`. For YAML and TOML the comments and key order of
+/// the file being replaced (if there is one) are kept
+fn to_string(
+    format: Format,
+    file_name: &str,
+    includes: &[String],
+    strategies: &[StrategyInFile],
+) -> String {
+    let strategies = match format {
+        Format::Toml => Cow::Owned(toml::without_null_args(strategies)),
+        _ => Cow::Borrowed(strategies),
+    };
+    let entries: Vec<Entry> = includes
+        .iter()
+        .map(|include| Entry::Include { include })
+        .chain(strategies.iter().map(Entry::Strategy))
+        .collect();
+    let replacing = fs::read_to_string(file_name).ok();
+    match format {
+        Format::Json => serde_json::to_string_pretty(&entries).unwrap(),
+        Format::Yaml => yaml::write(&entries, replacing.as_deref()).unwrap_or_else(|e| {
             panic!(
-                "Invalid json found in strategy file at '{}': {:#}",
+                "Unable to write strategy file '{}' as yaml: {}",
                 file_name, e
             )
-        })
+        }),
+        Format::Toml => toml::write(&entries, replacing.as_deref()).unwrap_or_else(|e| {
+            panic!(
+                "Unable to write strategy file '{}' as toml: {}",
+                file_name, e
+            )
+        }),
+    }
+}
+
+/// Reads the strategy file at `path` and the files it includes. If `path` is a directory every
+/// strategy file in it is read, in order of their names
+fn read_sources(path: &str) -> std::io::Result<Vec<Source>> {
+    let mut sources = Vec::new();
+    add_sources(Path::new(path), &mut sources)?;
+    Ok(sources)
+}
+
+fn add_sources(path: &Path, sources: &mut Vec<Source>) -> std::io::Result<()> {
+    if path.is_dir() {
+        let mut files: Vec<PathBuf> = fs::read_dir(path)?
+            .map(|entry| entry.map(|entry| entry.path()))
+            .collect::<std::io::Result<Vec<PathBuf>>>()?;
+        files.retain(|file| is_strategy_file(file));
+        files.sort();
+        for file in files {
+            add_sources(&file, sources)?;
+        }
+        return Ok(());
+    }
+
+    let file_name = path.to_string_lossy().to_string();
+    let file_contents = fs::read_to_string(path)?;
+    if sources
+        .iter()
+        .any(|source| same_file(&source.file_name, &file_name))
+    {
+        panic!("Strategy file '{}' is included more than once", file_name);
+    }
+    let format = Format::from_file_name(&file_name);
+    let (includes, strategies) = from_str(format, &file_contents).unwrap_or_else(|e| {
+        panic!(
+            "Invalid {} found in strategy file at '{}': {}",
+            format.name(),
+            file_name,
+            e
+        )
+    });
+    sources.push(Source {
+        file_name: file_name.clone(),
+        includes: includes.clone(),
+        strategies,
     });
 
-    match result {
-        Ok(_) => result,
-        Err(ref err) => match err.kind() {
-            std::io::ErrorKind::NotFound => result,
+    let directory = path.parent().unwrap_or_else(|| Path::new(""));
+    for include in includes {
+        add_sources(&directory.join(&include), sources).unwrap_or_else(|err| {
+            panic!(
+                "Unable to read strategy file '{}' included from '{}': {:?}",
+                include, file_name, err
+            )
+        });
+    }
+    Ok(())
+}
+
+fn is_strategy_file(path: &Path) -> bool {
+    let is_hidden = path
+        .file_name()
+        .is_some_and(|name| name.to_string_lossy().starts_with('.'));
+    let extension = path
+        .extension()
+        .map(|extension| extension.to_string_lossy().to_lowercase());
+    path.is_file()
+        && !is_hidden
+        && matches!(
+            extension.as_deref(),
+            Some("json") | Some("yaml") | Some("yml") | Some("toml")
+        )
+}
+
+fn same_file(a: &str, b: &str) -> bool {
+    match (fs::canonicalize(a), fs::canonicalize(b)) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => a == b,
+    }
+}
+
+/// Merges the tables of every file, with the global salt and locale entries (which have no table
+/// name) first
+fn merge(sources: Vec<Source>) -> Vec<StrategyInFile> {
+    let mut defined_in: HashMap<&str, &str> = HashMap::new();
+    for source in &sources {
+        for strategy in &source.strategies {
+            if strategy.table_name.is_empty() {
+                continue;
+            }
+            match defined_in.get(strategy.table_name.as_str()) {
+                Some(other_file) if *other_file != source.file_name => panic!(
+                    "Table '{}' is defined in both '{}' and '{}'",
+                    strategy.table_name, other_file, source.file_name
+                ),
+                _ => defined_in.insert(&strategy.table_name, &source.file_name),
+            };
+        }
+    }
+
+    let (globals, tables): (Vec<StrategyInFile>, Vec<StrategyInFile>) = sources
+        .into_iter()
+        .flat_map(|source| source.strategies)
+        .partition(|strategy| strategy.table_name.is_empty());
+    globals.into_iter().chain(tables).collect()
+}
+
+/// The names of the files that make up the strategy file at `file_name`
+pub fn files(file_name: &str) -> Vec<String> {
+    read_sources(file_name)
+        .map(|sources| sources.into_iter().map(|source| source.file_name).collect())
+        .unwrap_or_default()
+}
+
+pub fn read(file_name: &str) -> Result<Vec<StrategyInFile>, std::io::Error> {
+    match read_sources(file_name) {
+        Ok(sources) => Ok(merge(sources)),
+        Err(err) => match err.kind() {
+            std::io::ErrorKind::NotFound => Err(err),
             _ => panic!("Unable to read strategy file at {}: {:?}", file_name, err),
         },
     }
 }
 
-pub fn write(file_name: &str, mut new_file_contents: Vec<StrategyInFile>) -> std::io::Result<()> {
+/// Writes the strategies back to the files they were read from. When the strategy file is split
+/// across several files, new tables go to the file for their schema
+pub fn write(file_name: &str, new_file_contents: Vec<StrategyInFile>) -> std::io::Result<()> {
+    if !Path::new(file_name).is_dir() && includes_of(file_name).is_empty() {
+        return write_file(file_name, &[], new_file_contents);
+    }
+    let sources = read_sources(file_name)?;
+
+    let mut files: Vec<Source> = sources
+        .iter()
+        .map(|source| Source {
+            file_name: source.file_name.clone(),
+            includes: source.includes.clone(),
+            strategies: Vec::new(),
+        })
+        .collect();
+    for strategy in new_file_contents {
+        let target = file_for(&strategy, file_name, &sources);
+        match files.iter_mut().find(|file| file.file_name == target) {
+            Some(file) => file.strategies.push(strategy),
+            None => files.push(Source {
+                file_name: target,
+                includes: Vec::new(),
+                strategies: vec![strategy],
+            }),
+        }
+    }
+    for file in files {
+        write_file(&file.file_name, &file.includes, file.strategies)?;
+    }
+    Ok(())
+}
+
+/// The files included by the strategy file at `file_name`, if it can be read
+fn includes_of(file_name: &str) -> Vec<String> {
+    fs::read_to_string(file_name)
+        .ok()
+        .and_then(|file_contents| from_str(Format::from_file_name(file_name), &file_contents).ok())
+        .map(|(includes, _)| includes)
+        .unwrap_or_default()
+}
+
+/// The file a table belongs in: the one it is already in, otherwise the file named after its
+/// schema, its own file when every file holds one table named after it, or a file that already has
+/// tables in its schema. Failing those it goes to a new file for its schema (in a directory) or
+/// the root file
+fn file_for(strategy: &StrategyInFile, root: &str, sources: &[Source]) -> String {
+    if let Some(source) = sources.iter().find(|source| {
+        source
+            .strategies
+            .iter()
+            .any(|existing| existing.table_name == strategy.table_name)
+    }) {
+        return source.file_name.clone();
+    }
+
+    let root_is_directory = Path::new(root).is_dir();
+    let included: Vec<&Source> = sources
+        .iter()
+        .filter(|source| root_is_directory || !same_file(&source.file_name, root))
+        .collect();
+    if strategy.table_name.is_empty() {
+        return match (root_is_directory, included.first()) {
+            (false, _) => root.to_string(),
+            (true, Some(source)) => source.file_name.clone(),
+            (true, None) => new_file(Path::new(root), "strategy", "json"),
+        };
+    }
+
+    let schema = schema_of(&strategy.table_name);
+    let extension = included
+        .first()
+        .and_then(|source| Path::new(&source.file_name).extension())
+        .map(|extension| extension.to_string_lossy().to_string())
+        .unwrap_or_else(|| "json".to_string());
+    if let Some(source) = included
+        .iter()
+        .find(|source| file_stem(&source.file_name) == schema)
+    {
+        return source.file_name.clone();
+    }
+    let one_table_per_file = !included.is_empty()
+        && included.iter().all(|source| {
+            !source.strategies.is_empty()
+                && source
+                    .strategies
+                    .iter()
+                    .all(|existing| existing.table_name == file_stem(&source.file_name))
+        });
+    if one_table_per_file {
+        let directory = Path::new(&included[0].file_name)
+            .parent()
+            .unwrap_or_else(|| Path::new(""));
+        return new_file(directory, &strategy.table_name, &extension);
+    }
+    if let Some(source) = included.iter().find(|source| {
+        source
+            .strategies
+            .iter()
+            .any(|existing| schema_of(&existing.table_name) == schema)
+    }) {
+        return source.file_name.clone();
+    }
+    if root_is_directory {
+        new_file(Path::new(root), schema, &extension)
+    } else {
+        root.to_string()
+    }
+}
+
+fn schema_of(table_name: &str) -> &str {
+    table_name.split('.').next().unwrap_or(table_name)
+}
+
+fn file_stem(file_name: &str) -> String {
+    Path::new(file_name)
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
+
+fn new_file(directory: &Path, name: &str, extension: &str) -> String {
+    directory
+        .join(format!("{}.{}", name, extension))
+        .to_string_lossy()
+        .to_string()
+}
+
+fn write_file(
+    file_name: &str,
+    includes: &[String],
+    mut new_file_contents: Vec<StrategyInFile>,
+) -> std::io::Result<()> {
     new_file_contents.sort();
 
     for s in new_file_contents.iter_mut() {
         sort_columns(s)
     }
-    let file = fs::OpenOptions::new()
+    let to_write = to_string(
+        Format::from_file_name(file_name),
+        file_name,
+        includes,
+        &new_file_contents,
+    );
+    let mut file = fs::OpenOptions::new()
         .write(true)
         .truncate(true)
         .create(true)
         .open(file_name)?;
 
-    serde_json::to_writer_pretty(file, &new_file_contents)?;
+    file.write_all(to_write.as_bytes())?;
 
     Ok(())
 }
@@ -115,3 +487,270 @@ pub fn to_csv(
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::NamedTempFile;
+
+    fn strategy_file(suffix: &str, contents: &str) -> NamedTempFile {
+        let mut file = tempfile::Builder::new().suffix(suffix).tempfile().unwrap();
+        file.write_all(contents.as_bytes()).unwrap();
+        file
+    }
+
+    fn path(file: &NamedTempFile) -> &str {
+        file.path().to_str().unwrap()
+    }
+
+    fn users_table() -> StrategyInFile {
+        let mut email = ColumnInFile::new("email");
+        email.data_category = DataCategory::Pii;
+        email.transformer.name = TransformerType::FakeEmail;
+        StrategyInFile {
+            table_name: "public.users".to_string(),
+            description: "".to_string(),
+            truncate: false,
+            salt: None,
+            locale: None,
+            columns: vec![ColumnInFile::new("created_at"), email],
+        }
+    }
+
+    #[test]
+    fn the_format_is_chosen_by_extension() {
+        assert_eq!(Format::from_file_name("strategy.json"), Format::Json);
+        assert_eq!(Format::from_file_name("./strategy.yaml"), Format::Yaml);
+        assert_eq!(Format::from_file_name("strategy.YML"), Format::Yaml);
+        assert_eq!(Format::from_file_name("config/strategy.toml"), Format::Toml);
+        assert_eq!(Format::from_file_name("strategy"), Format::Json);
+    }
+
+    #[test]
+    fn reads_what_was_written_in_every_format() {
+        for suffix in [".json", ".yaml", ".toml"] {
+            let file = strategy_file(suffix, "");
+            write(path(&file), vec![users_table()]).unwrap();
+            let read_back = read(path(&file)).unwrap();
+            assert_eq!(read_back, vec![users_table()], "{}", suffix);
+            assert_eq!(
+                read_back[0].columns[1].transformer.name,
+                TransformerType::FakeEmail,
+                "{}",
+                suffix
+            );
+        }
+    }
+
+    #[test]
+    fn rewriting_a_yaml_file_keeps_its_comments() {
+        let file = strategy_file(
+            ".yaml",
+            "# Reviewed by the data team\n\n- table_name: public.users\n  description: \"\"\n  truncate: false\n  columns:\n    # Only used for login\n    - name: email\n      data_category: Pii # confirmed\n      description: \"\"\n      transformer:\n        name: FakeEmail\n        args: null\n",
+        );
+        let mut strategies = read(path(&file)).unwrap();
+        strategies[0].columns.push(ColumnInFile::new("created_at"));
+        write(path(&file), strategies).unwrap();
+
+        assert_eq!(
+            fs::read_to_string(path(&file)).unwrap(),
+            "# Reviewed by the data team\n\n- table_name: public.users\n  description: ''\n  truncate: false\n  columns:\n  - data_category: Unknown\n    description: ''\n    name: created_at\n    transformer:\n      name: Error\n  # Only used for login\n  - name: email\n    data_category: Pii # confirmed\n    description: ''\n    transformer:\n      name: FakeEmail\n"
+        );
+    }
+
+    #[test]
+    fn comments_in_a_yaml_file_move_with_their_table_when_it_is_sorted() {
+        let file = strategy_file(
+            ".yaml",
+            "# Strategies for the app\n\n- table_name: public.users # main table\n  truncate: false\n  description: |\n    Not a # comment\n  columns: []\n# then accounts\n- table_name: public.accounts\n  truncate: true # no longer needed\n  description: ''\n  columns: []\n",
+        );
+        let strategies = read(path(&file)).unwrap();
+        write(path(&file), strategies).unwrap();
+
+        assert_eq!(
+            fs::read_to_string(path(&file)).unwrap(),
+            "# Strategies for the app\n\n# then accounts\n- table_name: public.accounts\n  truncate: true # no longer needed\n  description: ''\n  columns: []\n- table_name: public.users # main table\n  truncate: false\n  description: |\n    Not a # comment\n  columns: []\n"
+        );
+    }
+
+    #[test]
+    fn rewriting_a_toml_file_keeps_its_comments() {
+        let file = strategy_file(
+            ".toml",
+            "# Reviewed by the data team\n\n[[tables]]\ntable_name = \"public.users\"\n\n# Only used for login\n[[tables.columns]]\nname = \"email\"\ndata_category = \"Pii\" # confirmed\ndescription = \"\"\ntransformer = { name = \"FakeEmail\" }\n",
+        );
+        let mut strategies = read(path(&file)).unwrap();
+        strategies[0].columns.push(ColumnInFile::new("created_at"));
+        write(path(&file), strategies).unwrap();
+
+        assert_eq!(
+            fs::read_to_string(path(&file)).unwrap(),
+            "# Reviewed by the data team\n\n[[tables]]\ntable_name = \"public.users\"\ndescription = \"\"\ntruncate = false\n\n[[tables.columns]]\ndata_category = \"Unknown\"\ndescription = \"\"\nname = \"created_at\"\ntransformer = { name = \"Error\" }\n\n# Only used for login\n[[tables.columns]]\nname = \"email\"\ndata_category = \"Pii\" # confirmed\ndescription = \"\"\ntransformer = { name = \"FakeEmail\" }\n"
+        );
+    }
+
+    #[test]
+    fn toml_files_leave_out_null_args() {
+        let file = strategy_file(".toml", "");
+        let mut strategies = vec![users_table()];
+        strategies[0].columns[1].transformer.args =
+            Some(HashMap::from([("domain".to_string(), Value::Null)]));
+        write(path(&file), strategies).unwrap();
+
+        assert!(fs::read_to_string(path(&file))
+            .unwrap()
+            .contains("transformer = { name = \"FakeEmail\", args = {} }\n"));
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid yaml found in strategy file")]
+    fn panics_on_invalid_yaml() {
+        let file = strategy_file(".yaml", "- table_name: a\n   columns: []\n");
+        let _ = read(path(&file));
+    }
+
+    fn table(table_name: &str) -> StrategyInFile {
+        StrategyInFile {
+            table_name: table_name.to_string(),
+            description: "".to_string(),
+            truncate: false,
+            salt: None,
+            locale: None,
+            columns: vec![ColumnInFile::new("id")],
+        }
+    }
+
+    fn table_names(file_name: &str) -> Vec<String> {
+        let (_, strategies) = from_str(
+            Format::from_file_name(file_name),
+            &fs::read_to_string(file_name).unwrap(),
+        )
+        .unwrap();
+        strategies
+            .into_iter()
+            .map(|strategy| strategy.table_name)
+            .collect()
+    }
+
+    #[test]
+    fn reads_every_file_in_a_directory() {
+        let directory = tempfile::tempdir().unwrap();
+        let file_name = |name: &str| directory.path().join(name).to_string_lossy().to_string();
+        write_file(&file_name("public.json"), &[], vec![table("public.users")]).unwrap();
+        write_file(&file_name("audit.yaml"), &[], vec![table("audit.events")]).unwrap();
+        fs::write(file_name("README.md"), "Not a strategy file").unwrap();
+
+        let strategies = read(directory.path().to_str().unwrap()).unwrap();
+        let names: Vec<&str> = strategies
+            .iter()
+            .map(|strategy| strategy.table_name.as_str())
+            .collect();
+        assert_eq!(names, vec!["audit.events", "public.users"]);
+    }
+
+    #[test]
+    fn reads_included_files_relative_to_the_file_including_them() {
+        let directory = tempfile::tempdir().unwrap();
+        let file_name = |name: &str| directory.path().join(name).to_string_lossy().to_string();
+        fs::create_dir(file_name("tables")).unwrap();
+        write_file(
+            &file_name("tables/public.toml"),
+            &[],
+            vec![table("public.users")],
+        )
+        .unwrap();
+        let mut global = table("");
+        global.salt = Some("salt".to_string());
+        global.columns = Vec::new();
+        write_file(
+            &file_name("strategy.yaml"),
+            &["tables".to_string()],
+            vec![global],
+        )
+        .unwrap();
+
+        let strategies = read(&file_name("strategy.yaml")).unwrap();
+        assert_eq!(strategies[0].salt, Some("salt".to_string()));
+        assert_eq!(strategies[1].table_name, "public.users");
+        assert_eq!(strategies.len(), 2);
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "table 'public.users', column 'email': unknown transformer 'FakeEmial', did you mean 'FakeEmail'?"
+    )]
+    fn panics_with_the_table_and_column_of_a_misspelt_transformer() {
+        let file = strategy_file(
+            ".yaml",
+            "- table_name: public.users\n  columns:\n    - name: email\n      data_category: Pii\n      description: ''\n      transformer:\n        name: FakeEmial\n",
+        );
+        let _ = read(path(&file));
+    }
+
+    #[test]
+    #[should_panic(expected = "Table 'public.users' is defined in both")]
+    fn panics_when_a_table_is_in_more_than_one_file() {
+        let directory = tempfile::tempdir().unwrap();
+        let file_name = |name: &str| directory.path().join(name).to_string_lossy().to_string();
+        write_file(&file_name("a.json"), &[], vec![table("public.users")]).unwrap();
+        write_file(&file_name("b.json"), &[], vec![table("public.users")]).unwrap();
+
+        let _ = read(directory.path().to_str().unwrap());
+    }
+
+    #[test]
+    fn writes_new_tables_to_the_file_for_their_schema() {
+        let directory = tempfile::tempdir().unwrap();
+        let file_name = |name: &str| directory.path().join(name).to_string_lossy().to_string();
+        write_file(&file_name("public.yaml"), &[], vec![table("public.users")]).unwrap();
+        write_file(&file_name("audit.yaml"), &[], vec![table("audit.events")]).unwrap();
+
+        let mut strategies = read(directory.path().to_str().unwrap()).unwrap();
+        strategies.extend([table("public.orders"), table("billing.invoices")]);
+        write(directory.path().to_str().unwrap(), strategies).unwrap();
+
+        assert_eq!(
+            table_names(&file_name("public.yaml")),
+            vec!["public.orders", "public.users"]
+        );
+        assert_eq!(table_names(&file_name("audit.yaml")), vec!["audit.events"]);
+        assert_eq!(
+            table_names(&file_name("billing.yaml")),
+            vec!["billing.invoices"]
+        );
+    }
+
+    #[test]
+    fn writes_new_tables_to_their_own_file_when_there_is_one_file_per_table() {
+        let directory = tempfile::tempdir().unwrap();
+        let file_name = |name: &str| directory.path().join(name).to_string_lossy().to_string();
+        fs::create_dir(file_name("tables")).unwrap();
+        write_file(
+            &file_name("tables/public.users.json"),
+            &[],
+            vec![table("public.users")],
+        )
+        .unwrap();
+        write_file(&file_name("strategy.json"), &["tables".to_string()], vec![]).unwrap();
+
+        let mut strategies = read(&file_name("strategy.json")).unwrap();
+        strategies.push(table("public.orders"));
+        write(&file_name("strategy.json"), strategies).unwrap();
+
+        assert_eq!(
+            table_names(&file_name("tables/public.orders.json")),
+            vec!["public.orders"]
+        );
+        assert_eq!(
+            table_names(&file_name("tables/public.users.json")),
+            vec!["public.users"]
+        );
+        let (includes, strategies) = from_str(
+            Format::Json,
+            &fs::read_to_string(file_name("strategy.json")).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(includes, vec!["tables"]);
+        assert!(strategies.is_empty());
+    }
+}
@@ -0,0 +1,599 @@
+use crate::parsers::strategy_file::Entry;
+use lazy_static::lazy_static;
+use regex::Regex;
+use schemars::generate::SchemaSettings;
+use serde_json::{Map, Value};
+
+lazy_static! {
+    static ref SCHEMA: Value = schema();
+}
+
+/// What a `$ref` to a missing definition allows, which is anything
+static ANYTHING: Value = Value::Bool(true);
+
+/// The JSON Schema (draft 7) of the strategy file, generated from the structs it is read into,
+/// which editors can use to check and complete it
+pub fn schema() -> Value {
+    let generator = SchemaSettings::draft07().into_generator();
+    let mut schema = generator.into_root_schema_for::<Vec<Entry<'static>>>();
+    schema.insert("title".to_string(), Value::from("Anonymiser strategy file"));
+    schema.to_value()
+}
+
+/// Checks a strategy file (read in as json) against the schema, so mistakes are reported with the
+/// table and column they are in (and a suggestion for misspelt names) rather than as a serde error
+pub fn validate(value: &Value) -> Result<(), String> {
+    let mut checker = Checker {
+        definitions: SCHEMA["definitions"].as_object().unwrap(),
+        errors: Vec::new(),
+    };
+    match value {
+        Value::Array(entries) => {
+            for (index, entry) in entries.iter().enumerate() {
+                checker.check_entry(index, &SCHEMA["items"], entry);
+            }
+        }
+        _ => checker
+            .errors
+            .push("the strategy file must be a list of tables".to_string()),
+    }
+    if checker.errors.is_empty() {
+        Ok(())
+    } else {
+        Err(format!("\n\t{}", checker.errors.join("\n\t")))
+    }
+}
+
+/// Checks values against the parts of JSON Schema the generated schema uses, naming tables,
+/// columns and transformers in the errors
+struct Checker<'a> {
+    definitions: &'a Map<String, Value>,
+    errors: Vec<String>,
+}
+
+impl<'a> Checker<'a> {
+    /// Follows a `$ref` to its definition, along with the name of the definition
+    fn resolve(&self, schema: &'a Value) -> (Option<&'a str>, &'a Value) {
+        match schema
+            .get("$ref")
+            .and_then(Value::as_str)
+            .and_then(|reference| reference.strip_prefix("#/definitions/"))
+        {
+            Some(name) => (Some(name), self.definitions.get(name).unwrap_or(&ANYTHING)),
+            None => (None, schema),
+        }
+    }
+
+    fn check_entry(&mut self, index: usize, schema: &'a Value, entry: &Value) {
+        let Value::Object(entry) = entry else {
+            self.errors.push(format!(
+                "entry {}: expected an object, not {}",
+                index, entry
+            ));
+            return;
+        };
+        let context = match entry.get("table_name").and_then(Value::as_str) {
+            Some(table_name) if !table_name.is_empty() => format!("table '{}'", table_name),
+            _ => format!("entry {}", index),
+        };
+        // Includes and tables are told apart by their keys, with tables as the fallback
+        let branches: Vec<&Value> = branches(self.resolve(schema).1)
+            .into_iter()
+            .flatten()
+            .map(|branch| self.resolve(branch).1)
+            .collect();
+        let known_keys = |branch: &Value| {
+            entry
+                .keys()
+                .filter(|key| branch["properties"].get(key.as_str()).is_some())
+                .count()
+        };
+        if let Some(branch) = branches
+            .iter()
+            .rev()
+            .max_by_key(|branch| known_keys(branch))
+        {
+            self.check_object(&context, branch, entry, None);
+        }
+    }
+
+    fn check(&mut self, context: &str, schema: &'a Value, value: &Value) {
+        let (definition, schema) = self.resolve(schema);
+        match (definition, value) {
+            (Some("Transformer"), _) => return self.check_transformer(context, value),
+            (Some("ColumnInFile"), Value::Object(column)) => {
+                let context = match column.get("name").and_then(Value::as_str) {
+                    Some(name) => format!("{}, column '{}'", context, name),
+                    None => format!("{}, a column", context),
+                };
+                return self.check_object(&context, schema, column, None);
+            }
+            _ => (),
+        }
+
+        if let Some(branches) = branches(schema) {
+            let branch = branches
+                .iter()
+                .find(|branch| self.is_valid(context, branch, value))
+                .or_else(|| branches.iter().find(|branch| self.matches(branch, value)));
+            if let Some(branch) = branch {
+                self.check(context, branch, value);
+            }
+            return;
+        }
+        match value {
+            Value::Object(object) if schema.get("properties").is_some() => {
+                self.check_object(context, schema, object, None)
+            }
+            Value::Array(items) => {
+                let Some(item_schema) = schema.get("items") else {
+                    return;
+                };
+                for item in items {
+                    if self.matches(item_schema, item) {
+                        self.check(context, item_schema, item);
+                    } else {
+                        self.errors.push(format!(
+                            "{}: expected {}, not {}",
+                            context,
+                            self.expected(item_schema),
+                            describe(item)
+                        ));
+                    }
+                }
+            }
+            _ => (),
+        }
+    }
+
+    /// Whether `value` matches `schema` without any errors, used to pick between alternatives
+    fn is_valid(&self, context: &str, schema: &'a Value, value: &Value) -> bool {
+        let mut trial = Checker {
+            definitions: self.definitions,
+            errors: Vec::new(),
+        };
+        trial.matches(schema, value) && {
+            trial.check(context, schema, value);
+            trial.errors.is_empty()
+        }
+    }
+
+    /// Checks the keys of an object and their values. The keys of args are reported as args of
+    /// `transformer`
+    fn check_object(
+        &mut self,
+        context: &str,
+        schema: &'a Value,
+        object: &Map<String, Value>,
+        transformer: Option<&str>,
+    ) {
+        let properties = schema.get("properties").and_then(Value::as_object);
+        let names: Vec<&str> = properties
+            .into_iter()
+            .flat_map(|properties| properties.keys().map(String::as_str))
+            .collect();
+        if schema.get("additionalProperties") == Some(&Value::Bool(false)) {
+            for key in object.keys().filter(|key| !names.contains(&key.as_str())) {
+                let did_you_mean = did_you_mean(key, &names);
+                self.errors.push(match transformer {
+                    Some(name) => {
+                        format!("{}: {} has no arg '{}'{}", context, name, key, did_you_mean)
+                    }
+                    None => format!("{}: unknown key '{}'{}", context, key, did_you_mean),
+                });
+            }
+        }
+        for key in schema
+            .get("required")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+            .filter_map(Value::as_str)
+            .filter(|key| !object.contains_key(*key))
+        {
+            self.errors.push(match transformer {
+                Some(name) => format!("{}: {} is missing arg '{}'", context, name, key),
+                None => format!("{}: missing '{}'", context, key),
+            });
+        }
+
+        for (key, value) in object {
+            let Some(property) = properties.and_then(|properties| properties.get(key)) else {
+                continue;
+            };
+            if self.matches(property, value) {
+                self.check(context, property, value);
+            } else {
+                let key = match transformer {
+                    Some(name) => format!("{} arg '{}'", name, key),
+                    None => format!("'{}'", key),
+                };
+                self.errors.push(format!(
+                    "{}, {}: expected {}, not {}",
+                    context,
+                    key,
+                    self.expected(property),
+                    describe(value)
+                ));
+            }
+        }
+    }
+
+    fn check_transformer(&mut self, context: &str, transformer: &Value) {
+        let transformer = match transformer {
+            Value::Array(steps) if steps.is_empty() => {
+                self.errors
+                    .push(format!("{}: a pipeline needs at least one step", context));
+                return;
+            }
+            Value::Array(steps) => {
+                for step in steps {
+                    self.check_transformer(context, step);
+                }
+                return;
+            }
+            Value::Object(transformer) => transformer,
+            _ => {
+                self.errors.push(format!(
+                    "{}: expected a transformer object (or a list of them), not {}",
+                    context, transformer
+                ));
+                return;
+            }
+        };
+        let schema = self
+            .definitions
+            .get("SingleTransformer")
+            .unwrap_or(&ANYTHING);
+        let keys: Vec<&str> = schema["properties"]
+            .as_object()
+            .into_iter()
+            .flat_map(|properties| properties.keys().map(String::as_str))
+            .collect();
+        for key in transformer
+            .keys()
+            .filter(|key| !keys.contains(&key.as_str()))
+        {
+            self.errors.push(format!(
+                "{}: unknown key '{}'{}",
+                context,
+                key,
+                did_you_mean(key, &keys)
+            ));
+        }
+
+        let name = match transformer.get("name") {
+            Some(Value::String(name)) => name,
+            Some(name) => {
+                self.errors.push(format!(
+                    "{}: the transformer name can't be {}",
+                    context,
+                    describe(name)
+                ));
+                return;
+            }
+            None => {
+                self.errors
+                    .push(format!("{}: the transformer is missing a 'name'", context));
+                return;
+            }
+        };
+        let args_schema = schema["allOf"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .find(|condition| condition["if"]["properties"]["name"]["const"] == *name)
+            .map(|condition| &condition["then"]["properties"]["args"]);
+        let Some(args_schema) = args_schema else {
+            let names: Vec<&str> = self
+                .resolve(&schema["properties"]["name"])
+                .1
+                .get("enum")
+                .and_then(Value::as_array)
+                .into_iter()
+                .flatten()
+                .filter_map(Value::as_str)
+                .collect();
+            self.errors.push(format!(
+                "{}: unknown transformer '{}'{}",
+                context,
+                name,
+                did_you_mean(name, &names)
+            ));
+            return;
+        };
+
+        match transformer.get("args") {
+            None | Some(Value::Null) => (),
+            Some(Value::Object(args)) => self.check_object(context, args_schema, args, Some(name)),
+            Some(args) => self.errors.push(format!(
+                "{}: the args of {} can't be {}",
+                context,
+                name,
+                describe(args)
+            )),
+        }
+    }
+
+    /// Whether the type of `value` is one `schema` allows, without looking inside objects and lists
+    fn matches(&self, schema: &'a Value, value: &Value) -> bool {
+        let (_, schema) = self.resolve(schema);
+        if let Some(branches) = branches(schema) {
+            return branches.iter().any(|branch| self.matches(branch, value));
+        }
+        if let Some(options) = schema.get("enum").and_then(Value::as_array) {
+            return options.contains(value);
+        }
+        if let Some(constant) = schema.get("const") {
+            return constant == value;
+        }
+        if let (Some(pattern), Value::String(text)) =
+            (schema.get("pattern").and_then(Value::as_str), value)
+        {
+            if !Regex::new(pattern).is_ok_and(|pattern| pattern.is_match(text)) {
+                return false;
+            }
+        }
+        let allowed = types(schema);
+        allowed.is_empty()
+            || allowed.iter().any(|allowed| {
+                *allowed == type_of(value) || (*allowed == "number" && value.is_number())
+            })
+    }
+
+    /// What `schema` allows, for errors
+    fn expected(&self, schema: &'a Value) -> String {
+        let (_, schema) = self.resolve(schema);
+        if let Some(branches) = branches(schema) {
+            return branches
+                .first()
+                .map(|branch| self.expected(branch))
+                .unwrap_or_default();
+        }
+        if let Some(options) = schema.get("enum").and_then(Value::as_array) {
+            if options.iter().any(Value::is_boolean) {
+                return "true or false".to_string();
+            }
+            let options: Vec<String> = options
+                .iter()
+                .map(|option| option.as_str().map_or(option.to_string(), String::from))
+                .collect();
+            return format!("one of: {}", options.join(", "));
+        }
+        let expected = match types(schema).into_iter().find(|t| *t != "null") {
+            Some("string") => "a string",
+            Some("number") | Some("integer") => "a number",
+            Some("boolean") => "true or false",
+            Some("array") if schema["items"].get("type") == Some(&Value::from("string")) => {
+                "a list of strings"
+            }
+            Some("array") => "a list",
+            Some("object") => "an object",
+            _ => "something else",
+        };
+        expected.to_string()
+    }
+}
+
+/// The alternatives of an `anyOf` or `oneOf`
+fn branches(schema: &Value) -> Option<&Vec<Value>> {
+    schema
+        .get("anyOf")
+        .or_else(|| schema.get("oneOf"))
+        .and_then(Value::as_array)
+}
+
+/// The types a schema allows, none meaning any
+fn types(schema: &Value) -> Vec<&str> {
+    match schema.get("type") {
+        Some(Value::String(allowed)) => vec![allowed.as_str()],
+        Some(Value::Array(allowed)) => allowed.iter().filter_map(Value::as_str).collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// The JSON Schema type of a value, with whole numbers as `integer`
+fn type_of(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(n) if n.is_i64() || n.is_u64() => "integer",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+fn describe(value: &Value) -> String {
+    match value {
+        Value::Null => "null".to_string(),
+        Value::Array(_) => "a list".to_string(),
+        Value::Object(_) => "an object".to_string(),
+        _ => value.to_string(),
+    }
+}
+
+/// A suggestion of the closest of `options` to a misspelt `word`, if any are close enough
+fn did_you_mean(word: &str, options: &[&str]) -> String {
+    let word = word.to_lowercase();
+    let closest = options
+        .iter()
+        .map(|option| (edit_distance(&word, &option.to_lowercase()), option))
+        .min_by_key(|(distance, _)| *distance);
+    match closest {
+        Some((distance, option)) if distance <= (word.chars().count() / 3).max(1) => {
+            format!(", did you mean '{}'?", option)
+        }
+        _ if options.is_empty() => ", it takes no args".to_string(),
+        _ => format!(", expected one of: {}", options.join(", ")),
+    }
+}
+
+/// The number of characters that have to be added, removed, changed or swapped with their
+/// neighbour to turn `a` into `b`
+fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let mut distances = vec![vec![0; b.len() + 1]; a.len() + 1];
+    for (i, row) in distances.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    for (j, distance) in distances[0].iter_mut().enumerate() {
+        *distance = j;
+    }
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            let mut distance = (distances[i - 1][j] + 1)
+                .min(distances[i][j - 1] + 1)
+                .min(distances[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                distance = distance.min(distances[i - 2][j - 2] + 1);
+            }
+            distances[i][j] = distance;
+        }
+    }
+    distances[a.len()][b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parsers::strategy_structs::{Transformer, TransformerType};
+    use crate::parsers::transformer_args::{self, ParsedTransformer};
+    use schemars::SchemaGenerator;
+    use serde_json::json;
+    use std::collections::HashMap;
+
+    fn errors(value: Value) -> String {
+        validate(&value).unwrap_err()
+    }
+
+    fn column(transformer: Value) -> Value {
+        json!([{
+            "table_name": "public.users",
+            "columns": [{
+                "data_category": "Pii",
+                "description": "",
+                "name": "email",
+                "transformer": transformer
+            }]
+        }])
+    }
+
+    #[test]
+    fn every_transformer_is_in_the_schema() {
+        let transformers = TransformerType::all();
+        assert_eq!(transformers.len(), 49);
+        let schema = schema();
+        assert_eq!(
+            schema["definitions"]["SingleTransformer"]["allOf"]
+                .as_array()
+                .unwrap()
+                .len(),
+            transformers.len()
+        );
+    }
+
+    #[test]
+    fn the_schema_args_are_the_typed_args() {
+        let mut generator = SchemaGenerator::default();
+        let field = Regex::new("`([a-z_0-9]+)`").unwrap();
+        for name in TransformerType::all() {
+            let parse = |arg: &str| {
+                ParsedTransformer::parse(&Transformer {
+                    name: name.clone(),
+                    args: Some(HashMap::from([(arg.to_string(), Value::Null)])),
+                })
+            };
+            let schema = transformer_args::args_schema(&name, &mut generator).to_value();
+            let schema_args: Vec<&String> =
+                schema["properties"].as_object().unwrap().keys().collect();
+
+            // Every arg in the schema is one the typed args know
+            for arg in &schema_args {
+                assert!(
+                    !parse(arg).is_err_and(|e| e.contains("unknown field")),
+                    "{:?}: {}",
+                    name,
+                    arg
+                );
+            }
+            // and every arg the typed args know is in the schema
+            let error = parse("not_an_arg").unwrap_err();
+            for known in field.captures_iter(&error).skip(1) {
+                assert!(
+                    schema_args.contains(&&known[1].to_string()),
+                    "{:?}: {}",
+                    name,
+                    &known[1]
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn the_example_strategy_file_is_valid() {
+        let file = std::fs::read_to_string("test_files/strategy.json").unwrap();
+        assert_eq!(validate(&serde_json::from_str(&file).unwrap()), Ok(()));
+    }
+
+    #[test]
+    fn accepts_pipelines_includes_and_nested_transformers() {
+        let mut strategy = column(json!({
+            "name": "Conditional",
+            "args": {
+                "cases": [{"when": {"column": "type", "equals": "email"}, "transformer": [{"name": "FakeEmail"}, {"name": "Lowercase"}]}],
+                "default": {"name": "Scramble", "args": {"ensure_unique": true}}
+            }
+        }));
+        strategy
+            .as_array_mut()
+            .unwrap()
+            .insert(0, json!({"include": "tables"}));
+        assert_eq!(validate(&strategy), Ok(()));
+    }
+
+    #[test]
+    fn suggests_transformer_names_and_arg_keys() {
+        assert_eq!(
+            errors(column(json!({"name": "FakeEmial"}))),
+            "\n\ttable 'public.users', column 'email': unknown transformer 'FakeEmial', did you mean 'FakeEmail'?"
+        );
+        assert_eq!(
+            errors(column(json!({"name": "FakeEmail", "args": {"keep_domian": true}}))),
+            "\n\ttable 'public.users', column 'email': FakeEmail has no arg 'keep_domian', did you mean 'keep_domain'?"
+        );
+        assert_eq!(
+            errors(column(json!({"name": "Identity", "args": {"value": "x"}}))),
+            "\n\ttable 'public.users', column 'email': Identity has no arg 'value', expected one of: ensure_unique, unique_group"
+        );
+    }
+
+    #[test]
+    fn reports_errors_in_nested_transformers_and_args() {
+        assert_eq!(
+            errors(column(json!([
+                {"name": "FakeEmail"},
+                {"name": "Truncate", "args": {"length": "ten"}},
+                {"name": "Uppercse"}
+            ]))),
+            "\n\ttable 'public.users', column 'email', Truncate arg 'length': expected a number, not \"ten\"\
+             \n\ttable 'public.users', column 'email': unknown transformer 'Uppercse', did you mean 'Uppercase'?"
+        );
+    }
+
+    #[test]
+    fn reports_unknown_and_missing_keys() {
+        assert_eq!(
+            errors(json!([{
+                "table_name": "public.users",
+                "trucate": true,
+                "columns": [{"name": "id", "data_category": "General", "transformer": {"name": "Identity"}}]
+            }])),
+            "\n\ttable 'public.users': unknown key 'trucate', did you mean 'truncate'?\
+             \n\ttable 'public.users', column 'id': missing 'description'"
+        );
+    }
+}
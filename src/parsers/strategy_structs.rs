@@ -1,8 +1,15 @@
+use crate::parsers::transformer_args::{self, ParsedTransformer};
+use schemars::{json_schema, JsonSchema, Schema, SchemaGenerator};
 use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::borrow::Cow;
 use std::cmp::Ordering;
 use std::collections::HashMap;
 
-#[derive(Clone, Debug, Eq, Serialize, Deserialize)]
+pub type TransformerArgs = HashMap<String, Value>;
+
+#[derive(Clone, Debug, Eq, Serialize, Deserialize, JsonSchema)]
+#[schemars(deny_unknown_fields)]
 pub struct ColumnInFile {
     pub data_category: DataCategory,
     pub description: String,
@@ -43,20 +50,28 @@ impl PartialEq for ColumnInFile {
     }
 }
 
-#[derive(Clone, Debug, Eq, Serialize, Deserialize)]
+#[derive(Clone, Debug, Eq, Serialize, Deserialize, JsonSchema)]
+#[schemars(deny_unknown_fields)]
 pub struct StrategyInFile {
+    /// The schema and name of the table e.g. public.users
     #[serde(default)]
     pub table_name: String,
 
     #[serde(default)]
     pub description: String,
 
+    /// Leave the table empty
     #[serde(default)]
     pub truncate: bool,
 
+    /// The global salt, only used in the first entry
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub salt: Option<String>,
 
+    /// The global locale, only used in the first entry
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub locale: Option<String>,
+
     #[serde(default)]
     pub columns: Vec<ColumnInFile>,
 }
@@ -100,7 +115,7 @@ impl PartialOrd for SimpleColumn {
 pub struct ColumnInfo {
     pub data_category: DataCategory,
     pub name: String,
-    pub transformer: Transformer,
+    pub transformer: ParsedTransformer,
 }
 
 #[derive(Clone, Debug, Eq, PartialEq, Hash, Serialize, Deserialize)]
@@ -129,6 +144,33 @@ impl From<String> for DataCategory {
     }
 }
 
+/// Any name is accepted, as names other than the built in ones are custom classifications
+impl JsonSchema for DataCategory {
+    fn schema_name() -> Cow<'static, str> {
+        "DataCategory".into()
+    }
+
+    fn json_schema(_: &mut SchemaGenerator) -> Schema {
+        let built_in: Vec<String> = [
+            DataCategory::CommerciallySensitive,
+            DataCategory::General,
+            DataCategory::PotentialPii,
+            DataCategory::Pii,
+            DataCategory::Security,
+            DataCategory::Unknown,
+        ]
+        .into_iter()
+        .map(String::from)
+        .collect();
+        json_schema!({
+            "anyOf": [
+                { "enum": built_in },
+                { "type": "string", "description": "A custom classification" }
+            ]
+        })
+    }
+}
+
 impl From<DataCategory> for String {
     fn from(val: DataCategory) -> Self {
         match val {
@@ -143,12 +185,16 @@ impl From<DataCategory> for String {
     }
 }
 
-#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize, JsonSchema)]
 pub enum TransformerType {
+    Conditional,
+    Dictionary,
     EmptyJson,
     Error,
+    FakeAccountNumber,
     FakeBase16String,
     FakeBase32String,
+    FakeCardNumber,
     FakeCity,
     FakeCompanyName,
     FakeEmail,
@@ -157,28 +203,166 @@ pub enum TransformerType {
     FakeFullAddress,
     FakeFullName,
     FakeIPv4,
+    FakeIPv6,
+    FakeIban,
     FakeLastName,
+    FakeMacAddress,
     FakeNationalIdentityNumber,
     FakePhoneNumber,
     FakePostCode,
+    FakeSortCode,
     FakeState,
     FakeStreetAddress,
+    FakeUrl,
+    FakeUserAgent,
     FakeUsername,
     FakeUUID,
     Fixed,
     Identity,
+    Lowercase,
+    Null,
     ObfuscateDay,
     ObfuscateDateTime,
+    Pad,
+    Pipeline,
+    Prefix,
+    ProbabilisticNull,
+    Redact,
+    RegexReplace,
+    Replace,
     Scramble,
     ScrambleBlank,
+    Suffix,
+    Template,
+    Truncate,
+    Uppercase,
 }
 
-#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+/// In the strategy file a column's transformer is either a single transformer, or a list of steps
+/// which is read in as a `Pipeline` with the steps in its args
+#[derive(Clone, Debug, Eq, PartialEq, Deserialize)]
+#[serde(try_from = "Value")]
 pub struct Transformer {
     pub name: TransformerType,
+    pub args: Option<TransformerArgs>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct SingleTransformer {
+    name: TransformerType,
 
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub args: Option<HashMap<String, String>>,
+    args: Option<TransformerArgs>,
+}
+
+impl JsonSchema for Transformer {
+    fn schema_name() -> Cow<'static, str> {
+        "Transformer".into()
+    }
+
+    fn json_schema(generator: &mut SchemaGenerator) -> Schema {
+        let single = generator.subschema_for::<SingleTransformer>();
+        json_schema!({
+            "anyOf": [
+                single,
+                {
+                    "type": "array",
+                    "description": "Transformers applied in order, each to the output of the one before",
+                    "items": single,
+                    "minItems": 1
+                }
+            ]
+        })
+    }
+}
+
+/// The args allowed depend on the transformer, so each name has its own schema for them
+impl JsonSchema for SingleTransformer {
+    fn schema_name() -> Cow<'static, str> {
+        "SingleTransformer".into()
+    }
+
+    fn json_schema(generator: &mut SchemaGenerator) -> Schema {
+        let conditions: Vec<Value> = TransformerType::all()
+            .iter()
+            .map(|name| {
+                json!({
+                    "if": { "properties": { "name": { "const": name } } },
+                    "then": {
+                        "properties": { "args": transformer_args::args_schema(name, generator) }
+                    }
+                })
+            })
+            .collect();
+        json_schema!({
+            "type": "object",
+            "properties": {
+                "name": generator.subschema_for::<TransformerType>(),
+                "args": { "type": ["object", "null"] }
+            },
+            "required": ["name"],
+            "additionalProperties": false,
+            "allOf": conditions
+        })
+    }
+}
+
+impl TransformerType {
+    /// Every transformer, in the order they are listed in the schema
+    pub fn all() -> Vec<TransformerType> {
+        let schema = TransformerType::json_schema(&mut SchemaGenerator::default());
+        schema
+            .get("enum")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+            .filter_map(|name| serde_json::from_value(name.clone()).ok())
+            .collect()
+    }
+}
+
+impl Transformer {
+    pub fn pipeline(steps: Vec<Transformer>) -> Self {
+        let steps = steps
+            .into_iter()
+            .map(|step| serde_json::to_value(step).unwrap())
+            .collect();
+        Transformer {
+            name: TransformerType::Pipeline,
+            args: Some(HashMap::from([("steps".to_string(), Value::Array(steps))])),
+        }
+    }
+}
+
+impl TryFrom<Value> for Transformer {
+    type Error = serde_json::Error;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        match value {
+            Value::Array(_) => Ok(Transformer::pipeline(serde_json::from_value(value)?)),
+            _ => {
+                let single: SingleTransformer = serde_json::from_value(value)?;
+                Ok(Transformer {
+                    name: single.name,
+                    args: single.args,
+                })
+            }
+        }
+    }
+}
+
+impl Serialize for Transformer {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let steps = self.args.as_ref().and_then(|args| args.get("steps"));
+        match (&self.name, steps) {
+            (TransformerType::Pipeline, Some(steps)) => steps.serialize(serializer),
+            _ => SingleTransformer {
+                name: self.name.clone(),
+                args: self.args.clone(),
+            }
+            .serialize(serializer),
+        }
+    }
 }
 
 pub struct TransformerOverrides {
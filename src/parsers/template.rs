@@ -0,0 +1,346 @@
+use crate::parsers::data_row;
+use crate::parsers::locales::{self, Locale};
+use crate::parsers::strategy_structs::ColumnInfo;
+use crate::parsers::transformer;
+use crate::parsers::transformer_args::{Args, ParsedTransformer, TemplateArgs};
+use fake::faker::internet::en::{DomainSuffix, Username};
+use fake::faker::lorem::en::Word;
+use fake::Fake;
+use rand::rngs::SmallRng;
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, HashSet};
+
+const DEFAULT_HASH_LENGTH: usize = 8;
+const FAKES: [&str; 8] = [
+    "city",
+    "company_name",
+    "domain_suffix",
+    "first_name",
+    "full_name",
+    "last_name",
+    "username",
+    "word",
+];
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Part {
+    Literal(String),
+    /// The transformed value of another column in the same row
+    Column(String),
+    /// A value from faker e.g. `{fake:first_name}`
+    Fake(String),
+    /// The start of a sha256 of the transformed value of another column e.g. `{hash:id:6}`
+    Hash {
+        column: String,
+        length: usize,
+    },
+}
+
+pub fn parse(template: &str) -> Result<Vec<Part>, String> {
+    let mut parts = Vec::new();
+    let mut literal = String::new();
+    let mut chars = template.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '{' if chars.peek() == Some(&'{') => {
+                chars.next();
+                literal.push('{');
+            }
+            '}' if chars.peek() == Some(&'}') => {
+                chars.next();
+                literal.push('}');
+            }
+            '{' => {
+                let mut placeholder = String::new();
+                loop {
+                    match chars.next() {
+                        Some('}') => break,
+                        Some(c) => placeholder.push(c),
+                        None => return Err(format!("unclosed '{{' in template '{}'", template)),
+                    }
+                }
+                if !literal.is_empty() {
+                    parts.push(Part::Literal(std::mem::take(&mut literal)));
+                }
+                parts.push(parse_placeholder(&placeholder)?);
+            }
+            '}' => return Err(format!("unmatched '}}' in template '{}'", template)),
+            _ => literal.push(c),
+        }
+    }
+    if !literal.is_empty() {
+        parts.push(Part::Literal(literal));
+    }
+    Ok(parts)
+}
+
+fn parse_placeholder(placeholder: &str) -> Result<Part, String> {
+    let segments: Vec<&str> = placeholder.split(':').map(str::trim).collect();
+    match segments.as_slice() {
+        [""] => Err("empty placeholder '{}' in template".to_string()),
+        [column] => Ok(Part::Column(column.to_string())),
+        ["fake", name] if FAKES.contains(name) => Ok(Part::Fake(name.to_string())),
+        ["fake", name] => Err(format!(
+            "unknown fake '{}', expected one of: {}",
+            name,
+            FAKES.join(", ")
+        )),
+        ["hash", column] => Ok(Part::Hash {
+            column: column.to_string(),
+            length: DEFAULT_HASH_LENGTH,
+        }),
+        ["hash", column, length] => match length.parse::<usize>() {
+            Ok(length) if (1..=64).contains(&length) => Ok(Part::Hash {
+                column: column.to_string(),
+                length,
+            }),
+            _ => Err(format!(
+                "invalid hash length '{}', it must be between 1 and 64",
+                length
+            )),
+        },
+        _ => Err(format!("invalid placeholder '{{{}}}'", placeholder)),
+    }
+}
+
+/// Whether the transformer is a template, or a pipeline with a template as one of its steps
+pub fn is_template(transformer: &ParsedTransformer) -> bool {
+    match &transformer.args {
+        Args::Template(..) => true,
+        Args::Pipeline(steps) => steps.iter().any(is_template),
+        _ => false,
+    }
+}
+
+/// The other columns whose transformed values this template needs
+pub fn dependencies(transformer: &ParsedTransformer) -> Vec<String> {
+    match &transformer.args {
+        Args::Pipeline(steps) => steps.iter().flat_map(dependencies).collect(),
+        Args::Template(_, parts) => parts
+            .iter()
+            .filter_map(|part| match part {
+                Part::Column(column) | Part::Hash { column, .. } => Some(column.clone()),
+                _ => None,
+            })
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Orders column indexes so that every template column comes after the columns it refers to.
+/// Non template columns keep their original order and come first. Returns the names of any
+/// template columns that end up depending on themselves
+pub fn transform_order(columns: &[ColumnInfo]) -> Result<Vec<usize>, Vec<String>> {
+    let positions: HashMap<&str, usize> = columns
+        .iter()
+        .enumerate()
+        .map(|(i, column)| (column.name.as_str(), i))
+        .collect();
+
+    let (mut ordered, mut remaining): (Vec<usize>, Vec<usize>) =
+        (0..columns.len()).partition(|i| !is_template(&columns[*i].transformer));
+    let mut done: HashSet<usize> = ordered.iter().cloned().collect();
+
+    while !remaining.is_empty() {
+        let (ready, waiting): (Vec<usize>, Vec<usize>) = remaining.iter().partition(|i| {
+            dependencies(&columns[**i].transformer)
+                .iter()
+                .all(|dependency| {
+                    positions
+                        .get(dependency.as_str())
+                        .map_or(true, |position| done.contains(position))
+                })
+        });
+        if ready.is_empty() {
+            return Err(waiting.iter().map(|i| columns[*i].name.clone()).collect());
+        }
+        done.extend(ready.iter());
+        ordered.extend(ready);
+        remaining = waiting;
+    }
+    Ok(ordered)
+}
+
+/// Checks that every column the template refers to is in the table
+pub fn validate(transformer: &ParsedTransformer, column_names: &[&str]) -> Result<(), String> {
+    for column in dependencies(transformer) {
+        if !column_names.contains(&column.as_str()) {
+            return Err(format!("template refers to unknown column '{}'", column));
+        }
+    }
+    Ok(())
+}
+
+pub fn render(
+    rng: &mut SmallRng,
+    value: &str,
+    args: &TemplateArgs,
+    parts: &[Part],
+    column_values: &[(String, String)],
+    global_salt: Option<&str>,
+) -> String {
+    let locale = args.locale.unwrap_or(Locale::En);
+    let mut seeded_rng = args
+        .deterministic
+        .then(|| transformer::get_faker_rng(value, None, global_salt));
+    let rng = seeded_rng.as_mut().unwrap_or(rng);
+
+    let column_value = |column: &str| -> &str {
+        column_values
+            .iter()
+            .find(|(name, _)| name == column)
+            .map(|(_, value)| value.as_str())
+            .filter(|value| *value != "\\N")
+            .unwrap_or("")
+    };
+
+    parts
+        .iter()
+        .map(|part| match part {
+            Part::Literal(literal) => data_row::encode(literal).into_owned(),
+            Part::Column(column) => column_value(column).to_string(),
+            Part::Fake(name) => data_row::encode(&fake(rng, name, locale)).into_owned(),
+            Part::Hash { column, length } => {
+                let mut hasher = Sha256::new();
+                hasher.update(column_value(column).as_bytes());
+                if let Some(salt) = global_salt {
+                    hasher.update(salt.as_bytes());
+                }
+                base16::encode_lower(&hasher.finalize())[..*length].to_string()
+            }
+        })
+        .collect()
+}
+
+/// A fake value in the locale of the template. Domain suffixes, usernames and words are the same in
+/// every locale
+fn fake(rng: &mut SmallRng, name: &str, locale: Locale) -> String {
+    match name {
+        "city" => locales::city(locale, rng),
+        "company_name" => locales::company_name(locale, rng),
+        "domain_suffix" => DomainSuffix().fake_with_rng(rng),
+        "first_name" => locales::first_name(locale, rng),
+        "full_name" => {
+            let first_name = locales::first_name(locale, rng);
+            format!("{} {}", first_name, locales::last_name(locale, rng))
+        }
+        "last_name" => locales::last_name(locale, rng),
+        "username" => Username().fake_with_rng(rng),
+        "word" => Word().fake_with_rng(rng),
+        _ => panic!("unknown fake '{}' in template", name),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parsers::rng;
+    use crate::parsers::strategy_structs::{TransformerArgs, TransformerType};
+    use serde_json::json;
+
+    fn template(template: &str) -> Option<TransformerArgs> {
+        Some(HashMap::from([("template".to_string(), json!(template))]))
+    }
+
+    #[test]
+    fn parses_placeholders_and_escaped_braces() {
+        assert_eq!(
+            parse("{{{first_name}}}.{fake:word}-{hash:id:4}").unwrap(),
+            vec![
+                Part::Literal("{".to_string()),
+                Part::Column("first_name".to_string()),
+                Part::Literal("}.".to_string()),
+                Part::Fake("word".to_string()),
+                Part::Literal("-".to_string()),
+                Part::Hash {
+                    column: "id".to_string(),
+                    length: 4
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn rejects_unknown_fakes() {
+        assert!(parse("{fake:shoe_size}")
+            .unwrap_err()
+            .contains("unknown fake 'shoe_size'"));
+    }
+
+    #[test]
+    fn renders_other_column_values_and_hashes() {
+        let mut rng = rng::get();
+        let column_values = vec![
+            ("id".to_string(), "42".to_string()),
+            ("first_name".to_string(), "Jane".to_string()),
+            ("last_name".to_string(), "Smith".to_string()),
+        ];
+        let template = "{first_name}.{last_name}.{hash:id:6}@example.test";
+        let email = render(
+            &mut rng,
+            "real@example.com",
+            &serde_json::from_value(json!({ "template": template })).unwrap(),
+            &parse(template).unwrap(),
+            &column_values,
+            None,
+        );
+        assert_eq!(email, "Jane.Smith.73475c@example.test");
+    }
+
+    #[test]
+    fn renders_fakes_in_the_locale_of_the_template() {
+        let name = render(
+            &mut rng::get(),
+            "Peter",
+            &serde_json::from_value(json!({
+                "template": "{fake:first_name}",
+                "deterministic": true,
+                "locale": "de_DE"
+            }))
+            .unwrap(),
+            &parse("{fake:first_name}").unwrap(),
+            &[],
+            None,
+        );
+        let expected = locales::first_name(
+            Locale::DeDe,
+            &mut transformer::get_faker_rng("Peter", None, None),
+        );
+        assert_eq!(name, expected);
+    }
+
+    #[test]
+    fn orders_templates_after_the_columns_they_use() {
+        let columns = vec![
+            ColumnInfo::builder()
+                .with_name("email")
+                .with_transformer(
+                    TransformerType::Template,
+                    template("{username}@example.test"),
+                )
+                .build(),
+            ColumnInfo::builder()
+                .with_name("username")
+                .with_transformer(TransformerType::Template, template("{name}"))
+                .build(),
+            ColumnInfo::builder().with_name("name").build(),
+        ];
+        assert_eq!(transform_order(&columns).unwrap(), vec![2, 1, 0]);
+    }
+
+    #[test]
+    fn circular_templates_are_an_error() {
+        let columns = vec![
+            ColumnInfo::builder()
+                .with_name("a")
+                .with_transformer(TransformerType::Template, template("{b}"))
+                .build(),
+            ColumnInfo::builder()
+                .with_name("b")
+                .with_transformer(TransformerType::Template, template("{a}"))
+                .build(),
+        ];
+        assert_eq!(transform_order(&columns).unwrap_err(), vec!["a", "b"]);
+    }
+}
@@ -0,0 +1,118 @@
+use crate::parsers::strategy_structs::StrategyInFile;
+use serde::Serialize;
+use serde_json::Value;
+use toml_edit::{ArrayOfTables, DocumentMut, Item, Key, Table};
+
+/// TOML files must start with a table, so the list of tables goes under this key
+pub const TABLES_KEY: &str = "tables";
+
+pub fn parse(text: &str) -> Result<Value, String> {
+    toml_edit::de::from_str(text).map_err(|e| e.to_string())
+}
+
+/// Writes `entries` under `[[tables]]`, with the columns of each table under `[[tables.columns]]`.
+/// If the file being replaced is given, its comments and key order are kept, with the comments of
+/// each table and column moving with it (found by its `table_name` or `name`) when it's re-sorted
+pub fn write<T: Serialize>(entries: &[T], replacing: Option<&str>) -> Result<String, String> {
+    #[derive(Serialize)]
+    struct Tables<'a, T> {
+        tables: &'a [T],
+    }
+
+    let mut document =
+        toml_edit::ser::to_document(&Tables { tables: entries }).map_err(|e| e.to_string())?;
+    to_arrays_of_tables(document.as_table_mut());
+    if let Some(old) = replacing.and_then(|text| text.parse::<DocumentMut>().ok()) {
+        keep_comments(document.as_table_mut(), old.as_table());
+        document.set_trailing(old.trailing().as_str().unwrap_or_default());
+    }
+    Ok(document.to_string())
+}
+
+/// TOML has no null, so null args (and nulls inside them) are left out
+pub fn without_null_args(strategies: &[StrategyInFile]) -> Vec<StrategyInFile> {
+    let mut strategies = strategies.to_vec();
+    for column in strategies
+        .iter_mut()
+        .flat_map(|strategy| &mut strategy.columns)
+    {
+        if let Some(args) = &mut column.transformer.args {
+            args.retain(|_, value| !value.is_null());
+            args.values_mut().for_each(remove_nulls);
+        }
+    }
+    strategies
+}
+
+fn remove_nulls(value: &mut Value) {
+    match value {
+        Value::Array(items) => {
+            items.retain(|item| !item.is_null());
+            items.iter_mut().for_each(remove_nulls);
+        }
+        Value::Object(object) => {
+            object.retain(|_, value| !value.is_null());
+            object.values_mut().for_each(remove_nulls);
+        }
+        _ => (),
+    }
+}
+
+/// Lists of tables are written as `[[key]]` sections, rather than inline, so they can have comments
+fn to_arrays_of_tables(table: &mut Table) {
+    for (_, item) in table.iter_mut() {
+        let Some(array) = item
+            .as_array()
+            .filter(|array| !array.is_empty() && array.iter().all(|value| value.is_inline_table()))
+        else {
+            continue;
+        };
+        let mut tables = ArrayOfTables::new();
+        for value in array.iter() {
+            let mut table = value.as_inline_table().unwrap().clone().into_table();
+            to_arrays_of_tables(&mut table);
+            tables.push(table);
+        }
+        *item = Item::ArrayOfTables(tables);
+    }
+}
+
+fn keep_comments(table: &mut Table, old: &Table) {
+    *table.decor_mut() = old.decor().clone();
+    // Keys that are new to the file go after the ones it already had
+    let rank = |key: &Key| {
+        old.iter()
+            .position(|(old_key, _)| old_key == key.get())
+            .unwrap_or(usize::MAX)
+    };
+    table.sort_values_by(|a, _, b, _| rank(a).cmp(&rank(b)));
+    for (mut key, item) in table.iter_mut() {
+        let Some((old_key, old_item)) = old.get_key_value(key.get()) else {
+            continue;
+        };
+        *key.leaf_decor_mut() = old_key.leaf_decor().clone();
+        match (item, old_item) {
+            (Item::Value(value), Item::Value(old_value)) => {
+                *value.decor_mut() = old_value.decor().clone()
+            }
+            (Item::ArrayOfTables(tables), Item::ArrayOfTables(old_tables)) => {
+                for table in tables.iter_mut() {
+                    if let Some(old_table) = old_tables
+                        .iter()
+                        .find(|old_table| identity(old_table) == identity(table))
+                    {
+                        keep_comments(table, old_table);
+                    }
+                }
+            }
+            _ => (),
+        }
+    }
+}
+
+/// How a table, column or include is found in the file being replaced
+fn identity(table: &Table) -> Option<&str> {
+    ["table_name", "name", "include"]
+        .iter()
+        .find_map(|key| table.get(key).and_then(Item::as_str))
+}
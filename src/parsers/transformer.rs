@@ -1,23 +1,33 @@
-use crate::parsers::national_insurance_number;
-use crate::parsers::strategy_structs::{Transformer, TransformerType};
+use crate::parsers::conditional;
+use crate::parsers::data_row;
+use crate::parsers::dictionary::Dictionary;
+use crate::parsers::email::{self, EmailArgs};
+use crate::parsers::financial;
+use crate::parsers::locales::{self, Locale};
+use crate::parsers::national_identity_number;
+use crate::parsers::network;
+use crate::parsers::nulls;
+use crate::parsers::phone_number::{self, NotFaked};
+use crate::parsers::pipeline;
+use crate::parsers::postcode;
+use crate::parsers::redact;
+use crate::parsers::regex_replace;
+use crate::parsers::strategy_structs::{TransformerArgs, TransformerType};
+use crate::parsers::template;
+use crate::parsers::transformer_args::{Args, DictionaryArgs, ParsedTransformer};
 use crate::parsers::types::Type::Array;
 use crate::parsers::types::Type::SingleValue;
 use crate::parsers::types::*;
 use base16;
 use base32::Alphabet;
 use chrono::{Datelike, NaiveDate, Timelike};
-use core::ops::Range;
-use fake::faker::address::en::*;
-use fake::faker::company::en::*;
 use fake::faker::internet::en::*;
-use fake::faker::name::en::*;
 use fake::Fake;
 use log::trace;
 use rand::SeedableRng;
 use rand::{rngs::SmallRng, Rng};
 use sha2::{Digest, Sha256};
 use std::borrow::Cow;
-use std::collections::HashMap;
 use std::fmt::Write;
 use std::sync::atomic::{AtomicUsize, Ordering};
 use uuid::Uuid;
@@ -53,7 +63,7 @@ fn get_unique() -> usize {
 /// // With both ID and salt for run-level consistency
 /// let rng3 = get_faker_rng("test", Some("user_123"), Some("global_salt_2024"));
 /// ```
-fn get_faker_rng(value: &str, id: Option<&str>, salt: Option<&str>) -> SmallRng {
+pub fn get_faker_rng(value: &str, id: Option<&str>, salt: Option<&str>) -> SmallRng {
     let mut hasher = Sha256::new();
     let combined = match (id, salt) {
         (Some(id), Some(salt)) => format!("{}{}{}", value, id, salt),
@@ -70,7 +80,7 @@ pub fn transform<'line>(
     rng: &mut SmallRng,
     value: &'line str,
     column_type: &Type,
-    transformer: &'line Transformer,
+    transformer: &'line ParsedTransformer,
     table_name: &str,
     column_values: &[(String, String)],
     global_salt: Option<&str>,
@@ -83,6 +93,11 @@ pub fn transform<'line>(
         return Cow::from(value);
     }
 
+    // Nulls replace the whole value, rather than each item of an array
+    if [TransformerType::Null, TransformerType::ProbabilisticNull].contains(&transformer.name) {
+        return null_or_value(value, transformer, column_values, global_salt);
+    }
+
     if let Array {
         sub_type: underlying_type,
     } = column_type
@@ -101,66 +116,248 @@ pub fn transform<'line>(
     let unique = get_unique();
 
     // Get the id value if specified in transformer args
-    let id = transformer.args.as_ref().and_then(|args| {
-        args.get("id_column").and_then(|id_column| {
-            column_values
-                .iter()
-                .find(|(col, _)| col == id_column)
-                .map(|(_, val)| val.as_str())
-        })
+    let id = transformer.args.id_column().and_then(|id_column| {
+        column_values
+            .iter()
+            .find(|(col, _)| col == id_column)
+            .map(|(_, val)| val.as_str())
     });
 
+    let args = &transformer.args;
+    let locale = args.locale();
+
     //TODO error if inappropriate transformer for type is used e.g. scramble for json should give
     //nice error rather than making invalid sql
 
-    match transformer.name {
-        TransformerType::Error => {
+    match (&transformer.name, args) {
+        (TransformerType::Error, _) => {
             panic!("Error transform still in place for table: {}", table_name)
         }
-        TransformerType::EmptyJson => Cow::from("{}"),
-        TransformerType::FakeBase16String => Cow::from(fake_base16_string()),
-        TransformerType::FakeBase32String => Cow::from(fake_base32_string()),
-        TransformerType::FakeCity => Cow::from(CityName().fake::<String>()),
-        TransformerType::FakeCompanyName => Cow::from(fake_company_name(
+        (TransformerType::Conditional, Args::Conditional(args)) => conditional::transform(
+            rng,
+            value,
+            column_type,
+            args,
+            table_name,
+            column_values,
+            global_salt,
+        ),
+        (TransformerType::Dictionary, Args::Dictionary(args, dictionary)) => {
+            Cow::from(from_dictionary(value, args, dictionary, id, global_salt))
+        }
+        (TransformerType::EmptyJson, _) => Cow::from("{}"),
+        (TransformerType::FakeAccountNumber, _) => Cow::from(financial::digits(
+            value,
+            "00000000",
+            &mut value_rng(value, args.deterministic(), id, global_salt),
+        )),
+        (TransformerType::FakeBase16String, _) => Cow::from(fake_base16_string()),
+        (TransformerType::FakeBase32String, _) => Cow::from(fake_base32_string()),
+        (TransformerType::FakeCardNumber, Args::CardNumber(card_args)) => {
+            Cow::from(financial::card_number(
+                value,
+                card_args.keep_bin,
+                card_args.keep_last_4,
+                &mut value_rng(value, args.deterministic(), id, global_salt),
+            ))
+        }
+        (TransformerType::FakeCity, _) => Cow::from(locales::city(locale, &mut rand::thread_rng())),
+        (TransformerType::FakeCompanyName, _) => {
+            Cow::from(fake_company_name(value, args, locale, unique, global_salt))
+        }
+        (TransformerType::FakeEmail, Args::FakeEmail(email_args)) => {
+            Cow::from(email::fake(value, email_args, global_salt))
+        }
+        (TransformerType::FakeEmailOrPhone, Args::FakeEmailOrPhone(email_args, _)) => Cow::from(
+            fake_email_or_phone(value, email_args, args, locale, id, table_name, global_salt),
+        ),
+        (TransformerType::FakeFirstName, _) => {
+            Cow::from(fake_first_name(value, args, locale, id, global_salt))
+        }
+        (TransformerType::FakeFullAddress, _) => Cow::from(fake_full_address(locale)),
+        (TransformerType::FakeFullName, _) => {
+            Cow::from(fake_full_name(value, args, locale, id, global_salt))
+        }
+        (TransformerType::FakeIPv4 | TransformerType::FakeIPv6, Args::Ip(ip_args)) => {
+            Cow::from(network::ip(
+                value,
+                ip_args.keep_prefix_v4,
+                ip_args.keep_prefix_v6,
+                transformer.name == TransformerType::FakeIPv6,
+                &mut value_rng(value, args.deterministic(), id, global_salt),
+            ))
+        }
+        (TransformerType::FakeIban, _) => Cow::from(financial::iban(
+            value,
+            &mut value_rng(value, args.deterministic(), id, global_salt),
+        )),
+        (TransformerType::FakeLastName, _) => {
+            Cow::from(fake_last_name(value, args, locale, id, global_salt))
+        }
+        (TransformerType::FakeMacAddress, _) => Cow::from(network::mac_address(
+            value,
+            &mut value_rng(value, args.deterministic(), id, global_salt),
+        )),
+        (
+            TransformerType::FakeNationalIdentityNumber,
+            Args::NationalIdentityNumber(number_args),
+        ) => Cow::from(national_identity_number::fake(
+            value,
+            number_args.format,
+            number_args.unique.then_some(unique),
+            &mut value_rng(value, args.deterministic(), id, global_salt),
+        )),
+        (TransformerType::FakePostCode, Args::PostCode(postcode_args)) => {
+            Cow::from(postcode::fake(
+                value,
+                postcode_args.mode,
+                &mut value_rng(value, args.deterministic(), id, global_salt),
+            ))
+        }
+        (TransformerType::FakeSortCode, _) => Cow::from(financial::digits(
+            value,
+            "00-00-00",
+            &mut value_rng(value, args.deterministic(), id, global_salt),
+        )),
+        (TransformerType::FakePhoneNumber, _) => Cow::from(fake_phone_number(
+            value,
+            args,
+            locale,
+            id,
+            table_name,
+            global_salt,
+        )),
+        (TransformerType::FakeStreetAddress, _) => Cow::from(fake_street_address(locale)),
+        (TransformerType::FakeState, _) => {
+            Cow::from(locales::state(locale, &mut rand::thread_rng()))
+        }
+        (TransformerType::FakeUrl, _) => Cow::from(network::url(
+            value,
+            &mut value_rng(value, args.deterministic(), id, global_salt),
+        )),
+        (TransformerType::FakeUserAgent, _) => Cow::from(network::user_agent(&mut value_rng(
+            value,
+            args.deterministic(),
+            id,
+            global_salt,
+        ))),
+        (TransformerType::FakeUsername, _) => Cow::from(fake_username(args, unique)),
+        (TransformerType::Scramble, _) => Cow::from(scramble(rng, value)),
+        (TransformerType::ScrambleBlank, _) => Cow::from(scramble_blank(value)),
+        (TransformerType::Null | TransformerType::ProbabilisticNull, _) => {
+            null_or_value(value, transformer, column_values, global_salt)
+        }
+        (TransformerType::ObfuscateDay, _) => Cow::from(obfuscate_day(value, table_name)),
+        (TransformerType::Fixed, Args::Fixed(fixed_args)) => Cow::from(fixed_args.value.as_str()),
+        (TransformerType::Identity, _) => Cow::from(value),
+        (TransformerType::FakeUUID, _) => Cow::from(fake_uuid(value, args, global_salt)),
+        (TransformerType::ObfuscateDateTime, _) => Cow::from(obfuscate_datetime(value, table_name)),
+        (TransformerType::Redact, Args::Redact(redact_args)) => Cow::from(redact::redact(
+            rng,
             value,
-            &transformer.args,
-            unique,
+            redact_args,
+            column_values,
             global_salt,
         )),
-        TransformerType::FakeEmail => Cow::from(fake_email(value, global_salt)),
-        TransformerType::FakeEmailOrPhone => Cow::from(fake_email_or_phone(value, global_salt)),
-        TransformerType::FakeFirstName => {
-            Cow::from(fake_first_name(value, &transformer.args, id, global_salt))
+        (TransformerType::RegexReplace, Args::RegexReplace(replace_args)) => {
+            Cow::from(regex_replace::replace(
+                rng,
+                value,
+                replace_args,
+                table_name,
+                column_values,
+                global_salt,
+            ))
+        }
+        (TransformerType::Pipeline, Args::Pipeline(steps)) => pipeline::transform(
+            rng,
+            value,
+            column_type,
+            steps,
+            table_name,
+            column_values,
+            global_salt,
+        ),
+        (
+            TransformerType::Lowercase
+            | TransformerType::Pad
+            | TransformerType::Prefix
+            | TransformerType::Replace
+            | TransformerType::Suffix
+            | TransformerType::Truncate
+            | TransformerType::Uppercase,
+            _,
+        ) => Cow::from(pipeline::post_process(value, transformer, table_name)),
+        (TransformerType::Template, Args::Template(template_args, parts)) => Cow::from(
+            template::render(rng, value, template_args, parts, column_values, global_salt),
+        ),
+        (name, args) => panic!(
+            "{:?} transformer in table: '{}' has the args of another transformer: {:?}",
+            name, table_name, args
+        ),
+    }
+}
+
+fn null_or_value<'value>(
+    value: &'value str,
+    transformer: &ParsedTransformer,
+    column_values: &[(String, String)],
+    global_salt: Option<&str>,
+) -> Cow<'value, str> {
+    let null = match &transformer.args {
+        Args::ProbabilisticNull(args) => {
+            nulls::should_null(value, args, column_values, global_salt)
         }
-        TransformerType::FakeFullAddress => Cow::from(fake_full_address()),
-        TransformerType::FakeFullName => {
-            Cow::from(fake_full_name(value, &transformer.args, id, global_salt))
+        _ => transformer.name == TransformerType::Null,
+    };
+    if null {
+        Cow::from(nulls::NULL)
+    } else {
+        Cow::from(value)
+    }
+}
+
+/// Checks the args which refer to other columns in the table, and those of nested transformers, so
+/// that mistakes are reported when the strategy file is loaded rather than part way through a run.
+/// Everything else about the args is checked when they're parsed
+pub fn validate_args(transformer: &ParsedTransformer, column_names: &[&str]) -> Result<(), String> {
+    match &transformer.args {
+        Args::Conditional(args) => conditional::validate(args, column_names),
+        Args::FakeEmail(args) => email::validate(args),
+        Args::FakeEmailOrPhone(args, _) => {
+            email::validate(args).and_then(|_| phone_number::validate(transformer.args.locale()))
         }
-        TransformerType::FakeIPv4 => Cow::from(IPv4().fake::<String>()),
-        TransformerType::FakeLastName => {
-            Cow::from(fake_last_name(value, &transformer.args, id, global_salt))
+        Args::Person(_) if transformer.name == TransformerType::FakePhoneNumber => {
+            phone_number::validate(transformer.args.locale())
         }
-        TransformerType::FakeNationalIdentityNumber => Cow::from(fake_national_identity_number()),
-        TransformerType::FakePostCode => Cow::from(fake_postcode(value)),
-        TransformerType::FakePhoneNumber => Cow::from(fake_phone_number(value)),
-        TransformerType::FakeStreetAddress => Cow::from(fake_street_address()),
-        TransformerType::FakeState => Cow::from(StateName().fake::<String>()),
-        TransformerType::FakeUsername => Cow::from(fake_username(&transformer.args, unique)),
-        TransformerType::Scramble => Cow::from(scramble(rng, value)),
-        TransformerType::ScrambleBlank => Cow::from(scramble_blank(value)),
-        TransformerType::ObfuscateDay => Cow::from(obfuscate_day(value, table_name)),
-        TransformerType::Fixed => fixed(&transformer.args, table_name),
-        TransformerType::Identity => Cow::from(value),
-        TransformerType::FakeUUID => Cow::from(fake_uuid(value, &transformer.args, global_salt)),
-        TransformerType::ObfuscateDateTime => Cow::from(obfuscate_datetime(value, table_name)),
+        Args::Pipeline(steps) => steps
+            .iter()
+            .try_for_each(|step| validate_args(step, column_names)),
+        Args::ProbabilisticNull(args) => nulls::validate(args, column_names),
+        Args::Redact(args) => redact::validate(args, column_names),
+        Args::RegexReplace(args) => regex_replace::validate(args, column_names),
+        Args::Template(..) => template::validate(transformer, column_names),
+        _ => Ok(()),
     }
 }
 
+/// Transformers nested inside others only see the original values of the row, so they can't be
+/// templates (which would otherwise copy original values into the output)
+pub fn validate_nested(
+    transformer: &ParsedTransformer,
+    column_names: &[&str],
+) -> Result<(), String> {
+    if template::is_template(transformer) {
+        return Err("Template can only be used as a column's own transformer".to_string());
+    }
+    validate_args(transformer, column_names)
+}
+
 fn transform_array<'value>(
     rng: &mut SmallRng,
     value: &'value str,
     underlying_type: &SubType,
-    transformer: &Transformer,
+    transformer: &ParsedTransformer,
     table_name: &str,
     column_values: &[(String, String)],
     global_salt: Option<&str>,
@@ -184,9 +381,13 @@ fn transform_array<'value>(
         )
     } else {
         let unsplit_array = &value[1..value.len() - 1];
+        if unsplit_array.is_empty() {
+            return Cow::from(value);
+        }
         unsplit_array
-            .split(", ")
+            .split(',')
             .map(|list_item| {
+                let list_item = list_item.trim();
                 transform(
                     rng,
                     list_item,
@@ -207,7 +408,7 @@ fn transform_quoted_array(
     rng: &mut SmallRng,
     value: &str,
     sub_type: &Type,
-    transformer: &Transformer,
+    transformer: &ParsedTransformer,
     table_name: &str,
     column_values: &[(String, String)],
     global_salt: Option<&str>,
@@ -219,8 +420,7 @@ fn transform_quoted_array(
     let mut last_char_seen: char = 'a';
     let last_char_index = value.len() - 1;
     for (i, c) in value.chars().enumerate() {
-        trace!("-----------");
-        trace!("current value is '{}'", c);
+        trace!(value = c; "next character of array");
         if i == 0 {
             continue;
         } else if !inside_word && c == '"' {
@@ -255,37 +455,49 @@ fn transform_quoted_array(
 
         last_char_seen = c;
         trace!(
-            "current_word: '{}', inside_word: '{}', last_char_seen: '{}', index: '{}/{}'",
-            current_word,
-            inside_word,
-            last_char_seen,
-            i,
-            last_char_index
+            value = current_word.as_str(),
+            inside_word = inside_word,
+            index = i,
+            last_index = last_char_index;
+            "current word of array"
         );
     }
-    trace!("\noutput - {:?}", word_acc);
+    trace!(value = word_acc.as_str(); "transformed array");
     //Remove the trailing comma from line: 145!
     word_acc.pop();
     word_acc
 }
 
-fn is_deterministic(args: &Option<HashMap<String, String>>) -> bool {
+pub fn arg<'a>(args: &'a Option<TransformerArgs>, key: &str) -> Option<&'a str> {
     args.as_ref()
-        .and_then(|args| args.get("deterministic"))
-        .is_some_and(|val| val == "true")
+        .and_then(|args| args.get(key))
+        .and_then(|value| value.as_str())
 }
 
-fn prepend_unique_if_present(
-    new_value: String,
-    args: &Option<HashMap<String, String>>,
-    unique: usize,
-) -> String {
-    let unique_value = args
-        .as_ref()
-        .and_then(|a| a.get("unique"))
-        .map_or_else(|| false, |u| u == "true");
+/// Flags can be given either as the string `"true"` (as they always have been) or a json `true`
+pub fn arg_is_true(args: &Option<TransformerArgs>, key: &str) -> bool {
+    args.as_ref()
+        .and_then(|args| args.get(key))
+        .is_some_and(|value| value == "true" || value == true)
+}
 
-    if unique_value {
+/// An rng seeded from the value (and id and salt) when the transformer is deterministic, otherwise
+/// a random one
+fn value_rng(
+    value: &str,
+    deterministic: bool,
+    id: Option<&str>,
+    global_salt: Option<&str>,
+) -> SmallRng {
+    if deterministic {
+        get_faker_rng(value, id, global_salt)
+    } else {
+        SmallRng::from_rng(rand::thread_rng()).unwrap_or_else(|_| SmallRng::from_entropy())
+    }
+}
+
+fn prepend_unique_if_present(new_value: String, args: &Args, unique: usize) -> String {
+    if args.unique() {
         format!("{}-{}", unique, new_value)
     } else {
         new_value
@@ -308,54 +520,56 @@ fn fake_base32_string() -> String {
 
 fn fake_company_name(
     value: &str,
-    args: &Option<HashMap<String, String>>,
+    args: &Args,
+    locale: Locale,
     unique: usize,
     global_salt: Option<&str>,
 ) -> String {
     let mut seeded_rng = get_faker_rng(value, None, global_salt);
-    let new_company_name = CompanyName().fake_with_rng::<String, _>(&mut seeded_rng);
+    let new_company_name = locales::company_name(locale, &mut seeded_rng);
     prepend_unique_if_present(new_company_name, args, unique)
 }
 
-fn fake_email(value: &str, global_salt: Option<&str>) -> String {
-    let mut seeded_rng = get_faker_rng(value, None, global_salt);
-    let new_email = FreeEmail().fake_with_rng::<String, _>(&mut seeded_rng);
-
-    let mut hasher = Sha256::new();
-    hasher.update(value.as_bytes());
-    let hash = hasher.finalize();
-    let prefix = base16::encode_lower(&hash[..6]);
-
-    format!("{}-{}", prefix, new_email)
+fn from_dictionary(
+    value: &str,
+    args: &DictionaryArgs,
+    dictionary: &Dictionary,
+    id: Option<&str>,
+    global_salt: Option<&str>,
+) -> String {
+    let picked = dictionary.pick(&mut value_rng(value, args.deterministic, id, global_salt));
+    data_row::encode(picked).into_owned()
 }
 
-fn fake_email_or_phone(current_value: &str, global_salt: Option<&str>) -> String {
+fn fake_email_or_phone(
+    current_value: &str,
+    email_args: &EmailArgs,
+    args: &Args,
+    locale: Locale,
+    id: Option<&str>,
+    table_name: &str,
+    global_salt: Option<&str>,
+) -> String {
     if current_value.starts_with('+') && !current_value.contains('@') {
-        fake_phone_number(current_value)
+        fake_phone_number(current_value, args, locale, id, table_name, global_salt)
     } else {
-        fake_email(current_value, global_salt)
+        email::fake(current_value, email_args, global_salt)
     }
 }
 
-fn fake_street_address() -> String {
-    let building: String = BuildingNumber().fake();
-    let street_name: String = StreetName().fake();
-    format!("{} {}", building, street_name)
+fn fake_street_address(locale: Locale) -> String {
+    locales::street_address(locale, &mut rand::thread_rng())
 }
 
-fn fake_full_address() -> String {
-    let line_1 = fake_street_address();
-    let city_name: String = CityName().fake();
-    let state: String = StateName().fake();
+fn fake_full_address(locale: Locale) -> String {
+    let line_1 = fake_street_address(locale);
+    let city_name = locales::city(locale, &mut rand::thread_rng());
+    let state = locales::state(locale, &mut rand::thread_rng());
     format!("{}, {}, {}", line_1, city_name, state)
 }
 
-fn fake_uuid(
-    value: &str,
-    args: &Option<HashMap<String, String>>,
-    global_salt: Option<&str>,
-) -> String {
-    if !is_deterministic(args) {
+fn fake_uuid(value: &str, args: &Args, global_salt: Option<&str>) -> String {
+    if !args.deterministic() {
         return Uuid::new_v4().to_string();
     }
 
@@ -366,102 +580,98 @@ fn fake_uuid(
 
 fn fake_first_name(
     value: &str,
-    args: &Option<HashMap<String, String>>,
+    args: &Args,
+    locale: Locale,
     id: Option<&str>,
     global_salt: Option<&str>,
 ) -> String {
-    let deterministic = is_deterministic(args);
-    let id_to_use = if deterministic { id } else { None };
+    let id_to_use = if args.deterministic() { id } else { None };
 
     match id_to_use {
         Some(id) => {
             let mut seeded_rng = get_faker_rng(value, Some(id), global_salt);
-            FirstName().fake_with_rng::<String, _>(&mut seeded_rng)
+            locales::first_name(locale, &mut seeded_rng)
         }
-        None => FirstName().fake::<String>(),
+        None => locales::first_name(locale, &mut rand::thread_rng()),
     }
 }
 
 fn fake_last_name(
     value: &str,
-    args: &Option<HashMap<String, String>>,
+    args: &Args,
+    locale: Locale,
     id: Option<&str>,
     global_salt: Option<&str>,
 ) -> String {
-    let deterministic = is_deterministic(args);
-    let id_to_use = if deterministic { id } else { None };
+    let id_to_use = if args.deterministic() { id } else { None };
 
     match id_to_use {
         Some(id) => {
             let mut seeded_rng = get_faker_rng(value, Some(id), global_salt);
-            LastName().fake_with_rng::<String, _>(&mut seeded_rng)
+            locales::last_name(locale, &mut seeded_rng)
         }
-        None => LastName().fake::<String>(),
+        None => locales::last_name(locale, &mut rand::thread_rng()),
     }
 }
 
 fn fake_full_name(
     value: &str,
-    args: &Option<HashMap<String, String>>,
+    args: &Args,
+    locale: Locale,
     id: Option<&str>,
     global_salt: Option<&str>,
 ) -> String {
-    let deterministic = is_deterministic(args);
-    let id_to_use = if deterministic { id } else { None };
-
-    let first = fake_first_name(&format!("{}_first", value), args, id_to_use, global_salt);
-    let last = fake_last_name(&format!("{}_last", value), args, id_to_use, global_salt);
+    let id_to_use = if args.deterministic() { id } else { None };
+
+    let first = fake_first_name(
+        &format!("{}_first", value),
+        args,
+        locale,
+        id_to_use,
+        global_salt,
+    );
+    let last = fake_last_name(
+        &format!("{}_last", value),
+        args,
+        locale,
+        id_to_use,
+        global_salt,
+    );
     format!("{} {}", first, last)
 }
 
-fn fake_national_identity_number() -> String {
-    //TODO currently this is free text so they can enter anything at all,
-    //so im not bothering with us vs uk,
-    //there dont seem to be any us social sec numbers in the DB currently
-    national_insurance_number::random()
-}
-
-//https://www.ofcom.org.uk/phones-telecoms-and-internet/information-for-industry/numbering/numbers-for-drama
-static UK_FAKE_MOBILE_RANGE: Range<i32> = 900000..960999;
-
-fn fake_phone_number(current_value: &str) -> String {
-    let mut rng =
-        SmallRng::from_rng(rand::thread_rng()).unwrap_or_else(|_| SmallRng::from_entropy());
-    if current_value.starts_with("+447") {
-        let random = rng.gen_range(UK_FAKE_MOBILE_RANGE.clone());
-        format!("+447700{0}", random)
-    } else {
-        let area_code = rng.gen_range(200..999);
-        let rest = rng.gen_range(1000000..9999999);
-        format!("+1{}{}", area_code, rest)
+/// A fictitious number like the original, or one in the locale's format for values that aren't
+/// numbers. The locale is checked when the strategy file is loaded, but numbers from other
+/// countries without a reserved range can only be found part way through a run
+fn fake_phone_number(
+    current_value: &str,
+    args: &Args,
+    locale: Locale,
+    id: Option<&str>,
+    table_name: &str,
+    global_salt: Option<&str>,
+) -> String {
+    let mut rng = value_rng(current_value, args.deterministic(), id, global_salt);
+    match phone_number::fake(current_value, locale, &mut rng) {
+        Ok(number) => number,
+        Err(NotFaked::NotANumber) if locale == Locale::En => format!(
+            "+1{}55501{:02}",
+            rng.gen_range(200..999),
+            rng.gen_range(0..100)
+        ),
+        Err(NotFaked::NotANumber) => locales::phone_number(locale, &mut rng),
+        Err(NotFaked::NoFictitiousRange(calling_code)) => panic!(
+            "Phone number from a country without numbers reserved for fiction (+{}) found in table: \"{}\"",
+            calling_code, table_name
+        ),
     }
 }
 
-fn fake_postcode(current_value: &str) -> String {
-    //TODO not sure this is unicode safe...
-    let mut truncated_value = current_value.to_string();
-    truncated_value.truncate(3);
-    truncated_value.to_string()
-}
-
-fn fake_username(args: &Option<HashMap<String, String>>, unique: usize) -> String {
+fn fake_username(args: &Args, unique: usize) -> String {
     let username = Username().fake();
     prepend_unique_if_present(username, args, unique)
 }
 
-fn fixed<'a>(args: &'a Option<HashMap<String, String>>, table_name: &str) -> Cow<'a, str> {
-    let value = args
-        .as_ref()
-        .and_then(|a| a.get("value"))
-        .unwrap_or_else(|| {
-            panic!(
-                "'value' must be present in args for a fixed transformer in table: '{}'\ngot: '{:?}'",
-                table_name, args,
-            )
-        });
-    Cow::from(value)
-}
-
 fn obfuscate_day(value: &str, table_name: &str) -> String {
     match NaiveDate::parse_from_str(value, "%Y-%m-%d") {
         Ok(date) => {
@@ -594,11 +804,42 @@ fn scramble_blank(original_value: &str) -> String {
 mod tests {
     use super::*;
     use crate::parsers::rng;
+    use crate::parsers::strategy_structs::Transformer;
     use regex::Regex;
+    use std::collections::HashMap;
 
     const TABLE_NAME: &str = "gert_lush_table";
     const EMPTY_COLUMNS: &[(String, String)] = &[];
 
+    fn parsed(transformer: &Transformer) -> ParsedTransformer {
+        ParsedTransformer::parse(transformer).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Parses the transformer as the strategy file would be, then transforms the value with it
+    fn transform(
+        rng: &mut SmallRng,
+        value: &str,
+        column_type: &Type,
+        transformer: &Transformer,
+        table_name: &str,
+        column_values: &[(String, String)],
+        global_salt: Option<&str>,
+    ) -> Cow<'static, str> {
+        let transformer = parsed(transformer);
+        Cow::Owned(
+            super::transform(
+                rng,
+                value,
+                column_type,
+                &transformer,
+                table_name,
+                column_values,
+                global_salt,
+            )
+            .into_owned(),
+        )
+    }
+
     #[test]
     fn null_is_not_transformed() {
         let null = "\\N";
@@ -662,6 +903,74 @@ mod tests {
         assert!(new_first_name == first_name);
     }
 
+    #[test]
+    fn dictionary_is_deterministic_by_value() {
+        let transformer = Transformer {
+            name: TransformerType::Dictionary,
+            args: Some(HashMap::from([
+                ("file".to_string(), "test_files/job_titles.csv".into()),
+                ("weight_column".to_string(), "weight".into()),
+                ("deterministic".to_string(), "true".into()),
+            ])),
+        };
+        let column_type = Type::SingleValue {
+            sub_type: SubType::Character,
+        };
+        let mut rng = rng::get();
+        let title = transform(
+            &mut rng,
+            "Head of Engineering",
+            &column_type,
+            &transformer,
+            TABLE_NAME,
+            EMPTY_COLUMNS,
+            None,
+        );
+        assert!([
+            "Software Engineer",
+            "Data Analyst",
+            "Delivery Manager",
+            "Apprentice, Level 3"
+        ]
+        .contains(&title.as_ref()));
+        for _ in 0..10 {
+            let same_title = transform(
+                &mut rng,
+                "Head of Engineering",
+                &column_type,
+                &transformer,
+                TABLE_NAME,
+                EMPTY_COLUMNS,
+                None,
+            );
+            assert_eq!(title, same_title);
+        }
+    }
+
+    #[test]
+    fn dictionary_values_are_escaped_for_copy_data() {
+        let transformer = Transformer {
+            name: TransformerType::Dictionary,
+            args: Some(HashMap::from([(
+                "file".to_string(),
+                "test_files/course_folders.txt".into(),
+            )])),
+        };
+        let mut rng = rng::get();
+        let folder = transform(
+            &mut rng,
+            "D:\\\\Private",
+            &Type::SingleValue {
+                sub_type: SubType::Character,
+            },
+            &transformer,
+            TABLE_NAME,
+            EMPTY_COLUMNS,
+            None,
+        );
+        assert_eq!(folder, "C:\\\\Courses\\tRust");
+    }
+
     #[test]
     fn fake_base16_string() {
         let verification_key = "1702a4eddd53d6fa79ed4a677e64c002";
@@ -761,7 +1070,7 @@ mod tests {
             name: TransformerType::FakeUUID,
             args: Some(HashMap::from([(
                 "deterministic".to_string(),
-                "true".to_string(),
+                "true".into(),
             )])),
         };
 
@@ -834,7 +1143,7 @@ mod tests {
             name: TransformerType::FakeUUID,
             args: Some(HashMap::from([(
                 "deterministic".to_string(),
-                "true".to_string(),
+                "true".into(),
             )])),
         };
 
@@ -980,7 +1289,7 @@ mod tests {
         let mut rng = rng::get();
         let transformer = &Transformer {
             name: TransformerType::FakeCompanyName,
-            args: Some(HashMap::from([("unique".to_string(), "true".to_string())])),
+            args: Some(HashMap::from([("unique".to_string(), "true".into())])),
         };
         let new_company_name = transform(
             &mut rng,
@@ -1200,8 +1509,8 @@ mod tests {
         let transformer = Transformer {
             name: TransformerType::FakeFirstName,
             args: Some(HashMap::from([
-                ("deterministic".to_string(), "true".to_string()),
-                ("id_column".to_string(), "user_id".to_string()),
+                ("deterministic".to_string(), "true".into()),
+                ("id_column".to_string(), "user_id".into()),
             ])),
         };
 
@@ -1266,8 +1575,8 @@ mod tests {
         let transformer = Transformer {
             name: TransformerType::FakeFirstName,
             args: Some(HashMap::from([
-                ("deterministic".to_string(), "true".to_string()),
-                ("id_column".to_string(), "user_id".to_string()),
+                ("deterministic".to_string(), "true".into()),
+                ("id_column".to_string(), "user_id".into()),
             ])),
         };
 
@@ -1356,8 +1665,8 @@ mod tests {
         let transformer = Transformer {
             name: TransformerType::FakeFullName,
             args: Some(HashMap::from([
-                ("deterministic".to_string(), "true".to_string()),
-                ("id_column".to_string(), "user_id".to_string()),
+                ("deterministic".to_string(), "true".into()),
+                ("id_column".to_string(), "user_id".into()),
             ])),
         };
 
@@ -1443,8 +1752,8 @@ mod tests {
         let transformer = Transformer {
             name: TransformerType::FakeLastName,
             args: Some(HashMap::from([
-                ("deterministic".to_string(), "true".to_string()),
-                ("id_column".to_string(), "user_id".to_string()),
+                ("deterministic".to_string(), "true".into()),
+                ("id_column".to_string(), "user_id".into()),
             ])),
         };
 
@@ -1540,8 +1849,9 @@ mod tests {
             None,
         );
         assert!(new_national_identity_number != national_identity_number);
-        assert!(national_insurance_number::NATIONAL_INSURANCE_NUMBERS
-            .contains(&new_national_identity_number.as_ref()));
+        assert!(Regex::new(r"^[A-Z]{2} \d{2} \d{2} \d{2} [A-D]$")
+            .unwrap()
+            .is_match(&new_national_identity_number));
     }
 
     #[test]
@@ -1634,6 +1944,67 @@ mod tests {
         assert_eq!(new_phone_number.len(), 12);
     }
 
+    #[test]
+    fn fake_card_number_deterministic() {
+        let card_number = "4929 1234 5678 9012";
+        let transformer = Transformer {
+            name: TransformerType::FakeCardNumber,
+            args: Some(HashMap::from([
+                ("deterministic".to_string(), true.into()),
+                ("id_column".to_string(), "id".into()),
+                ("keep_last_4".to_string(), true.into()),
+            ])),
+        };
+        let column_values = [("id".to_string(), "1".to_string())];
+        let transform_card = || {
+            transform(
+                &mut rng::get(),
+                card_number,
+                &Type::single_value(SubType::Character),
+                &transformer,
+                TABLE_NAME,
+                &column_values,
+                None,
+            )
+            .into_owned()
+        };
+        let new_card_number = transform_card();
+        assert!(crate::parsers::pii_patterns::is_luhn_valid(
+            &new_card_number
+        ));
+        assert!(new_card_number.ends_with(" 9012"));
+        assert_eq!(new_card_number, transform_card());
+    }
+
+    #[test]
+    fn fake_phone_number_deterministic() {
+        let phone_number = "+33 6 12 34 56 78";
+        let transformer = Transformer {
+            name: TransformerType::FakePhoneNumber,
+            args: Some(HashMap::from([
+                ("deterministic".to_string(), "true".into()),
+                ("id_column".to_string(), "id".into()),
+            ])),
+        };
+        let column_values = [("id".to_string(), "1".to_string())];
+        let transform_number = || {
+            transform(
+                &mut rng::get(),
+                phone_number,
+                &Type::single_value(SubType::Character),
+                &transformer,
+                TABLE_NAME,
+                &column_values,
+                None,
+            )
+            .into_owned()
+        };
+        let new_phone_number = transform_number();
+        assert!(new_phone_number.starts_with("+33 6 39 98 "));
+        assert_eq!(new_phone_number.len(), phone_number.len());
+        assert_eq!(new_phone_number, transform_number());
+    }
+
     #[test]
     fn fake_postcode() {
         let postcode = "NW5 3QQ";
@@ -1652,7 +2023,8 @@ mod tests {
             EMPTY_COLUMNS,
             None,
         );
-        assert_eq!(new_postcode, "NW5");
+        assert!(new_postcode.starts_with("NW5 "), "{}", new_postcode);
+        assert_eq!(new_postcode.len(), 7);
     }
 
     #[test]
@@ -1682,7 +2054,7 @@ mod tests {
         let mut rng = rng::get();
         let transformer = &Transformer {
             name: TransformerType::FakeUsername,
-            args: Some(HashMap::from([("unique".to_string(), "true".to_string())])),
+            args: Some(HashMap::from([("unique".to_string(), "true".into())])),
         };
         let new_user_name = transform(
             &mut rng,
@@ -1712,10 +2084,7 @@ mod tests {
         let mut rng = rng::get();
         let transformer = &Transformer {
             name: TransformerType::Fixed,
-            args: Some(HashMap::from([(
-                "value".to_string(),
-                fixed_url.to_string(),
-            )])),
+            args: Some(HashMap::from([("value".to_string(), fixed_url.into())])),
         };
         let new_url = transform(
             &mut rng,
@@ -1731,7 +2100,7 @@ mod tests {
         assert_eq!(new_url, fixed_url);
     }
     #[test]
-    #[should_panic(expected = "'value' must be present in args for a fixed transformer")]
+    #[should_panic(expected = "invalid args for Fixed: missing field `value`")]
     fn fixed_panics_if_value_not_provided() {
         let mut rng = rng::get();
         let url = "any web address";
@@ -2104,6 +2473,34 @@ mod tests {
         );
     }
 
+    #[test]
+    fn can_fake_inet_arrays() {
+        let initial_value = "{192.168.10.25,2001:db8::1/64}";
+        let transformer = Transformer {
+            name: TransformerType::FakeIPv4,
+            args: Some(HashMap::from([
+                ("keep_prefix_v4".to_string(), 24.into()),
+                ("keep_prefix_v6".to_string(), 32.into()),
+            ])),
+        };
+        let mut rng = rng::get();
+        let new_value = transform(
+            &mut rng,
+            initial_value,
+            &Type::Array {
+                sub_type: SubType::Unknown {
+                    underlying_type: "inet".to_string(),
+                },
+            },
+            &transformer,
+            TABLE_NAME,
+            EMPTY_COLUMNS,
+            None,
+        );
+        let re = Regex::new(r"^\{192\.168\.10\.[0-9]{1,3},2001:db8:[0-9a-f:]+/64\}$").unwrap();
+        assert!(re.is_match(&new_value), "new value: {}", new_value);
+    }
+
     #[test]
     fn scramble_blank_maintains_word_boundaries() {
         let initial_value = "Sample Text";
@@ -2422,4 +2819,254 @@ mod tests {
         );
         assert_eq!(new_datetime, "2025-02-01 00:00:00");
     }
+
+    #[test]
+    fn regex_replace_replaces_matches_inside_free_text() {
+        let notes = r"Call me on 07700 900123\nor email bob@example.com";
+        let mut rng = rng::get();
+        let transformer = Transformer {
+            name: TransformerType::RegexReplace,
+            args: Some(HashMap::from([(
+                "patterns".to_string(),
+                serde_json::json!([
+                    {"pattern": "email", "replace_with": {"fixed": "someone@example.net"}},
+                    {"pattern": "phone_number", "replace_with": {"mask": "0"}}
+                ]),
+            )])),
+        };
+        let new_notes = transform(
+            &mut rng,
+            notes,
+            &Type::SingleValue {
+                sub_type: SubType::Character,
+            },
+            &transformer,
+            TABLE_NAME,
+            EMPTY_COLUMNS,
+            None,
+        );
+        assert_eq!(
+            new_notes,
+            r"Call me on 00000 000000\nor email someone@example.net"
+        );
+    }
+
+    #[test]
+    fn regex_replace_can_use_a_custom_regex_and_a_nested_transformer() {
+        let notes = "customer ref ABC-1234 complained";
+        let mut rng = rng::get();
+        let transformer = Transformer {
+            name: TransformerType::RegexReplace,
+            args: Some(HashMap::from([(
+                "patterns".to_string(),
+                serde_json::json!([{
+                    "regex": "[A-Z]{3}-\\d{4}",
+                    "replace_with": {"transformer": {"name": "Scramble"}}
+                }]),
+            )])),
+        };
+        let new_notes = transform(
+            &mut rng,
+            notes,
+            &Type::SingleValue {
+                sub_type: SubType::Character,
+            },
+            &transformer,
+            TABLE_NAME,
+            EMPTY_COLUMNS,
+            None,
+        );
+        let re = Regex::new(r"^customer ref [a-z]{4}\d{4} complained$").unwrap();
+        assert!(re.is_match(&new_notes), "{} didn't match", new_notes);
+    }
+
+    #[test]
+    fn regex_replace_earlier_patterns_win_where_matches_overlap() {
+        let notes = "card 4242 4242 4242 4242";
+        let mut rng = rng::get();
+        let transformer = Transformer {
+            name: TransformerType::RegexReplace,
+            args: Some(HashMap::from([(
+                "patterns".to_string(),
+                serde_json::json!([
+                    {"pattern": "card_number", "replace_with": {"fixed": "[CARD]"}},
+                    {"regex": "\\d{4}", "replace_with": {"fixed": "[DIGITS]"}}
+                ]),
+            )])),
+        };
+        let new_notes = transform(
+            &mut rng,
+            notes,
+            &Type::SingleValue {
+                sub_type: SubType::Character,
+            },
+            &transformer,
+            TABLE_NAME,
+            EMPTY_COLUMNS,
+            None,
+        );
+        assert_eq!(new_notes, "card [CARD]");
+    }
+
+    #[test]
+    #[should_panic(expected = "unknown pattern 'passport'")]
+    fn regex_replace_panics_on_unknown_pattern() {
+        let mut rng = rng::get();
+        transform(
+            &mut rng,
+            "some text",
+            &Type::SingleValue {
+                sub_type: SubType::Character,
+            },
+            &Transformer {
+                name: TransformerType::RegexReplace,
+                args: Some(HashMap::from([(
+                    "patterns".to_string(),
+                    serde_json::json!([{"pattern": "passport", "replace_with": {"fixed": "x"}}]),
+                )])),
+            },
+            TABLE_NAME,
+            EMPTY_COLUMNS,
+            None,
+        );
+    }
+
+    fn contact_value_transformer() -> Transformer {
+        Transformer {
+            name: TransformerType::Conditional,
+            args: Some(HashMap::from([
+                (
+                    "cases".to_string(),
+                    serde_json::json!([
+                        {
+                            "when": {"column": "contact_type", "equals": "email"},
+                            "transformer": {"name": "Fixed", "args": {"value": "an email"}}
+                        },
+                        {
+                            "when": {"column": "contact_type", "in": ["phone", "mobile"]},
+                            "transformer": {"name": "Fixed", "args": {"value": "a phone"}}
+                        },
+                        {
+                            "when": {"column": "contact_type", "is_null": true},
+                            "transformer": {"name": "Fixed", "args": {"value": "no type"}}
+                        }
+                    ]),
+                ),
+                (
+                    "default".to_string(),
+                    serde_json::json!({"name": "ScrambleBlank"}),
+                ),
+            ])),
+        }
+    }
+
+    #[test]
+    fn conditional_uses_the_first_matching_case() {
+        let mut rng = rng::get();
+        let transformer = contact_value_transformer();
+        let transform_with_type = |rng: &mut SmallRng, contact_type: &str| {
+            transform(
+                rng,
+                "peter@example.com",
+                &Type::SingleValue {
+                    sub_type: SubType::Character,
+                },
+                &transformer,
+                TABLE_NAME,
+                &[("contact_type".to_string(), contact_type.to_string())],
+                None,
+            )
+            .into_owned()
+        };
+
+        assert_eq!(transform_with_type(&mut rng, "email"), "an email");
+        assert_eq!(transform_with_type(&mut rng, "mobile"), "a phone");
+        assert_eq!(transform_with_type(&mut rng, "\\N"), "no type");
+        assert_eq!(transform_with_type(&mut rng, "fax"), "_________________");
+    }
+
+    #[test]
+    fn phone_numbers_need_a_locale_with_numbers_reserved_for_fiction() {
+        let phone_number = |name: TransformerType, locale: &str| {
+            parsed(&Transformer {
+                name,
+                args: Some(HashMap::from([("locale".to_string(), locale.into())])),
+            })
+        };
+        assert!(validate_args(
+            &phone_number(TransformerType::FakePhoneNumber, "de_de"),
+            &[]
+        )
+        .is_ok());
+        for name in [
+            TransformerType::FakePhoneNumber,
+            TransformerType::FakeEmailOrPhone,
+        ] {
+            assert_eq!(
+                validate_args(&phone_number(name, "pt_br"), &[]).unwrap_err(),
+                "no +55 phone numbers are reserved for fiction, so fake ones can't be made for the 'pt_br' locale"
+            );
+        }
+    }
+
+    #[test]
+    fn conditional_args_are_validated() {
+        let transformer = contact_value_transformer();
+        assert!(validate_args(&parsed(&transformer), &["contact_type"]).is_ok());
+        assert_eq!(
+            validate_args(&parsed(&transformer), &["kind"]).unwrap_err(),
+            "condition refers to unknown column 'contact_type'"
+        );
+    }
+
+    #[test]
+    fn conditional_predicates_are_checked_when_parsed() {
+        let with_when = |when: serde_json::Value| Transformer {
+            name: TransformerType::Conditional,
+            args: Some(HashMap::from([
+                (
+                    "cases".to_string(),
+                    serde_json::json!([{"when": when, "transformer": {"name": "Identity"}}]),
+                ),
+                (
+                    "default".to_string(),
+                    serde_json::json!({"name": "Identity"}),
+                ),
+            ])),
+        };
+        assert!(ParsedTransformer::parse(&with_when(
+            serde_json::json!({"column": "contact_type", "regex": "^e"})
+        ))
+        .is_ok());
+        assert_eq!(
+            ParsedTransformer::parse(&with_when(
+                serde_json::json!({"column": "contact_type", "equals": "email", "is_null": true})
+            ))
+            .unwrap_err(),
+            "invalid args for Conditional: the condition on 'contact_type' needs exactly one of 'equals', 'in', 'regex' or 'is_null'"
+        );
+        assert!(ParsedTransformer::parse(&with_when(
+            serde_json::json!({"column": "contact_type", "regex": "("})
+        ))
+        .unwrap_err()
+        .starts_with("invalid args for Conditional: invalid regex '('"));
+    }
+
+    #[test]
+    fn templates_cannot_be_nested() {
+        let transformer = Transformer {
+            name: TransformerType::Conditional,
+            args: Some(HashMap::from([
+                ("cases".to_string(), serde_json::json!([])),
+                (
+                    "default".to_string(),
+                    serde_json::json!({"name": "Template", "args": {"template": "{name}"}}),
+                ),
+            ])),
+        };
+        assert_eq!(
+            validate_args(&parsed(&transformer), &["name"]).unwrap_err(),
+            "Template can only be used as a column's own transformer"
+        );
+    }
 }
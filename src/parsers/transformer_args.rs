@@ -0,0 +1,759 @@
+use crate::parsers::conditional::ConditionalArgs;
+use crate::parsers::dictionary::{self, Dictionary};
+use crate::parsers::email::EmailArgs;
+use crate::parsers::locales::Locale;
+use crate::parsers::national_identity_number::{self, Format};
+use crate::parsers::network;
+use crate::parsers::nulls;
+use crate::parsers::pipeline;
+use crate::parsers::postcode::Mode;
+use crate::parsers::redact::RedactArgs;
+use crate::parsers::regex_replace::ReplaceRule;
+use crate::parsers::strategy_structs::{Transformer, TransformerArgs, TransformerType};
+use crate::parsers::template::{self, Part};
+use crate::parsers::unique::UniqueArgs;
+use schemars::{json_schema, JsonSchema, Schema, SchemaGenerator};
+use serde::de::{DeserializeOwned, Error};
+use serde::{Deserialize, Deserializer};
+use serde_json::{Map, Value};
+use std::borrow::Cow;
+use std::str::FromStr;
+use std::sync::Arc;
+
+/// Args every transformer (apart from the string post processors) takes, which are parsed into
+/// `UniqueArgs` rather than each transformer's own args
+const UNIQUE_ARGS: [&str; 2] = ["ensure_unique", "unique_group"];
+
+/// The args of `PersonArgs`, which `FakeEmailOrPhone` takes as well as those of `EmailArgs`
+const PERSON_ARGS: [&str; 3] = ["deterministic", "id_column", "locale"];
+
+/// Flags can be given either as the string `"true"` (as they always have been) or a json `true`
+pub fn flag<'de, D: Deserializer<'de>>(deserializer: D) -> Result<bool, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Flag {
+        Bool(bool),
+        String(String),
+    }
+    match Flag::deserialize(deserializer)? {
+        Flag::Bool(flag) => Ok(flag),
+        Flag::String(flag) => flag
+            .parse()
+            .map_err(|_| D::Error::custom(format!("expected true or false, not '{}'", flag))),
+    }
+}
+
+/// Numbers can be given either as a json number or a string
+pub fn number<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+    T: FromStr + Deserialize<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Number<T> {
+        Number(T),
+        String(String),
+    }
+    match Number::<T>::deserialize(deserializer)? {
+        Number::Number(number) => Ok(number),
+        Number::String(number) => number
+            .parse()
+            .map_err(|_| D::Error::custom(format!("expected a number, not '{}'", number))),
+    }
+}
+
+/// The schema of a flag, which can be a json boolean or a string (see `flag`)
+pub fn flag_schema(_: &mut SchemaGenerator) -> Schema {
+    json_schema!({ "enum": [true, false, "true", "false"] })
+}
+
+/// The schema of a number, which can be a json number or a string of one (see `number`)
+pub fn number_schema(_: &mut SchemaGenerator) -> Schema {
+    json_schema!({
+        "anyOf": [
+            { "type": "number" },
+            { "type": "string", "pattern": "^[-+]?[0-9]+(\\.[0-9]+)?$" }
+        ]
+    })
+}
+
+#[derive(Clone, Debug, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct NoArgs {}
+
+/// The args of transformers which can be seeded from the value (and an id) to always give the same
+/// output
+#[derive(Clone, Debug, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct SeededArgs {
+    /// Always give the same output for the same value (and id)
+    #[serde(default, deserialize_with = "flag")]
+    #[schemars(schema_with = "flag_schema")]
+    pub deterministic: bool,
+    /// A column of the same row to seed deterministic output with
+    pub id_column: Option<String>,
+}
+
+#[derive(Clone, Debug, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct LocaleArgs {
+    /// The locale of the fake values
+    pub locale: Option<Locale>,
+}
+
+#[derive(Clone, Debug, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct CardNumberArgs {
+    /// Keep the first 6 digits
+    #[serde(default, deserialize_with = "flag")]
+    #[schemars(schema_with = "flag_schema")]
+    pub keep_bin: bool,
+    /// Keep the last 4 digits
+    #[serde(default, deserialize_with = "flag")]
+    #[schemars(schema_with = "flag_schema")]
+    pub keep_last_4: bool,
+    /// Always give the same output for the same value (and id)
+    #[serde(default, deserialize_with = "flag")]
+    #[schemars(schema_with = "flag_schema")]
+    pub deterministic: bool,
+    /// A column of the same row to seed deterministic output with
+    pub id_column: Option<String>,
+}
+
+#[derive(Clone, Debug, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct CompanyNameArgs {
+    /// Prefix values with an incrementing number
+    #[serde(default, deserialize_with = "flag")]
+    #[schemars(schema_with = "flag_schema")]
+    pub unique: bool,
+    /// The locale of the fake values
+    pub locale: Option<Locale>,
+}
+
+#[derive(Clone, Debug, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct DictionaryArgs {
+    /// The file of values to pick from
+    pub file: String,
+    /// The column of a .csv file to use
+    pub column: Option<String>,
+    /// A column of a .csv file with relative weights
+    pub weight_column: Option<String>,
+    /// Always give the same output for the same value (and id)
+    #[serde(default, deserialize_with = "flag")]
+    #[schemars(schema_with = "flag_schema")]
+    pub deterministic: bool,
+    /// A column of the same row to seed deterministic output with
+    pub id_column: Option<String>,
+}
+
+/// The args of the fake names and phone numbers
+#[derive(Clone, Debug, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct PersonArgs {
+    /// Always give the same output for the same value (and id)
+    #[serde(default, deserialize_with = "flag")]
+    #[schemars(schema_with = "flag_schema")]
+    pub deterministic: bool,
+    /// A column of the same row to seed deterministic output with
+    pub id_column: Option<String>,
+    /// The locale of the fake values
+    pub locale: Option<Locale>,
+}
+
+#[derive(Clone, Debug, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct IpArgs {
+    /// The number of leading bits of IPv4 addresses to keep, less than 32
+    #[serde(default, deserialize_with = "network::keep_prefix_v4")]
+    #[schemars(schema_with = "number_schema")]
+    pub keep_prefix_v4: u32,
+    /// The number of leading bits of IPv6 addresses to keep, less than 128
+    #[serde(default, deserialize_with = "network::keep_prefix_v6")]
+    #[schemars(schema_with = "number_schema")]
+    pub keep_prefix_v6: u32,
+    /// Always give the same output for the same value (and id)
+    #[serde(default, deserialize_with = "flag")]
+    #[schemars(schema_with = "flag_schema")]
+    pub deterministic: bool,
+    /// A column of the same row to seed deterministic output with
+    pub id_column: Option<String>,
+}
+
+#[derive(Clone, Debug, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct NationalIdentityNumberArgs {
+    /// auto, uk_national_insurance, us_ssn or us_itin
+    #[serde(default, deserialize_with = "national_identity_number::format")]
+    #[schemars(with = "String")]
+    pub format: Option<Format>,
+    /// Prefix values with an incrementing number
+    #[serde(default, deserialize_with = "flag")]
+    #[schemars(schema_with = "flag_schema")]
+    pub unique: bool,
+    /// Always give the same output for the same value (and id)
+    #[serde(default, deserialize_with = "flag")]
+    #[schemars(schema_with = "flag_schema")]
+    pub deterministic: bool,
+    /// A column of the same row to seed deterministic output with
+    pub id_column: Option<String>,
+}
+
+#[derive(Clone, Debug, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct PostCodeArgs {
+    /// keep_outward (the default), district, fake or us_zip
+    #[serde(default)]
+    pub mode: Mode,
+    /// Always give the same output for the same value (and id)
+    #[serde(default, deserialize_with = "flag")]
+    #[schemars(schema_with = "flag_schema")]
+    pub deterministic: bool,
+    /// A column of the same row to seed deterministic output with
+    pub id_column: Option<String>,
+}
+
+#[derive(Clone, Debug, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct UsernameArgs {
+    /// Prefix values with an incrementing number
+    #[serde(default, deserialize_with = "flag")]
+    #[schemars(schema_with = "flag_schema")]
+    pub unique: bool,
+}
+
+#[derive(Clone, Debug, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct UuidArgs {
+    /// Always give the same output for the same value
+    #[serde(default, deserialize_with = "flag")]
+    #[schemars(schema_with = "flag_schema")]
+    pub deterministic: bool,
+}
+
+#[derive(Clone, Debug, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct FixedArgs {
+    /// The value to use
+    pub value: String,
+}
+
+#[derive(Clone, Debug, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct PipelineArgs {
+    /// The transformers to apply in order
+    pub steps: Vec<ParsedTransformer>,
+}
+
+#[derive(Clone, Debug, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct ProbabilisticNullArgs {
+    /// The fraction of values to null, between 0 and 1
+    #[serde(deserialize_with = "nulls::fraction")]
+    #[schemars(schema_with = "number_schema")]
+    pub fraction: f64,
+    /// A column of the same row to decide which values are nulled with
+    pub id_column: Option<String>,
+}
+
+#[derive(Clone, Debug, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct RegexReplaceArgs {
+    /// The patterns to find and what to replace them with
+    pub patterns: Vec<ReplaceRule>,
+}
+
+#[derive(Clone, Debug, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct TemplateArgs {
+    /// The template e.g. {first_name}.{last_name}@example.test
+    pub template: String,
+    /// Always give the same output for the same value
+    #[serde(default, deserialize_with = "flag")]
+    #[schemars(schema_with = "flag_schema")]
+    pub deterministic: bool,
+    /// The locale of the fake values
+    pub locale: Option<Locale>,
+}
+
+#[derive(Clone, Debug, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct TruncateArgs {
+    /// The number of characters to keep
+    #[serde(deserialize_with = "number")]
+    #[schemars(schema_with = "number_schema")]
+    pub length: usize,
+}
+
+#[derive(Clone, Debug, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum PadSide {
+    Left,
+    Right,
+}
+
+#[derive(Clone, Debug, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct PadArgs {
+    /// The length to pad to
+    #[serde(deserialize_with = "number")]
+    #[schemars(schema_with = "number_schema")]
+    pub length: usize,
+    /// The character to pad with
+    #[serde(default = "default_pad_with")]
+    pub with: char,
+    #[serde(default = "default_pad_side")]
+    pub side: PadSide,
+}
+
+fn default_pad_with() -> char {
+    ' '
+}
+
+fn default_pad_side() -> PadSide {
+    PadSide::Left
+}
+
+/// The args of `Prefix` and `Suffix`
+#[derive(Clone, Debug, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct AffixArgs {
+    /// The text to add
+    pub value: String,
+}
+
+#[derive(Clone, Debug, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct ReplaceArgs {
+    /// The text to replace
+    pub from: String,
+    /// The text to replace it with
+    pub to: String,
+}
+
+/// The typed args of a transformer
+#[derive(Clone, Debug)]
+pub enum Args {
+    None,
+    Seeded(SeededArgs),
+    Locale(LocaleArgs),
+    CardNumber(CardNumberArgs),
+    CompanyName(CompanyNameArgs),
+    Conditional(ConditionalArgs),
+    /// The dictionary is loaded along with the args, rather than for every value
+    Dictionary(DictionaryArgs, Arc<Dictionary>),
+    FakeEmail(EmailArgs),
+    FakeEmailOrPhone(EmailArgs, PersonArgs),
+    Person(PersonArgs),
+    Ip(IpArgs),
+    NationalIdentityNumber(NationalIdentityNumberArgs),
+    PostCode(PostCodeArgs),
+    Username(UsernameArgs),
+    Uuid(UuidArgs),
+    Fixed(FixedArgs),
+    Pipeline(Vec<ParsedTransformer>),
+    ProbabilisticNull(ProbabilisticNullArgs),
+    Redact(RedactArgs),
+    RegexReplace(RegexReplaceArgs),
+    /// The template is parsed along with the args, rather than for every value
+    Template(TemplateArgs, Vec<Part>),
+    Truncate(TruncateArgs),
+    Pad(PadArgs),
+    Affix(AffixArgs),
+    Replace(ReplaceArgs),
+}
+
+impl Args {
+    pub fn deterministic(&self) -> bool {
+        match self {
+            Args::Seeded(args) => args.deterministic,
+            Args::CardNumber(args) => args.deterministic,
+            Args::Dictionary(args, _) => args.deterministic,
+            Args::FakeEmailOrPhone(_, args) | Args::Person(args) => args.deterministic,
+            Args::Ip(args) => args.deterministic,
+            Args::NationalIdentityNumber(args) => args.deterministic,
+            Args::PostCode(args) => args.deterministic,
+            Args::Uuid(args) => args.deterministic,
+            Args::Redact(args) => args.deterministic,
+            Args::Template(args, _) => args.deterministic,
+            _ => false,
+        }
+    }
+
+    pub fn id_column(&self) -> Option<&str> {
+        match self {
+            Args::Seeded(args) => args.id_column.as_deref(),
+            Args::CardNumber(args) => args.id_column.as_deref(),
+            Args::Dictionary(args, _) => args.id_column.as_deref(),
+            Args::FakeEmailOrPhone(_, args) | Args::Person(args) => args.id_column.as_deref(),
+            Args::Ip(args) => args.id_column.as_deref(),
+            Args::NationalIdentityNumber(args) => args.id_column.as_deref(),
+            Args::PostCode(args) => args.id_column.as_deref(),
+            Args::ProbabilisticNull(args) => args.id_column.as_deref(),
+            _ => None,
+        }
+    }
+
+    /// The `locale` arg of the locale aware transformers, defaulting to `En`
+    pub fn locale(&self) -> Locale {
+        let locale = match self {
+            Args::Locale(args) => args.locale,
+            Args::CompanyName(args) => args.locale,
+            Args::FakeEmailOrPhone(_, args) | Args::Person(args) => args.locale,
+            Args::Redact(args) => args.locale,
+            Args::Template(args, _) => args.locale,
+            _ => None,
+        };
+        locale.unwrap_or(Locale::En)
+    }
+
+    /// The `unique` arg, which prefixes the fake values with a counter
+    pub fn unique(&self) -> bool {
+        match self {
+            Args::CompanyName(args) => args.unique,
+            Args::NationalIdentityNumber(args) => args.unique,
+            Args::Username(args) => args.unique,
+            _ => false,
+        }
+    }
+}
+
+/// A transformer with its args parsed, which is what is kept for each column once the strategy
+/// file has been loaded so that nothing is parsed again for every value
+#[derive(Clone, Debug)]
+pub struct ParsedTransformer {
+    pub name: TransformerType,
+    pub args: Args,
+    pub unique: UniqueArgs,
+    /// The transformer as it is in the strategy file
+    pub source: Transformer,
+}
+
+impl PartialEq for ParsedTransformer {
+    fn eq(&self, other: &Self) -> bool {
+        self.source == other.source
+    }
+}
+
+impl Eq for ParsedTransformer {}
+
+/// Transformers nested in others (e.g. the steps of a pipeline) are parsed along with them
+impl<'de> Deserialize<'de> for ParsedTransformer {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let transformer = Transformer::deserialize(deserializer)?;
+        ParsedTransformer::parse(&transformer).map_err(D::Error::custom)
+    }
+}
+
+impl ParsedTransformer {
+    /// Parses the args of a transformer into its typed args, so that unknown args (e.g. a misspelt
+    /// `determinstic`) and missing or mistyped ones are reported when the strategy file is loaded
+    /// rather than ignored or found part way through a run
+    pub fn parse(transformer: &Transformer) -> Result<Self, String> {
+        let name = &transformer.name;
+        let args = transformer.args.clone().unwrap_or_default();
+        let (unique, args): (TransformerArgs, TransformerArgs) =
+            if pipeline::STRING_POST_PROCESSORS.contains(name) {
+                (TransformerArgs::new(), args)
+            } else {
+                args.into_iter()
+                    .partition(|(key, _)| UNIQUE_ARGS.contains(&key.as_str()))
+            };
+
+        let typed_args = match name {
+            TransformerType::Conditional => Args::Conditional(parse(name, args)?),
+            TransformerType::Dictionary => {
+                let args: DictionaryArgs = parse(name, args)?;
+                let dictionary = dictionary::load(&args)?;
+                Args::Dictionary(args, dictionary)
+            }
+            TransformerType::EmptyJson
+            | TransformerType::Error
+            | TransformerType::FakeBase16String
+            | TransformerType::FakeBase32String
+            | TransformerType::Identity
+            | TransformerType::Lowercase
+            | TransformerType::Null
+            | TransformerType::ObfuscateDay
+            | TransformerType::ObfuscateDateTime
+            | TransformerType::Scramble
+            | TransformerType::ScrambleBlank
+            | TransformerType::Uppercase => {
+                parse::<NoArgs>(name, args)?;
+                Args::None
+            }
+            TransformerType::FakeAccountNumber
+            | TransformerType::FakeIban
+            | TransformerType::FakeMacAddress
+            | TransformerType::FakeSortCode
+            | TransformerType::FakeUrl
+            | TransformerType::FakeUserAgent => Args::Seeded(parse(name, args)?),
+            TransformerType::FakeCardNumber => Args::CardNumber(parse(name, args)?),
+            TransformerType::FakeCity
+            | TransformerType::FakeFullAddress
+            | TransformerType::FakeState
+            | TransformerType::FakeStreetAddress => Args::Locale(parse(name, args)?),
+            TransformerType::FakeCompanyName => Args::CompanyName(parse(name, args)?),
+            TransformerType::FakeEmail => Args::FakeEmail(parse(name, args)?),
+            TransformerType::FakeEmailOrPhone => {
+                // Emails get the email args and phone numbers the rest
+                let (person, email): (TransformerArgs, TransformerArgs) = args
+                    .into_iter()
+                    .partition(|(key, _)| PERSON_ARGS.contains(&key.as_str()));
+                Args::FakeEmailOrPhone(parse(name, email)?, parse(name, person)?)
+            }
+            TransformerType::FakeFirstName
+            | TransformerType::FakeFullName
+            | TransformerType::FakeLastName
+            | TransformerType::FakePhoneNumber => Args::Person(parse(name, args)?),
+            TransformerType::FakeIPv4 | TransformerType::FakeIPv6 => Args::Ip(parse(name, args)?),
+            TransformerType::FakeNationalIdentityNumber => {
+                Args::NationalIdentityNumber(parse(name, args)?)
+            }
+            TransformerType::FakePostCode => Args::PostCode(parse(name, args)?),
+            TransformerType::FakeUsername => Args::Username(parse(name, args)?),
+            TransformerType::FakeUUID => Args::Uuid(parse(name, args)?),
+            TransformerType::Fixed => Args::Fixed(parse(name, args)?),
+            TransformerType::Pad => Args::Pad(parse(name, args)?),
+            TransformerType::Pipeline => {
+                let args: PipelineArgs = parse(name, args)?;
+                if args.steps.is_empty() {
+                    return Err("a pipeline needs at least one step".to_string());
+                }
+                Args::Pipeline(args.steps)
+            }
+            TransformerType::Prefix | TransformerType::Suffix => Args::Affix(parse(name, args)?),
+            TransformerType::ProbabilisticNull => Args::ProbabilisticNull(parse(name, args)?),
+            TransformerType::Redact => Args::Redact(parse(name, args)?),
+            TransformerType::RegexReplace => Args::RegexReplace(parse(name, args)?),
+            TransformerType::Replace => Args::Replace(parse(name, args)?),
+            TransformerType::Template => {
+                let args: TemplateArgs = parse(name, args)?;
+                let parts = template::parse(&args.template)?;
+                Args::Template(args, parts)
+            }
+            TransformerType::Truncate => Args::Truncate(parse(name, args)?),
+        };
+
+        Ok(ParsedTransformer {
+            name: name.clone(),
+            args: typed_args,
+            unique: parse(name, unique)?,
+            source: transformer.clone(),
+        })
+    }
+}
+
+/// Nested transformers (e.g. the steps of a pipeline) are written the same as any other
+impl JsonSchema for ParsedTransformer {
+    fn schema_name() -> Cow<'static, str> {
+        Transformer::schema_name()
+    }
+
+    fn schema_id() -> Cow<'static, str> {
+        Transformer::schema_id()
+    }
+
+    fn json_schema(generator: &mut SchemaGenerator) -> Schema {
+        Transformer::json_schema(generator)
+    }
+}
+
+/// The schema of the args of a transformer, from the typed args `ParsedTransformer::parse` reads
+/// them into
+pub fn args_schema(name: &TransformerType, generator: &mut SchemaGenerator) -> Schema {
+    let mut parts = match name {
+        TransformerType::Conditional => vec![ConditionalArgs::json_schema(generator)],
+        TransformerType::Dictionary => vec![DictionaryArgs::json_schema(generator)],
+        TransformerType::EmptyJson
+        | TransformerType::Error
+        | TransformerType::FakeBase16String
+        | TransformerType::FakeBase32String
+        | TransformerType::Identity
+        | TransformerType::Lowercase
+        | TransformerType::Null
+        | TransformerType::ObfuscateDay
+        | TransformerType::ObfuscateDateTime
+        | TransformerType::Scramble
+        | TransformerType::ScrambleBlank
+        | TransformerType::Uppercase => vec![NoArgs::json_schema(generator)],
+        TransformerType::FakeAccountNumber
+        | TransformerType::FakeIban
+        | TransformerType::FakeMacAddress
+        | TransformerType::FakeSortCode
+        | TransformerType::FakeUrl
+        | TransformerType::FakeUserAgent => vec![SeededArgs::json_schema(generator)],
+        TransformerType::FakeCardNumber => vec![CardNumberArgs::json_schema(generator)],
+        TransformerType::FakeCity
+        | TransformerType::FakeFullAddress
+        | TransformerType::FakeState
+        | TransformerType::FakeStreetAddress => vec![LocaleArgs::json_schema(generator)],
+        TransformerType::FakeCompanyName => vec![CompanyNameArgs::json_schema(generator)],
+        TransformerType::FakeEmail => vec![EmailArgs::json_schema(generator)],
+        TransformerType::FakeEmailOrPhone => vec![
+            EmailArgs::json_schema(generator),
+            PersonArgs::json_schema(generator),
+        ],
+        TransformerType::FakeFirstName
+        | TransformerType::FakeFullName
+        | TransformerType::FakeLastName
+        | TransformerType::FakePhoneNumber => vec![PersonArgs::json_schema(generator)],
+        TransformerType::FakeIPv4 | TransformerType::FakeIPv6 => {
+            vec![IpArgs::json_schema(generator)]
+        }
+        TransformerType::FakeNationalIdentityNumber => {
+            vec![NationalIdentityNumberArgs::json_schema(generator)]
+        }
+        TransformerType::FakePostCode => vec![PostCodeArgs::json_schema(generator)],
+        TransformerType::FakeUsername => vec![UsernameArgs::json_schema(generator)],
+        TransformerType::FakeUUID => vec![UuidArgs::json_schema(generator)],
+        TransformerType::Fixed => vec![FixedArgs::json_schema(generator)],
+        TransformerType::Pad => vec![PadArgs::json_schema(generator)],
+        TransformerType::Pipeline => vec![PipelineArgs::json_schema(generator)],
+        TransformerType::Prefix | TransformerType::Suffix => {
+            vec![AffixArgs::json_schema(generator)]
+        }
+        TransformerType::ProbabilisticNull => vec![ProbabilisticNullArgs::json_schema(generator)],
+        TransformerType::Redact => vec![RedactArgs::json_schema(generator)],
+        TransformerType::RegexReplace => vec![RegexReplaceArgs::json_schema(generator)],
+        TransformerType::Replace => vec![ReplaceArgs::json_schema(generator)],
+        TransformerType::Template => vec![TemplateArgs::json_schema(generator)],
+        TransformerType::Truncate => vec![TruncateArgs::json_schema(generator)],
+    };
+    if !pipeline::STRING_POST_PROCESSORS.contains(name) {
+        parts.push(UniqueArgs::json_schema(generator));
+    }
+
+    let mut properties = Map::new();
+    let mut required = Vec::new();
+    for part in parts {
+        if let Some(Value::Object(part_properties)) = part.get("properties") {
+            properties.extend(part_properties.clone());
+        }
+        if let Some(Value::Array(part_required)) = part.get("required") {
+            required.extend(part_required.iter().cloned());
+        }
+    }
+    let mut schema = json_schema!({
+        "type": ["object", "null"],
+        "properties": properties,
+        "additionalProperties": false
+    });
+    if !required.is_empty() {
+        schema.insert("required".to_string(), Value::Array(required));
+    }
+    schema
+}
+
+fn parse<T: DeserializeOwned>(name: &TransformerType, args: TransformerArgs) -> Result<T, String> {
+    let args = serde_json::to_value(args).unwrap();
+    serde_json::from_value(args).map_err(|e| format!("invalid args for {:?}: {}", name, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::{json, Value};
+
+    fn parsed(name: TransformerType, args: Value) -> Result<ParsedTransformer, String> {
+        ParsedTransformer::parse(&Transformer {
+            name,
+            args: serde_json::from_value(args).unwrap(),
+        })
+    }
+
+    #[test]
+    fn accepts_flags_and_numbers_as_strings() {
+        let email = parsed(
+            TransformerType::FakeEmail,
+            json!({"keep_domain": "true", "max_length": "30", "unique": false}),
+        )
+        .unwrap();
+        match email.args {
+            Args::FakeEmail(args) => {
+                assert!(args.keep_domain);
+                assert_eq!(args.max_length, Some(30));
+            }
+            other => panic!("{:?}", other),
+        }
+
+        let nulls = parsed(
+            TransformerType::ProbabilisticNull,
+            json!({"fraction": 0.5, "id_column": "id"}),
+        )
+        .unwrap();
+        assert_eq!(nulls.args.id_column(), Some("id"));
+
+        let truncate = parsed(TransformerType::Truncate, json!({"length": "4"}));
+        assert!(truncate.is_ok());
+    }
+
+    #[test]
+    fn rejects_unknown_args() {
+        let name = parsed(
+            TransformerType::FakeFirstName,
+            json!({"determinstic": "true", "id_column": "id"}),
+        );
+        assert_eq!(
+            name.unwrap_err(),
+            "invalid args for FakeFirstName: unknown field `determinstic`, expected one of `deterministic`, `id_column`, `locale`"
+        );
+
+        let identity = parsed(TransformerType::Identity, json!({"value": "x"}));
+        assert!(identity.unwrap_err().contains("unknown field `value`"));
+    }
+
+    #[test]
+    fn rejects_missing_and_mistyped_args() {
+        let fixed = parsed(TransformerType::Fixed, json!(null));
+        assert_eq!(
+            fixed.unwrap_err(),
+            "invalid args for Fixed: missing field `value`"
+        );
+
+        let uuid = parsed(TransformerType::FakeUUID, json!({"deterministic": "yes"}));
+        assert_eq!(
+            uuid.unwrap_err(),
+            "invalid args for FakeUUID: expected true or false, not 'yes'"
+        );
+
+        let locale = parsed(TransformerType::FakeCity, json!({"locale": "xx_XX"}));
+        assert!(locale.unwrap_err().contains("unknown locale 'xx_XX'"));
+    }
+
+    #[test]
+    fn only_post_processors_reject_the_unique_args() {
+        let scramble = parsed(
+            TransformerType::Scramble,
+            json!({"ensure_unique": true, "unique_group": "emails"}),
+        )
+        .unwrap();
+        assert!(scramble.unique.ensure_unique);
+        assert_eq!(scramble.unique.unique_group.as_deref(), Some("emails"));
+
+        let truncate = parsed(
+            TransformerType::Truncate,
+            json!({"length": 4, "ensure_unique": true}),
+        );
+        assert!(truncate
+            .unwrap_err()
+            .contains("unknown field `ensure_unique`"));
+    }
+
+    #[test]
+    fn parses_nested_transformers() {
+        let pipeline = parsed(
+            TransformerType::Pipeline,
+            json!({"steps": [{"name": "FakeEmail"}, {"name": "Truncate", "args": {"length": "x"}}]}),
+        );
+        assert_eq!(
+            pipeline.unwrap_err(),
+            "invalid args for Pipeline: invalid args for Truncate: expected a number, not 'x'"
+        );
+
+        let empty = parsed(TransformerType::Pipeline, json!({"steps": []}));
+        assert_eq!(empty.unwrap_err(), "a pipeline needs at least one step");
+    }
+}
@@ -0,0 +1,327 @@
+use crate::parsers::nulls;
+use crate::parsers::transformer_args::{self, ParsedTransformer};
+use schemars::JsonSchema;
+use serde::de::Error;
+use serde::{Deserialize, Deserializer};
+use serde_json::Value;
+use std::collections::hash_map::{DefaultHasher, Entry};
+use std::collections::{HashMap, HashSet};
+use std::hash::{Hash, Hasher};
+
+/// How many times a value is regenerated before giving up, which only happens if the transformer
+/// can't produce enough different values
+const MAX_ATTEMPTS: usize = 100;
+
+/// The most fingerprints kept at once by default. Each takes about 30-40 bytes, so this is about
+/// 2 GB
+pub const DEFAULT_MAX_VALUES: usize = 50_000_000;
+
+/// The fingerprints of the values already output for each unique column or group, along with the
+/// fingerprint of the original value they came from. Only 64 bit hashes are kept (rather than the
+/// values), which with the hash map's overhead is about 30-40 bytes per value, so 50 million rows
+/// of one unique column take roughly 2 GB. A column's values are dropped once its table has been
+/// written, and a group's once the last table using it has. Going over `max_values` fingerprints
+/// stops the run, rather than running out of memory part way through a large dump
+#[derive(Debug, PartialEq, Eq)]
+pub struct UniqueValues {
+    columns: HashMap<(String, String), HashMap<u64, u64>>,
+    groups: HashMap<String, HashMap<u64, u64>>,
+    /// The tables using each group which haven't been written yet
+    group_tables: HashMap<String, HashSet<String>>,
+    max_values: usize,
+    count: usize,
+}
+
+impl Default for UniqueValues {
+    fn default() -> Self {
+        UniqueValues::new(HashMap::new(), DEFAULT_MAX_VALUES)
+    }
+}
+
+/// The `ensure_unique` and `unique_group` args, which every transformer apart from the string post
+/// processors takes
+#[derive(Clone, Debug, Default, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct UniqueArgs {
+    /// Never output the same value twice in this column
+    #[serde(default, deserialize_with = "transformer_args::flag")]
+    #[schemars(schema_with = "transformer_args::flag_schema")]
+    pub ensure_unique: bool,
+    /// Columns with the same group share their values e.g. so that emails copied into another
+    /// table stay unique (and consistent) across both
+    #[serde(default, deserialize_with = "group")]
+    #[schemars(with = "String")]
+    pub unique_group: Option<String>,
+}
+
+fn group<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<String>, D::Error> {
+    match Value::deserialize(deserializer)? {
+        Value::String(group) if !group.is_empty() => Ok(Some(group)),
+        other => Err(D::Error::custom(format!(
+            "'unique_group' must be the name of a group, not {}",
+            other
+        ))),
+    }
+}
+
+/// Whether the output of a transformer has to be unique, with `ensure_unique` or `unique_group`
+pub fn is_unique(transformer: &ParsedTransformer) -> bool {
+    transformer.unique.ensure_unique || transformer.unique.unique_group.is_some()
+}
+
+impl UniqueValues {
+    /// `group_tables` are the tables which use each `unique_group`, from
+    /// `Strategies::unique_groups`
+    pub fn new(group_tables: HashMap<String, HashSet<String>>, max_values: usize) -> Self {
+        UniqueValues {
+            columns: HashMap::new(),
+            groups: HashMap::new(),
+            group_tables,
+            max_values,
+            count: 0,
+        }
+    }
+
+    /// Transforms `value` with `transform`, regenerating it with a different salt until it is one
+    /// that hasn't been output for this column (or group) before. The first attempt uses the
+    /// table's salt, so values are the same as without uniqueness unless they collide. The same
+    /// original value is allowed to give the same output, so deterministic transformers stay
+    /// consistent across the columns of a group
+    pub fn transform<F: FnMut(Option<&str>) -> String>(
+        &mut self,
+        table_name: &str,
+        column_name: &str,
+        transformer: &ParsedTransformer,
+        value: &str,
+        salt: Option<&str>,
+        mut transform: F,
+    ) -> String {
+        let seen = match &transformer.unique.unique_group {
+            Some(group) => self.groups.entry(group.to_string()).or_default(),
+            None => self
+                .columns
+                .entry((table_name.to_string(), column_name.to_string()))
+                .or_default(),
+        };
+        let original = fingerprint(value);
+
+        for attempt in 0..MAX_ATTEMPTS {
+            let new_value = if attempt == 0 {
+                transform(salt)
+            } else {
+                transform(Some(&format!("{}-unique-{}", salt.unwrap_or(""), attempt)))
+            };
+            // Unique indexes allow any number of nulls
+            if new_value == nulls::NULL {
+                return new_value;
+            }
+            match seen.entry(fingerprint(&new_value)) {
+                Entry::Occupied(seen_original) if *seen_original.get() != original => (),
+                Entry::Occupied(_) => return new_value,
+                Entry::Vacant(entry) => {
+                    if self.count >= self.max_values {
+                        panic!(
+                            "Over {} unique values are being kept for {}.{} and the other unique columns written so far. Raise --max-unique-values if there's memory for about 40 bytes per value",
+                            self.max_values, table_name, column_name
+                        )
+                    }
+                    entry.insert(original);
+                    self.count += 1;
+                    return new_value;
+                }
+            }
+        }
+
+        panic!(
+            "Couldn't generate a unique value for {}.{} after {} attempts, {:?} doesn't produce enough different values",
+            table_name, column_name, MAX_ATTEMPTS, transformer.name
+        )
+    }
+
+    /// Forgets the values of a table's columns once all its rows have been written, and those of
+    /// any group it was the last table to use
+    pub fn finish_table(&mut self, table_name: &str) {
+        let mut dropped = 0;
+        self.columns.retain(|(table, _), values| {
+            let keep = table != table_name;
+            if !keep {
+                dropped += values.len();
+            }
+            keep
+        });
+        for (group, tables) in self.group_tables.iter_mut() {
+            if tables.remove(table_name) && tables.is_empty() {
+                dropped += self.groups.remove(group).map_or(0, |values| values.len());
+            }
+        }
+        self.group_tables.retain(|_, tables| !tables.is_empty());
+        self.count -= dropped;
+    }
+}
+
+fn fingerprint(value: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    value.hash(&mut hasher);
+    hasher.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parsers::strategy_structs::{Transformer, TransformerType};
+    use std::collections::HashMap;
+
+    fn transformer(args: &[(&str, &str)]) -> ParsedTransformer {
+        ParsedTransformer::parse(&Transformer {
+            name: TransformerType::FakeFirstName,
+            args: Some(
+                args.iter()
+                    .map(|(key, value)| (key.to_string(), (*value).into()))
+                    .collect(),
+            ),
+        })
+        .unwrap()
+    }
+
+    /// A transformer with only a few different outputs, which depend on the salt
+    fn few_values(value: &str, salt: Option<&str>) -> String {
+        (fingerprint(&format!("{}{}", value, salt.unwrap_or(""))) % 20).to_string()
+    }
+
+    #[test]
+    fn regenerates_values_that_collide() {
+        let mut unique_values = UniqueValues::default();
+        let transformer = transformer(&[("ensure_unique", "true")]);
+        let outputs: Vec<String> = (0..20)
+            .map(|i| {
+                unique_values.transform(
+                    "public.users",
+                    "code",
+                    &transformer,
+                    &i.to_string(),
+                    None,
+                    |salt| few_values(&i.to_string(), salt),
+                )
+            })
+            .collect();
+        let mut distinct = outputs.clone();
+        distinct.sort();
+        distinct.dedup();
+        assert_eq!(distinct.len(), 20);
+    }
+
+    #[test]
+    fn values_that_do_not_collide_are_unchanged() {
+        let mut unique_values = UniqueValues::default();
+        let transformer = transformer(&[("ensure_unique", "true")]);
+        let value = unique_values.transform(
+            "public.users",
+            "code",
+            &transformer,
+            "a",
+            Some("salt"),
+            |salt| few_values("a", salt),
+        );
+        assert_eq!(value, few_values("a", Some("salt")));
+    }
+
+    #[test]
+    fn groups_are_shared_across_tables_and_consistent() {
+        let mut unique_values = UniqueValues::default();
+        let transformer = transformer(&[("unique_group", "emails")]);
+        let mut transform = |table_name: &str, value: &str| {
+            unique_values.transform("", table_name, &transformer, value, None, |salt| {
+                few_values(value, salt)
+            })
+        };
+        let in_users: Vec<String> = (0..10)
+            .map(|i| transform("users", &i.to_string()))
+            .collect();
+        let in_contacts: Vec<String> = (0..10)
+            .map(|i| transform("contacts", &i.to_string()))
+            .collect();
+        assert_eq!(in_users, in_contacts);
+        let other = transform("contacts", "10");
+        assert!(!in_users.contains(&other));
+    }
+
+    #[test]
+    fn nulls_are_not_unique() {
+        let mut unique_values = UniqueValues::default();
+        let transformer = transformer(&[("ensure_unique", "true")]);
+        for value in ["a", "b"] {
+            let new_value =
+                unique_values.transform("public.users", "code", &transformer, value, None, |_| {
+                    nulls::NULL.to_string()
+                });
+            assert_eq!(new_value, nulls::NULL);
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "Couldn't generate a unique value for public.users.code")]
+    fn gives_up_when_there_are_not_enough_values() {
+        let mut unique_values = UniqueValues::default();
+        let transformer = transformer(&[("ensure_unique", "true")]);
+        for value in ["a", "b"] {
+            unique_values.transform("public.users", "code", &transformer, value, None, |_| {
+                "same".to_string()
+            });
+        }
+    }
+
+    #[test]
+    fn finishing_a_table_forgets_its_columns() {
+        let mut unique_values = UniqueValues::default();
+        let transformer = transformer(&[("ensure_unique", "true")]);
+        unique_values.transform("public.users", "code", &transformer, "a", None, |_| {
+            "x".to_string()
+        });
+        unique_values.finish_table("public.users");
+        assert_eq!(unique_values, UniqueValues::default());
+    }
+
+    #[test]
+    fn groups_are_forgotten_after_the_last_table_using_them() {
+        let group_tables = HashMap::from([(
+            "emails".to_string(),
+            HashSet::from(["public.users".to_string(), "public.contacts".to_string()]),
+        )]);
+        let mut unique_values = UniqueValues::new(group_tables, DEFAULT_MAX_VALUES);
+        let transformer = transformer(&[("unique_group", "emails")]);
+        for table_name in ["public.users", "public.contacts"] {
+            unique_values.transform(table_name, "email", &transformer, "a", None, |_| {
+                "x".to_string()
+            });
+        }
+
+        unique_values.finish_table("public.users");
+        assert_eq!(unique_values.groups["emails"].len(), 1);
+        unique_values.finish_table("public.contacts");
+        assert_eq!(unique_values, UniqueValues::default());
+    }
+
+    #[test]
+    #[should_panic(expected = "Over 2 unique values are being kept for public.users.code")]
+    fn stops_when_there_are_more_values_than_the_max() {
+        let mut unique_values = UniqueValues::new(HashMap::new(), 2);
+        let transformer = transformer(&[("ensure_unique", "true")]);
+        for value in ["a", "b", "c"] {
+            unique_values.transform("public.users", "code", &transformer, value, None, |_| {
+                value.to_string()
+            });
+        }
+    }
+
+    #[test]
+    fn rejects_empty_groups() {
+        let transformer = Transformer {
+            name: TransformerType::FakeEmail,
+            args: Some(HashMap::from([("unique_group".to_string(), "".into())])),
+        };
+        assert_eq!(
+            ParsedTransformer::parse(&transformer).unwrap_err(),
+            "invalid args for FakeEmail: 'unique_group' must be the name of a group, not \"\""
+        );
+    }
+}
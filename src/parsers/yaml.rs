@@ -0,0 +1,327 @@
+use serde::Serialize;
+use serde_yaml_ng::{Mapping, Value};
+use std::collections::{HashMap, VecDeque};
+
+/// The keys which identify a table, column or include in the file being replaced, in order of
+/// preference
+const IDENTITY_KEYS: [&str; 3] = ["table_name", "name", "include"];
+
+/// Writes `entries` as YAML. If the file being replaced is given, its comments and key order are
+/// kept, with the comments of each table and column moving with it (found by its `table_name` or
+/// `name`) when it's re-sorted. YAML parsers drop comments, so they're put back above (or at the
+/// end of) the line with the same path of keys as the one they were on in the old file
+pub fn write<T: Serialize>(entries: &[T], replacing: Option<&str>) -> Result<String, String> {
+    let mut value = serde_yaml_ng::to_value(entries).map_err(|e| e.to_string())?;
+    let Some(old_text) = replacing else {
+        return serde_yaml_ng::to_string(&value).map_err(|e| e.to_string());
+    };
+    if let Ok(old) = serde_yaml_ng::from_str::<Value>(old_text) {
+        keep_key_order(&mut value, &old);
+    }
+    let text = serde_yaml_ng::to_string(&value).map_err(|e| e.to_string())?;
+    Ok(keep_comments(&text, old_text))
+}
+
+fn keep_key_order(value: &mut Value, old: &Value) {
+    match (value, old) {
+        (Value::Mapping(mapping), Value::Mapping(old_mapping)) => {
+            // Keys that are new to the file go after the ones it already had
+            let mut ordered: Mapping = old_mapping
+                .keys()
+                .filter_map(|key| Some((key.clone(), mapping.shift_remove(key)?)))
+                .collect();
+            ordered.extend(std::mem::take(mapping));
+            for (key, value) in ordered.iter_mut() {
+                if let Some(old_value) = old_mapping.get(key) {
+                    keep_key_order(value, old_value);
+                }
+            }
+            *mapping = ordered;
+        }
+        (Value::Sequence(items), Value::Sequence(old_items)) => {
+            for (index, item) in items.iter_mut().enumerate() {
+                let old_item = match identity(item) {
+                    Some(identity) => old_items
+                        .iter()
+                        .find(|old_item| self::identity(old_item) == Some(identity)),
+                    None => old_items.get(index),
+                };
+                if let Some(old_item) = old_item {
+                    keep_key_order(item, old_item);
+                }
+            }
+        }
+        _ => (),
+    }
+}
+
+fn identity(value: &Value) -> Option<&str> {
+    IDENTITY_KEYS
+        .iter()
+        .find_map(|key| value.get(key).and_then(Value::as_str))
+}
+
+/// A line of a YAML document with something other than a comment on it
+struct Line {
+    /// The keys (and list items, by their identity or position) leading to what is on the line
+    path: Vec<String>,
+    /// Where the first key or `-` on the line is
+    indent: usize,
+    /// The comments and blank lines above it
+    leading: Vec<String>,
+    /// A comment at the end of the line, with the whitespace before it
+    trailing: Option<String>,
+    /// The line without its comment, followed by any lines its value continues onto
+    text: Vec<String>,
+}
+
+fn keep_comments(text: &str, old_text: &str) -> String {
+    let (mut old_lines, old_end) = lines(old_text);
+    // The comments above the first line are the file's, so they stay at the top
+    let header = old_lines
+        .first_mut()
+        .map(|line| std::mem::take(&mut line.leading))
+        .unwrap_or_default();
+    let mut old_by_path: HashMap<&[String], VecDeque<&Line>> = HashMap::new();
+    for line in &old_lines {
+        old_by_path.entry(&line.path).or_default().push_back(line);
+    }
+
+    let mut output: Vec<String> = header;
+    for line in lines(text).0 {
+        let old = old_by_path
+            .get_mut(line.path.as_slice())
+            .and_then(VecDeque::pop_front);
+        let mut text = line.text.into_iter();
+        match old {
+            Some(old) => {
+                output.extend(
+                    old.leading
+                        .iter()
+                        .map(|comment| reindent(comment, old.indent, line.indent)),
+                );
+                let first = text.next().unwrap_or_default();
+                output.push(first + old.trailing.as_deref().unwrap_or_default());
+            }
+            None => output.extend(text.next()),
+        }
+        output.extend(text);
+    }
+    output.extend(old_end);
+
+    let mut written = output.join("\n");
+    written.push('\n');
+    written
+}
+
+/// Moves a comment by as much as the line it is above has moved
+fn reindent(comment: &str, old_indent: usize, new_indent: usize) -> String {
+    let trimmed = comment.trim_start();
+    if trimmed.is_empty() {
+        return String::new();
+    }
+    let indent = (comment.len() - trimmed.len() + new_indent).saturating_sub(old_indent);
+    format!("{}{}", " ".repeat(indent), trimmed)
+}
+
+/// A key or list item that lines are nested in
+struct Frame {
+    column: usize,
+    /// The key, or `None` for a list item
+    key: Option<String>,
+    /// The position of a list item in its list
+    index: usize,
+    /// The list items directly in it so far
+    items: usize,
+}
+
+/// Splits a block style YAML document into the lines with content on them, and the comments and
+/// blank lines after the last of them
+fn lines(text: &str) -> (Vec<Line>, Vec<String>) {
+    let mut frames: Vec<Frame> = Vec::new();
+    let mut stack: Vec<usize> = Vec::new();
+    let mut root_items = 0;
+    let mut paths: Vec<Vec<usize>> = Vec::new();
+    let mut identities: HashMap<usize, (usize, String)> = HashMap::new();
+
+    let mut lines: Vec<Line> = Vec::new();
+    let mut pending: Vec<String> = Vec::new();
+    // Lines indented further than this continue the value of the line before
+    let mut continuation: Option<usize> = None;
+
+    for raw in text.lines() {
+        let content = raw.trim_start();
+        let indent = raw.len() - content.len();
+        if let (Some(column), Some(line)) = (continuation, lines.last_mut()) {
+            if content.is_empty() {
+                pending.push(raw.to_string());
+                continue;
+            }
+            if indent > column {
+                line.text.append(&mut pending);
+                line.text.push(raw.to_string());
+                continue;
+            }
+        }
+        continuation = None;
+        if content.is_empty() || content.starts_with('#') || content.starts_with("---") {
+            pending.push(raw.to_string());
+            continue;
+        }
+
+        let (content, trailing) = split_comment(content);
+        let mut column = indent;
+        let mut rest = content;
+        let mut item_column = None;
+        while rest == "-" || rest.starts_with("- ") {
+            while stack.last().is_some_and(|&top| {
+                let frame = &frames[top];
+                frame.column > column || (frame.key.is_none() && frame.column == column)
+            }) {
+                stack.pop();
+            }
+            let items = match stack.last() {
+                Some(&top) => &mut frames[top].items,
+                None => &mut root_items,
+            };
+            let index = *items;
+            *items += 1;
+            frames.push(Frame {
+                column,
+                key: None,
+                index,
+                items: 0,
+            });
+            stack.push(frames.len() - 1);
+
+            item_column = Some(column);
+            let after = rest[1..].trim_start();
+            column += rest.len() - after.len();
+            rest = after;
+        }
+        if let Some((key, value)) = split_key(rest) {
+            while stack
+                .last()
+                .is_some_and(|&top| frames[top].column >= column)
+            {
+                stack.pop();
+            }
+            let parent = stack.last().copied();
+            if let (Some(parent), Some(preference)) = (
+                parent.filter(|&parent| frames[parent].key.is_none()),
+                IDENTITY_KEYS.iter().position(|identity| *identity == key),
+            ) {
+                if !value.is_empty()
+                    && identities
+                        .get(&parent)
+                        .map_or(true, |(existing, _)| preference < *existing)
+                {
+                    identities.insert(parent, (preference, unquote(value).to_string()));
+                }
+            }
+            frames.push(Frame {
+                column,
+                key: Some(unquote(key).to_string()),
+                index: 0,
+                items: 0,
+            });
+            stack.push(frames.len() - 1);
+            continuation = (!value.is_empty()).then_some(column);
+        } else if !rest.is_empty() {
+            continuation = item_column;
+        }
+
+        paths.push(stack.clone());
+        lines.push(Line {
+            path: Vec::new(),
+            indent,
+            leading: std::mem::take(&mut pending),
+            trailing,
+            text: vec![raw[..indent + content.len()].to_string()],
+        });
+    }
+
+    for (line, path) in lines.iter_mut().zip(paths) {
+        line.path = path
+            .into_iter()
+            .map(|id| match (&frames[id].key, identities.get(&id)) {
+                (Some(key), _) => key.clone(),
+                (None, Some((_, identity))) => format!("[{}]", identity),
+                (None, None) => format!("[{}]", frames[id].index),
+            })
+            .collect();
+    }
+    (lines, pending)
+}
+
+/// Splits the comment (with the whitespace before it) off the end of a line, ignoring `#`s in
+/// quoted keys and values
+fn split_comment(content: &str) -> (&str, Option<String>) {
+    let mut quote: Option<char> = None;
+    let mut escaped = false;
+    let mut previous: Option<char> = None;
+    let mut significant: Option<char> = None;
+    for (index, c) in content.char_indices() {
+        match quote {
+            Some('"') if escaped => escaped = false,
+            Some('"') if c == '\\' => escaped = true,
+            Some(open) if c == open => quote = None,
+            Some(_) => (),
+            None if c == '#' && previous.map_or(true, char::is_whitespace) => {
+                let value = content[..index].trim_end();
+                return (value, Some(content[value.len()..].to_string()));
+            }
+            // Quotes only start a quoted key or value, not part way through a plain one
+            None if matches!(c, '"' | '\'') => {
+                let starts_value = match significant {
+                    None | Some('[' | '{' | ',') => true,
+                    Some(':' | '-') => previous.is_some_and(char::is_whitespace),
+                    _ => false,
+                };
+                if starts_value {
+                    quote = Some(c);
+                }
+            }
+            None => (),
+        }
+        if !c.is_whitespace() {
+            significant = Some(c);
+        }
+        previous = Some(c);
+    }
+    (content, None)
+}
+
+/// The key and value of a `key: value` (or `key:`) line, without a leading `- `
+fn split_key(content: &str) -> Option<(&str, &str)> {
+    if content.starts_with(['[', '{', '|', '>', '!', '&', '*']) {
+        return None;
+    }
+    let key_end = match content.chars().next() {
+        Some(quote @ ('"' | '\'')) => content[1..].find(quote)? + 2,
+        _ => 0,
+    };
+    let colon = key_end
+        + content[key_end..]
+            .match_indices(':')
+            .map(|(index, _)| index)
+            .find(|&index| {
+                content[key_end + index + 1..]
+                    .chars()
+                    .next()
+                    .map_or(true, char::is_whitespace)
+            })?;
+    Some((content[..colon].trim_end(), content[colon + 1..].trim()))
+}
+
+fn unquote(value: &str) -> &str {
+    for quote in ['"', '\''] {
+        if let Some(unquoted) = value
+            .strip_prefix(quote)
+            .and_then(|value| value.strip_suffix(quote))
+        {
+            return unquoted;
+        }
+    }
+    value
+}
@@ -0,0 +1,228 @@
+use log::info;
+use std::io::{IsTerminal, Write};
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+
+const BAR_INTERVAL: Duration = Duration::from_millis(250);
+const LOG_INTERVAL: Duration = Duration::from_secs(30);
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ProgressMode {
+    /// A single line on stderr, redrawn in place
+    Bar,
+    /// A log event every 30 seconds, for CI logs where a redrawing bar is just noise
+    Log,
+}
+type ParseError = &'static str;
+
+impl FromStr for ProgressMode {
+    type Err = ParseError;
+    fn from_str(progress_mode: &str) -> Result<Self, Self::Err> {
+        match progress_mode {
+            "bar" => Ok(ProgressMode::Bar),
+            "log" => Ok(ProgressMode::Log),
+            _ => Err("Could not parse progress mode"),
+        }
+    }
+}
+
+impl ProgressMode {
+    /// A bar when stderr is a terminal someone is watching, otherwise log lines
+    pub fn for_stderr() -> Self {
+        if std::io::stderr().is_terminal() {
+            ProgressMode::Bar
+        } else {
+            ProgressMode::Log
+        }
+    }
+}
+
+/// How far through the input file a run is, for showing on stderr
+pub struct Progress {
+    mode: ProgressMode,
+    total_bytes: u64,
+    started: Instant,
+    last_shown: Instant,
+    current_table: Option<String>,
+    rows: u64,
+}
+
+impl Progress {
+    pub fn new(mode: ProgressMode, total_bytes: u64) -> Self {
+        let now = Instant::now();
+        Progress {
+            mode,
+            total_bytes,
+            started: now,
+            last_shown: now,
+            current_table: None,
+            rows: 0,
+        }
+    }
+
+    pub fn start_table(&mut self, table_name: &str) {
+        self.current_table = Some(table_name.to_string());
+    }
+
+    pub fn finish_table(&mut self) {
+        self.current_table = None;
+    }
+
+    pub fn add_row(&mut self) {
+        self.rows += 1;
+    }
+
+    /// Shows the progress if it's been long enough since it was last shown
+    pub fn update(&mut self, bytes_read: u64) {
+        let interval = match self.mode {
+            ProgressMode::Bar => BAR_INTERVAL,
+            ProgressMode::Log => LOG_INTERVAL,
+        };
+        if self.last_shown.elapsed() < interval {
+            return;
+        }
+        self.last_shown = Instant::now();
+
+        let elapsed = self.started.elapsed();
+        let line = self.line(bytes_read, elapsed);
+        match self.mode {
+            ProgressMode::Bar => {
+                let _ = write!(std::io::stderr().lock(), "\r{}\x1b[K", line);
+            }
+            ProgressMode::Log => info!(
+                table = self.current_table.as_deref().unwrap_or("-"),
+                bytes_read = bytes_read,
+                total_bytes = self.total_bytes,
+                rows = self.rows,
+                rows_per_second = per_second(self.rows, elapsed);
+                "{}",
+                line
+            ),
+        }
+    }
+
+    pub fn finish(&mut self, bytes_read: u64) {
+        let elapsed = self.started.elapsed();
+        let summary = format!(
+            "Anonymised {} in {}, {} rows at {} rows/s",
+            format_bytes(bytes_read),
+            format_duration(elapsed),
+            self.rows,
+            per_second(self.rows, elapsed),
+        );
+        match self.mode {
+            ProgressMode::Bar => eprintln!("\r{}\x1b[K", summary),
+            ProgressMode::Log => info!(
+                bytes_read = bytes_read,
+                rows = self.rows,
+                rows_per_second = per_second(self.rows, elapsed),
+                duration_seconds = elapsed.as_secs_f64();
+                "{}",
+                summary
+            ),
+        }
+    }
+
+    fn line(&self, bytes_read: u64, elapsed: Duration) -> String {
+        let fraction = if self.total_bytes == 0 {
+            0.0
+        } else {
+            (bytes_read as f64 / self.total_bytes as f64).min(1.0)
+        };
+        let eta = if bytes_read == 0 {
+            "unknown".to_string()
+        } else {
+            let remaining = self.total_bytes.saturating_sub(bytes_read);
+            format_duration(elapsed.mul_f64(remaining as f64 / bytes_read as f64))
+        };
+        format!(
+            "{:5.1}% {}/{} {} {} rows/s ETA {}",
+            fraction * 100.0,
+            format_bytes(bytes_read),
+            format_bytes(self.total_bytes),
+            self.current_table.as_deref().unwrap_or("-"),
+            per_second(self.rows, elapsed),
+            eta,
+        )
+    }
+}
+
+fn per_second(count: u64, elapsed: Duration) -> u64 {
+    let seconds = elapsed.as_secs_f64();
+    if seconds == 0.0 {
+        0
+    } else {
+        (count as f64 / seconds).round() as u64
+    }
+}
+
+fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KiB", "MiB", "GiB", "TiB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} {}", bytes, UNITS[0])
+    } else {
+        format!("{:.1} {}", value, UNITS[unit])
+    }
+}
+
+fn format_duration(duration: Duration) -> String {
+    let seconds = duration.as_secs();
+    match (seconds / 3600, seconds % 3600 / 60, seconds % 60) {
+        (0, 0, s) => format!("{}s", s),
+        (0, m, s) => format!("{}m {:02}s", m, s),
+        (h, m, s) => format!("{}h {:02}m {:02}s", h, m, s),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shows_how_far_through_the_input_it_is() {
+        let mut progress = Progress::new(ProgressMode::Log, 4 * 1024 * 1024 * 1024);
+        progress.start_table("public.users");
+        for _ in 0..1000 {
+            progress.add_row();
+        }
+
+        assert_eq!(
+            progress.line(1024 * 1024 * 1024, Duration::from_secs(100)),
+            " 25.0% 1.0 GiB/4.0 GiB public.users 10 rows/s ETA 5m 00s"
+        );
+    }
+
+    #[test]
+    fn has_no_eta_before_anything_is_read() {
+        let progress = Progress::new(ProgressMode::Bar, 0);
+        assert_eq!(
+            progress.line(0, Duration::ZERO),
+            "  0.0% 0 B/0 B - 0 rows/s ETA unknown"
+        );
+    }
+
+    #[test]
+    fn formats_bytes_and_durations() {
+        assert_eq!(format_bytes(512), "512 B");
+        assert_eq!(format_bytes(1536), "1.5 KiB");
+        assert_eq!(format_bytes(200 * 1024 * 1024 * 1024), "200.0 GiB");
+        assert_eq!(format_duration(Duration::from_secs(59)), "59s");
+        assert_eq!(
+            format_duration(Duration::from_secs(3 * 3600 + 62)),
+            "3h 01m 02s"
+        );
+    }
+
+    #[test]
+    fn parses_progress_modes() {
+        assert_eq!(ProgressMode::from_str("bar"), Ok(ProgressMode::Bar));
+        assert_eq!(ProgressMode::from_str("log"), Ok(ProgressMode::Log));
+        assert!(ProgressMode::from_str("fancy").is_err());
+    }
+}
@@ -0,0 +1,247 @@
+use crate::parsers::copy_row::{CurrentTableTransforms, TableTransformers};
+use crate::parsers::data_row;
+use crate::parsers::strategy_file;
+use crate::parsers::strategy_structs::Transformer;
+use chrono::{SecondsFormat, Utc};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::fs;
+use std::time::{Duration, Instant};
+
+/// What an `anonymise` run did, written as JSON next to the output with `--report-file` so that
+/// snapshots can be audited and alerted on
+#[derive(Debug, Serialize)]
+pub struct RunReport {
+    pub input_file: String,
+    pub output_file: String,
+    pub strategy_file: String,
+    /// A sha256 of the strategy file, or of all of its files in order when it is split up
+    pub strategy_file_sha256: String,
+    pub started_at: String,
+    pub finished_at: String,
+    pub duration_seconds: f64,
+    /// The size of the input file as it is on disk, i.e. before it's uncompressed
+    pub input_bytes: u64,
+    /// The size of the output file, after any compression
+    pub output_bytes: u64,
+    pub tables: BTreeMap<String, TableReport>,
+
+    #[serde(skip)]
+    started: Instant,
+    #[serde(skip)]
+    current_table: Option<(String, Instant)>,
+}
+
+#[derive(Debug, Default, PartialEq, Serialize)]
+pub struct TableReport {
+    pub truncated: bool,
+    /// The number of rows in the input, truncated tables have none in the output
+    pub rows: u64,
+    pub duration_seconds: f64,
+    pub columns: BTreeMap<String, ColumnReport>,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub struct ColumnReport {
+    pub transformer: Transformer,
+    /// Nulls in the output, including the ones written by `Null` and `ProbabilisticNull`
+    pub nulls: u64,
+    /// Values passed through without being transformed, which are nulls and `deleted`
+    pub skipped: u64,
+}
+
+impl RunReport {
+    pub fn new(input_file: &str, output_file: &str, strategy_file: &str) -> Self {
+        RunReport {
+            input_file: input_file.to_string(),
+            output_file: output_file.to_string(),
+            strategy_file: strategy_file.to_string(),
+            strategy_file_sha256: strategy_file_sha256(strategy_file),
+            started_at: now(),
+            finished_at: "".to_string(),
+            duration_seconds: 0.0,
+            input_bytes: 0,
+            output_bytes: 0,
+            tables: BTreeMap::new(),
+            started: Instant::now(),
+            current_table: None,
+        }
+    }
+
+    pub fn start_table(&mut self, current_table: &CurrentTableTransforms) {
+        let table = self
+            .tables
+            .entry(current_table.table_name.clone())
+            .or_default();
+        match current_table.table_transformers {
+            TableTransformers::ColumnTransformer(ref columns) => {
+                for column in columns {
+                    table
+                        .columns
+                        .entry(column.name.clone())
+                        .or_insert_with(|| ColumnReport {
+                            transformer: column.transformer.source.clone(),
+                            nulls: 0,
+                            skipped: 0,
+                        });
+                }
+            }
+            TableTransformers::Truncator => table.truncated = true,
+        }
+        self.current_table = Some((current_table.table_name.clone(), Instant::now()));
+    }
+
+    /// Counts a row of the table being read, the values in it which are skipped, and the nulls in
+    /// what it was transformed into
+    pub fn add_row(
+        &mut self,
+        current_table: &CurrentTableTransforms,
+        line: &str,
+        transformed: &str,
+    ) {
+        let table = self
+            .tables
+            .entry(current_table.table_name.clone())
+            .or_default();
+        table.rows += 1;
+
+        if let TableTransformers::ColumnTransformer(ref columns) = current_table.table_transformers
+        {
+            let values = data_row::split(line).zip(data_row::split(transformed));
+            for (column, (value, transformed)) in columns.iter().zip(values) {
+                if let Some(column_report) = table.columns.get_mut(&column.name) {
+                    column_report.nulls += u64::from(transformed == "\\N");
+                    column_report.skipped += u64::from(value == "\\N" || value == "deleted");
+                }
+            }
+        }
+    }
+
+    pub fn finish_table(&mut self) {
+        if let Some((table_name, started)) = self.current_table.take() {
+            if let Some(table) = self.tables.get_mut(&table_name) {
+                table.duration_seconds += seconds(started.elapsed());
+            }
+        }
+    }
+
+    pub fn finish(&mut self, input_bytes: u64, output_bytes: u64) {
+        self.finish_table();
+        self.input_bytes = input_bytes;
+        self.output_bytes = output_bytes;
+        self.finished_at = now();
+        self.duration_seconds = seconds(self.started.elapsed());
+    }
+
+    pub fn write(&self, file_name: &str) -> std::io::Result<()> {
+        fs::write(file_name, serde_json::to_string_pretty(self)? + "\n")
+    }
+}
+
+fn strategy_file_sha256(strategy_file: &str) -> String {
+    let mut hasher = Sha256::new();
+    for file_name in strategy_file::files(strategy_file) {
+        if let Ok(contents) = fs::read(&file_name) {
+            hasher.update(contents);
+        }
+    }
+    format!("{:x}", hasher.finalize())
+}
+
+fn now() -> String {
+    Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true)
+}
+
+fn seconds(duration: Duration) -> f64 {
+    (duration.as_secs_f64() * 1000.0).round() / 1000.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parsers::strategy_structs::{ColumnInfo, TransformerType};
+
+    fn users() -> CurrentTableTransforms {
+        CurrentTableTransforms::new(
+            "public.users".to_string(),
+            TableTransformers::ColumnTransformer(vec![
+                ColumnInfo::builder().with_name("id").build(),
+                ColumnInfo::builder()
+                    .with_name("email")
+                    .with_transformer(TransformerType::FakeEmail, None)
+                    .build(),
+            ]),
+            None,
+        )
+    }
+
+    fn column_report(transformer: TransformerType, nulls: u64, skipped: u64) -> ColumnReport {
+        ColumnReport {
+            transformer: Transformer {
+                name: transformer,
+                args: None,
+            },
+            nulls,
+            skipped,
+        }
+    }
+
+    #[test]
+    fn counts_rows_nulls_and_skipped_values() {
+        let mut report = RunReport::new("in.sql", "out.sql", "test_files/strategy.json");
+        report.start_table(&users());
+        report.add_row(&users(), "1\tjane@example.com\n", "1\tx1@example.test\n");
+        report.add_row(&users(), "2\t\\N\n", "2\t\\N\n");
+        report.add_row(&users(), "3\tdeleted\n", "3\tdeleted\n");
+        report.add_row(&users(), "4\tjohn@example.com\n", "4\t\\N\n");
+        report.finish(100, 50);
+
+        let table = &report.tables["public.users"];
+        assert!(!table.truncated);
+        assert_eq!(table.rows, 4);
+        assert_eq!(
+            table.columns,
+            BTreeMap::from([
+                (
+                    "email".to_string(),
+                    column_report(TransformerType::FakeEmail, 2, 2)
+                ),
+                (
+                    "id".to_string(),
+                    column_report(TransformerType::Identity, 0, 0)
+                ),
+            ])
+        );
+        assert_eq!((report.input_bytes, report.output_bytes), (100, 50));
+        assert!(report.finished_at >= report.started_at);
+    }
+
+    #[test]
+    fn marks_truncated_tables() {
+        let truncated = CurrentTableTransforms::new(
+            "public.audit_log".to_string(),
+            TableTransformers::Truncator,
+            None,
+        );
+        let mut report = RunReport::new("in.sql", "out.sql", "test_files/strategy.json");
+        report.start_table(&truncated);
+        report.add_row(&truncated, "1\tsomething happened\n", "");
+        report.finish_table();
+
+        let table = &report.tables["public.audit_log"];
+        assert!(table.truncated);
+        assert_eq!(table.rows, 1);
+        assert!(table.columns.is_empty());
+    }
+
+    #[test]
+    fn hashes_the_strategy_file() {
+        let report = RunReport::new("in.sql", "out.sql", "test_files/strategy.json");
+        let contents = fs::read("test_files/strategy.json").unwrap();
+        assert_eq!(
+            report.strategy_file_sha256,
+            format!("{:x}", Sha256::digest(contents))
+        );
+    }
+}
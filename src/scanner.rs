@@ -0,0 +1,523 @@
+use crate::parsers::column_rules::AUTO_CLASSIFIED;
+use crate::parsers::pii_patterns;
+use crate::parsers::strategy_structs::{
+    DataCategory, SimpleColumn, StrategyInFile, Transformer, TransformerType,
+};
+use fake::locales::{Data, EN};
+use itertools::Itertools;
+use lazy_static::lazy_static;
+use postgres::GenericClient;
+use serde_json::json;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+/// The Postgres types whose values are sampled, other types can't hold free text
+const TEXT_TYPES: [&str; 5] = ["text", "character varying", "character", "json", "jsonb"];
+
+/// What the sampled values are checked for, the built in patterns in the order they're preferred
+/// when a column matches more than one equally (e.g. a card number also looks like a phone number)
+const PATTERN_DETECTORS: [&str; 5] = [
+    "email",
+    "card_number",
+    "uk_national_insurance_number",
+    "phone_number",
+    "uk_postcode",
+];
+const PERSON_NAME: &str = "person_name";
+
+/// The fraction of values a detector has to match for the column to be treated as holding just
+/// that kind of value (and given its fake transformer) rather than free text with PII in it
+const MOSTLY: f64 = 0.8;
+
+lazy_static! {
+    static ref FIRST_NAMES: HashSet<String> = <EN as Data>::NAME_FIRST_NAME
+        .iter()
+        .map(|name| name.to_lowercase())
+        .collect();
+    static ref LAST_NAMES: HashSet<String> = <EN as Data>::NAME_LAST_NAME
+        .iter()
+        .map(|name| name.to_lowercase())
+        .collect();
+}
+
+/// The values sampled from a column, and how many of them each detector matched
+#[derive(Debug, PartialEq)]
+pub struct ColumnScan {
+    pub column: SimpleColumn,
+    pub sampled: usize,
+    pub detections: Vec<(&'static str, usize)>,
+}
+
+impl ColumnScan {
+    fn fraction(&self, matches: usize) -> f64 {
+        matches as f64 / self.sampled as f64
+    }
+}
+
+/// A column which looks like it holds PII, but isn't classified as holding any
+#[derive(Debug)]
+pub struct Finding {
+    pub scan: ColumnScan,
+    /// The data category in the strategy file, if the column is in it
+    pub data_category: Option<DataCategory>,
+    pub suggested_category: DataCategory,
+    pub suggested_transformer: Transformer,
+}
+
+impl fmt::Display for Finding {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let category = self
+            .data_category
+            .clone()
+            .map_or("Not in the strategy file".to_string(), String::from);
+        write!(
+            f,
+            "{} => {}: {}, but {} (suggest {} with {:?})",
+            self.scan.column.table_name,
+            self.scan.column.column_name,
+            category,
+            self.summary(),
+            String::from(self.suggested_category.clone()),
+            self.suggested_transformer.name,
+        )
+    }
+}
+
+impl Finding {
+    fn summary(&self) -> String {
+        let detections = self
+            .scan
+            .detections
+            .iter()
+            .map(|(detector, matches)| {
+                format!(
+                    "{:.0}% look like {}",
+                    self.scan.fraction(*matches) * 100.0,
+                    detector
+                )
+            })
+            .join(", ");
+        format!("of {} sampled values {}", self.scan.sampled, detections)
+    }
+}
+
+/// Samples up to `sample_size` values from every text and json column, and counts the values each
+/// detector matches
+pub fn scan<T: GenericClient>(
+    connection: &mut T,
+    column_types: &HashMap<SimpleColumn, String>,
+    sample_size: usize,
+) -> Vec<ColumnScan> {
+    column_types
+        .iter()
+        .filter(|(_, column_type)| TEXT_TYPES.contains(&column_type.as_str()))
+        .map(|(column, _)| column)
+        .sorted()
+        .map(|column| {
+            let values = sample(connection, column, sample_size);
+            scan_values(column.clone(), &values)
+        })
+        .collect()
+}
+
+/// A random sample of the column's values rather than the first rows, which would all be from
+/// the start of the table (e.g. the oldest rows)
+fn sample<T: GenericClient>(
+    connection: &mut T,
+    column: &SimpleColumn,
+    sample_size: usize,
+) -> Vec<String> {
+    let (schema, table) = column
+        .table_name
+        .split_once('.')
+        .unwrap_or(("public", &column.table_name));
+    let query = format!(
+        "SELECT {column}::text FROM {schema}.{table} WHERE {column} IS NOT NULL \
+         ORDER BY random() LIMIT {limit}",
+        column = quote(&column.column_name),
+        schema = quote(schema),
+        table = quote(table),
+        limit = sample_size,
+    );
+    connection
+        .query(query.as_str(), &[])
+        .unwrap_or_else(|e| {
+            panic!(
+                "Unable to sample {} => {}: {}",
+                column.table_name, column.column_name, e
+            )
+        })
+        .iter()
+        .map(|row| row.get(0))
+        .collect()
+}
+
+fn quote(identifier: &str) -> String {
+    format!("\"{}\"", identifier.replace('"', "\"\""))
+}
+
+pub fn scan_values(column: SimpleColumn, values: &[String]) -> ColumnScan {
+    let detections = PATTERN_DETECTORS
+        .iter()
+        .map(|detector| {
+            let pattern = pii_patterns::builtin(detector).unwrap();
+            let matches = values
+                .iter()
+                .filter(|value| pattern.find_iter(value).next().is_some())
+                .count();
+            (*detector, matches)
+        })
+        .chain([(
+            PERSON_NAME,
+            values.iter().filter(|value| has_person_name(value)).count(),
+        )])
+        .filter(|(_, matches)| *matches > 0)
+        .collect();
+
+    ColumnScan {
+        column,
+        sampled: values.len(),
+        detections,
+    }
+}
+
+/// Whether the value is a known first or last name on its own, or has a known first name followed
+/// by a known last name e.g. "... spoke to Oliver Smith about ..."
+fn has_person_name(value: &str) -> bool {
+    let words: Vec<String> = value
+        .split(|c: char| !c.is_alphabetic() && c != '\'' && c != '-')
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect();
+    match words.as_slice() {
+        [word] => FIRST_NAMES.contains(word) || LAST_NAMES.contains(word),
+        _ => words
+            .windows(2)
+            .any(|pair| FIRST_NAMES.contains(&pair[0]) && LAST_NAMES.contains(&pair[1])),
+    }
+}
+
+/// The columns where at least `threshold` of the sampled values look like PII, but which the
+/// strategy file doesn't classify as `Pii`, `PotentialPii` or `Security`
+pub fn findings(
+    scans: Vec<ColumnScan>,
+    strategies: &[StrategyInFile],
+    threshold: f64,
+) -> Vec<Finding> {
+    let categories: HashMap<SimpleColumn, &DataCategory> = strategies
+        .iter()
+        .flat_map(|strategy| {
+            strategy.columns.iter().map(|column| {
+                (
+                    SimpleColumn {
+                        table_name: strategy.table_name.clone(),
+                        column_name: column.name.clone(),
+                    },
+                    &column.data_category,
+                )
+            })
+        })
+        .collect();
+
+    scans
+        .into_iter()
+        .filter_map(|mut scan| {
+            let data_category = categories.get(&scan.column).cloned().cloned();
+            if matches!(
+                data_category,
+                Some(DataCategory::Pii | DataCategory::PotentialPii | DataCategory::Security)
+            ) || scan.sampled == 0
+            {
+                return None;
+            }
+            let sampled = scan.sampled as f64;
+            scan.detections
+                .retain(|(_, matches)| *matches as f64 / sampled >= threshold);
+            let name_columns = name_columns(strategies, &scan.column.table_name);
+            let (suggested_category, suggested_transformer) = suggestion(&scan, &name_columns)?;
+            Some(Finding {
+                scan,
+                data_category,
+                suggested_category,
+                suggested_transformer,
+            })
+        })
+        .collect()
+}
+
+/// The columns of the table given a fake name transformer, whose values `Redact` can look for
+fn name_columns(strategies: &[StrategyInFile], table_name: &str) -> Vec<String> {
+    strategies
+        .iter()
+        .filter(|strategy| strategy.table_name == table_name)
+        .flat_map(|strategy| strategy.columns.iter())
+        .filter(|column| {
+            matches!(
+                column.transformer.name,
+                TransformerType::FakeFirstName
+                    | TransformerType::FakeLastName
+                    | TransformerType::FakeFullName
+            )
+        })
+        .map(|column| column.name.clone())
+        .collect()
+}
+
+/// A fake transformer when the values are mostly one kind of PII, otherwise `Redact` for free text
+/// with PII in it. Redact's detectors don't find names, so names are looked for in the
+/// `name_columns`, or the text is scrambled if the table has none
+fn suggestion(scan: &ColumnScan, name_columns: &[String]) -> Option<(DataCategory, Transformer)> {
+    let (detector, matches) = scan
+        .detections
+        .iter()
+        .rev()
+        .max_by_key(|(_, matches)| *matches)?;
+    if scan.fraction(*matches) >= MOSTLY {
+        let name = match *detector {
+            "email" => TransformerType::FakeEmail,
+            "card_number" => TransformerType::FakeCardNumber,
+            "uk_national_insurance_number" => TransformerType::FakeNationalIdentityNumber,
+            "phone_number" => TransformerType::FakePhoneNumber,
+            "uk_postcode" => TransformerType::FakePostCode,
+            _ => TransformerType::FakeFullName,
+        };
+        return Some((DataCategory::Pii, Transformer { name, args: None }));
+    }
+
+    let (names, detectors): (Vec<&str>, Vec<&str>) = scan
+        .detections
+        .iter()
+        .map(|(detector, _)| *detector)
+        .partition(|detector| *detector == PERSON_NAME);
+    if !names.is_empty() && name_columns.is_empty() {
+        return Some((
+            DataCategory::PotentialPii,
+            Transformer {
+                name: TransformerType::Scramble,
+                args: None,
+            },
+        ));
+    }
+    let mut args = HashMap::new();
+    if !detectors.is_empty() {
+        args.insert("detectors".to_string(), json!(detectors));
+    }
+    if !names.is_empty() {
+        args.insert("name_columns".to_string(), json!(name_columns));
+    }
+    let args = (!args.is_empty()).then_some(args);
+    Some((
+        DataCategory::PotentialPii,
+        Transformer {
+            name: TransformerType::Redact,
+            args,
+        },
+    ))
+}
+
+/// Sets the suggested data category and transformer of every finding that is in the strategy
+/// file, adding a note to their description that they were auto-classified so they get reviewed
+pub fn apply(mut strategies: Vec<StrategyInFile>, findings: &[Finding]) -> Vec<StrategyInFile> {
+    for finding in findings {
+        let column = strategies
+            .iter_mut()
+            .filter(|strategy| strategy.table_name == finding.scan.column.table_name)
+            .flat_map(|strategy| strategy.columns.iter_mut())
+            .find(|column| column.name == finding.scan.column.column_name);
+        if let Some(column) = column {
+            column.data_category = finding.suggested_category.clone();
+            column.transformer = finding.suggested_transformer.clone();
+            let note = format!(
+                "{} by scan, {}, please review",
+                AUTO_CLASSIFIED,
+                finding.summary()
+            );
+            column.description = if column.description.is_empty() {
+                note
+            } else {
+                format!("{}. {}", column.description.trim_end_matches('.'), note)
+            };
+        }
+    }
+    strategies
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parsers::strategy_structs::ColumnInFile;
+
+    fn users_column(column_name: &str) -> SimpleColumn {
+        SimpleColumn {
+            table_name: "public.users".to_string(),
+            column_name: column_name.to_string(),
+        }
+    }
+
+    fn values(values: &[&str]) -> Vec<String> {
+        values.iter().map(|value| value.to_string()).collect()
+    }
+
+    fn strategy(column_name: &str, data_category: DataCategory) -> Vec<StrategyInFile> {
+        let mut column = ColumnInFile::new(column_name);
+        column.data_category = data_category;
+        column.transformer = Transformer {
+            name: TransformerType::Identity,
+            args: None,
+        };
+        vec![StrategyInFile {
+            table_name: "public.users".to_string(),
+            description: "".to_string(),
+            truncate: false,
+            salt: None,
+            locale: None,
+            columns: vec![column],
+        }]
+    }
+
+    #[test]
+    fn counts_the_values_each_detector_matches() {
+        let scan = scan_values(
+            users_column("misc_info"),
+            &values(&[
+                "call 07700 900123 after 5",
+                "jane@example.test",
+                "4111 1111 1111 1111",
+                "nothing to see",
+            ]),
+        );
+
+        assert_eq!(scan.sampled, 4);
+        assert_eq!(
+            scan.detections,
+            vec![("email", 1), ("card_number", 1), ("phone_number", 1)]
+        );
+    }
+
+    #[test]
+    fn detects_person_names() {
+        assert!(has_person_name("Oliver"));
+        assert!(has_person_name("spoke to oliver smith about it"));
+        assert!(!has_person_name("ordered a smith and wesson"));
+        assert!(!has_person_name("no names here"));
+    }
+
+    #[test]
+    fn reports_general_columns_that_look_like_pii() {
+        let scan = scan_values(
+            users_column("misc_info"),
+            &values(&["07700 900123", "07700 900456", "none", "none"]),
+        );
+
+        let findings = findings(
+            vec![scan],
+            &strategy("misc_info", DataCategory::General),
+            0.1,
+        );
+
+        assert_eq!(findings.len(), 1);
+        assert_eq!(
+            findings[0].to_string(),
+            "public.users => misc_info: General, but of 4 sampled values 50% look like phone_number (suggest PotentialPii with Redact)"
+        );
+        assert_eq!(
+            findings[0].suggested_transformer.args,
+            Some(HashMap::from([(
+                "detectors".to_string(),
+                json!(["phone_number"])
+            )]))
+        );
+    }
+
+    #[test]
+    fn suggests_a_fake_transformer_for_columns_of_one_kind_of_pii() {
+        let scan = scan_values(
+            users_column("contact"),
+            &values(&["a@example.test", "b@example.test", "c@example.test"]),
+        );
+
+        let findings = findings(vec![scan], &[], 0.1);
+
+        assert_eq!(findings[0].data_category, None);
+        assert_eq!(findings[0].suggested_category, DataCategory::Pii);
+        assert_eq!(
+            findings[0].suggested_transformer.name,
+            TransformerType::FakeEmail
+        );
+    }
+
+    #[test]
+    fn suggests_name_handling_for_columns_with_names_in() {
+        let notes = || {
+            scan_values(
+                users_column("notes"),
+                &values(&["spoke to Oliver Smith", "none", "none"]),
+            )
+        };
+        let mut strategies = strategy("notes", DataCategory::General);
+
+        let scrambled = findings(vec![notes()], &strategies, 0.1);
+        let mut first_name = ColumnInFile::new("first_name");
+        first_name.transformer.name = TransformerType::FakeFirstName;
+        strategies[0].columns.push(first_name);
+        let redacted = findings(vec![notes()], &strategies, 0.1);
+
+        assert_eq!(
+            scrambled[0].suggested_transformer,
+            Transformer {
+                name: TransformerType::Scramble,
+                args: None
+            }
+        );
+        assert_eq!(
+            redacted[0].suggested_transformer,
+            Transformer {
+                name: TransformerType::Redact,
+                args: Some(HashMap::from([(
+                    "name_columns".to_string(),
+                    json!(["first_name"])
+                )]))
+            }
+        );
+    }
+
+    #[test]
+    fn ignores_columns_classified_as_pii_or_below_the_threshold() {
+        let emails = || {
+            scan_values(
+                users_column("contact"),
+                &values(&["a@example.test", "none", "none", "none"]),
+            )
+        };
+
+        assert!(findings(vec![emails()], &strategy("contact", DataCategory::Pii), 0.1).is_empty());
+        assert!(findings(
+            vec![emails()],
+            &strategy("contact", DataCategory::General),
+            0.5
+        )
+        .is_empty());
+    }
+
+    #[test]
+    fn applies_the_suggestions_to_the_strategy_file() {
+        let scan = scan_values(users_column("contact"), &values(&["a@example.test"]));
+        let mut strategies = strategy("contact", DataCategory::General);
+        let findings = findings(vec![scan], &strategies, 0.1);
+        let annotated = apply(strategies.clone(), &findings);
+        strategies[0].columns[0].description = "Where to reach them.".to_string();
+
+        let column = &apply(strategies, &findings)[0].columns[0];
+
+        assert_eq!(column.data_category, DataCategory::Pii);
+        assert_eq!(column.transformer.name, TransformerType::FakeEmail);
+        assert_eq!(
+            annotated[0].columns[0].description,
+            "Auto-classified by scan, of 1 sampled values 100% look like email, please review"
+        );
+        assert_eq!(
+            column.description,
+            "Where to reach them. Auto-classified by scan, of 1 sampled values 100% look like email, please review"
+        );
+    }
+}
@@ -2,8 +2,10 @@
 pub mod builders {
     use crate::parsers::state::Types;
     use crate::parsers::strategy_structs::{
-        ColumnInFile, ColumnInfo, DataCategory, StrategyInFile, Transformer, TransformerType,
+        ColumnInFile, ColumnInfo, DataCategory, StrategyInFile, Transformer, TransformerArgs,
+        TransformerType,
     };
+    use crate::parsers::transformer_args::ParsedTransformer;
     use crate::parsers::types::{SubType, Type};
     use std::collections::HashMap;
 
@@ -18,7 +20,7 @@ pub mod builders {
         name: String,
         data_category: Option<DataCategory>,
         transformer_type: Option<TransformerType>,
-        transformer_args: Option<HashMap<String, String>>,
+        transformer_args: Option<TransformerArgs>,
     }
 
     impl ColumnInfoBuilder {
@@ -30,7 +32,7 @@ pub mod builders {
         pub fn with_transformer(
             mut self,
             transformer_type: TransformerType,
-            transformer_args: Option<HashMap<String, String>>,
+            transformer_args: Option<TransformerArgs>,
         ) -> ColumnInfoBuilder {
             self.transformer_type = Some(transformer_type);
             self.transformer_args = transformer_args;
@@ -46,10 +48,11 @@ pub mod builders {
             ColumnInfo {
                 name: self.name,
                 data_category: self.data_category.unwrap_or(DataCategory::General),
-                transformer: Transformer {
+                transformer: ParsedTransformer::parse(&Transformer {
                     args: self.transformer_args,
                     name: self.transformer_type.unwrap_or(TransformerType::Identity),
-                },
+                })
+                .unwrap(),
             }
         }
     }
@@ -87,6 +90,7 @@ pub mod builders {
                     .description
                     .unwrap_or_else(|| "Any description".to_string()),
                 salt: self.salt,
+                locale: None,
                 columns: self.columns,
             }
         }
@@ -97,7 +101,7 @@ pub mod builders {
         description: Option<String>,
         data_category: Option<DataCategory>,
         transformer_type: Option<TransformerType>,
-        transformer_args: Option<HashMap<String, String>>,
+        transformer_args: Option<TransformerArgs>,
     }
 
     impl ColumnInFile {
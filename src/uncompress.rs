@@ -29,6 +29,7 @@ mod tests {
             None,
             TransformerOverrides::none(),
             crate::parsers::custom_classifications::ClassificationConfig::default(),
+            crate::anonymiser::RunOptions::default(),
         )
         .unwrap();
 
@@ -39,6 +40,7 @@ mod tests {
             Some(None),
             TransformerOverrides::none(),
             crate::parsers::custom_classifications::ClassificationConfig::default(),
+            crate::anonymiser::RunOptions::default(),
         )
         .unwrap();
 
@@ -0,0 +1,496 @@
+use crate::file_reader::open;
+use crate::parsers::copy_row;
+use crate::parsers::data_row;
+use crate::parsers::pii_patterns::{self, PiiPattern};
+use crate::parsers::strategy_structs::{
+    DataCategory, SimpleColumn, StrategyInFile, TransformerType,
+};
+use aho_corasick::{AhoCorasick, MatchKind};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::fmt;
+use std::io::{self, BufRead};
+use std::ops::Range;
+
+/// Where in the anonymised dump original values were found, and which column they came from.
+/// `location` is `None` for lines outside of COPY data e.g. comments or INSERT statements
+#[derive(Debug, PartialEq)]
+pub struct Leak {
+    pub location: Option<SimpleColumn>,
+    pub source: SimpleColumn,
+    /// The number of values (or lines outside of COPY data) with at least one original value in
+    pub count: usize,
+}
+
+impl fmt::Display for Leak {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let location = self
+            .location
+            .as_ref()
+            .map_or("outside COPY data".to_string(), |c| {
+                format!("{} => {}", c.table_name, c.column_name)
+            });
+        write!(
+            f,
+            "{}: {} {} original values of {} => {}",
+            location,
+            self.count,
+            if self.count == 1 {
+                "value contains"
+            } else {
+                "values contain"
+            },
+            self.source.table_name,
+            self.source.column_name,
+        )
+    }
+}
+
+/// Finds the original values of the sensitive columns in `input_file` which are still in
+/// `output_file` after anonymising. At most `max_values` original values are held in memory, so
+/// when there are more both files are read again for each `max_values` of them, and a value in
+/// the output with original values of a column from more than one pass is counted in each
+pub fn verify(
+    input_file: &str,
+    output_file: &str,
+    strategies: &[StrategyInFile],
+    extractors: &[&PiiPattern],
+    min_length: usize,
+    max_values: usize,
+) -> io::Result<Vec<Leak>> {
+    if max_values == 0 {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "--max-values has to be at least 1",
+        ));
+    }
+    let sensitive_columns = sensitive_columns(strategies);
+    let mut leaks = Vec::new();
+    let mut skip = 0;
+    loop {
+        let (values, more) = original_values(
+            open(input_file)?,
+            &sensitive_columns,
+            extractors,
+            min_length,
+            skip..skip + max_values,
+        )?;
+        leaks.extend(find_leaks(open(output_file)?, values)?);
+        if !more {
+            return Ok(merge(leaks));
+        }
+        skip += max_values;
+    }
+}
+
+/// Adds up the leaks found in each pass by location and source column
+fn merge(leaks: Vec<Leak>) -> Vec<Leak> {
+    let mut counts: BTreeMap<(Option<SimpleColumn>, SimpleColumn), usize> = BTreeMap::new();
+    for leak in leaks {
+        *counts.entry((leak.location, leak.source)).or_default() += leak.count;
+    }
+    counts
+        .into_iter()
+        .map(|((location, source), count)| Leak {
+            location,
+            source,
+            count,
+        })
+        .collect()
+}
+
+/// The built in patterns used to pull values out of free text, e.g. the emails in a notes column
+pub fn extractors(names: &[String]) -> Result<Vec<&'static PiiPattern>, String> {
+    names
+        .iter()
+        .map(|name| {
+            pii_patterns::builtin(name).ok_or_else(|| {
+                format!(
+                    "Unknown pattern '{}', expected one of: {}",
+                    name,
+                    pii_patterns::builtin_names().join(", ")
+                )
+            })
+        })
+        .collect()
+}
+
+/// The columns whose original values must not appear in the output, which is every column apart
+/// from `General` ones and ones left as they are by the `Identity` transformer
+pub fn sensitive_columns(strategies: &[StrategyInFile]) -> HashSet<SimpleColumn> {
+    strategies
+        .iter()
+        .flat_map(|strategy| {
+            strategy
+                .columns
+                .iter()
+                .filter(|column| {
+                    column.data_category != DataCategory::General
+                        && column.transformer.name != TransformerType::Identity
+                })
+                .map(|column| SimpleColumn {
+                    table_name: strategy.table_name.clone(),
+                    column_name: column.name.clone(),
+                })
+        })
+        .collect()
+}
+
+/// Collects the values of the sensitive columns in the original dump, along with anything the
+/// extractors match in them, keyed by the value and pointing at the column it was first seen in.
+/// Values shorter than `min_length` are skipped, as they'd turn up all over the output by chance.
+/// Only the values numbered in `pass` (in the order they're read, repeats included) are kept, and
+/// whether there are any after them is returned too
+pub fn original_values<R: BufRead>(
+    original: R,
+    sensitive_columns: &HashSet<SimpleColumn>,
+    extractors: &[&PiiPattern],
+    min_length: usize,
+    pass: Range<usize>,
+) -> io::Result<(HashMap<String, SimpleColumn>, bool)> {
+    let mut values = HashMap::new();
+    let mut read = 0;
+    for_each_value(original, |column, value| {
+        let column = match column {
+            Some(column) if sensitive_columns.contains(column) => column,
+            _ => return,
+        };
+        let mut add = |value: &str| {
+            if value.chars().count() >= min_length {
+                if pass.contains(&read) {
+                    values
+                        .entry(value.to_string())
+                        .or_insert_with(|| column.clone());
+                }
+                read += 1;
+            }
+        };
+        add(value);
+        for extractor in extractors {
+            for found in extractor.find_iter(value) {
+                add(found.as_str());
+            }
+        }
+    })?;
+    Ok((values, read > pass.end))
+}
+
+/// Searches every value in the anonymised dump (and every line outside of COPY data) for the
+/// original values, counting the values they turn up in by location and source column
+pub fn find_leaks<R: BufRead>(
+    anonymised: R,
+    original_values: HashMap<String, SimpleColumn>,
+) -> io::Result<Vec<Leak>> {
+    let (needles, sources): (Vec<String>, Vec<SimpleColumn>) = original_values.into_iter().unzip();
+    let searcher = AhoCorasick::builder()
+        .match_kind(MatchKind::LeftmostLongest)
+        .build(&needles)
+        .expect("Unable to build the search for original values");
+
+    let mut counts: BTreeMap<(Option<SimpleColumn>, SimpleColumn), usize> = BTreeMap::new();
+    for_each_value(anonymised, |location, value| {
+        let found: BTreeSet<&SimpleColumn> = searcher
+            .find_iter(value)
+            .map(|m| &sources[m.pattern().as_usize()])
+            .collect();
+        for source in found {
+            *counts
+                .entry((location.cloned(), source.clone()))
+                .or_default() += 1;
+        }
+    })?;
+
+    Ok(counts
+        .into_iter()
+        .map(|((location, source), count)| Leak {
+            location,
+            source,
+            count,
+        })
+        .collect())
+}
+
+/// Calls `f` with every decoded value in the COPY data of a dump and the column it's in, and with
+/// every other line with no column
+fn for_each_value<R, F>(mut reader: R, mut f: F) -> io::Result<()>
+where
+    R: BufRead,
+    F: FnMut(Option<&SimpleColumn>, &str),
+{
+    let mut line = String::new();
+    let mut current_columns: Option<Vec<SimpleColumn>> = None;
+    loop {
+        line.clear();
+        if reader.read_line(&mut line)? == 0 {
+            return Ok(());
+        }
+
+        match current_columns {
+            Some(_) if line.starts_with("\\.") => current_columns = None,
+            Some(ref columns) => {
+                for (column, value) in columns.iter().zip(data_row::split(&line)) {
+                    if value != "\\N" {
+                        f(Some(column), &data_row::decode(value));
+                    }
+                }
+            }
+            None => match copy_row::table_and_columns(&line)
+                .filter(|_| line.starts_with("COPY ") && !line.contains('\t'))
+            {
+                Some((table_name, column_names)) => {
+                    current_columns = Some(
+                        column_names
+                            .into_iter()
+                            .map(|column_name| SimpleColumn {
+                                table_name: table_name.clone(),
+                                column_name,
+                            })
+                            .collect(),
+                    )
+                }
+                None => f(None, &line),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parsers::strategy_structs::{ColumnInFile, Transformer};
+    use itertools::Itertools;
+
+    const ORIGINAL: &str = "\
+--
+-- Data for Name: users
+--
+
+COPY public.users (id, email, first_name, notes) FROM stdin;
+1\tjane@example.com\tJane\tCall 07700 900123 after 5
+2\tjohn@example.com\tJo\t\\N
+\\.
+";
+
+    fn users_column(column_name: &str) -> SimpleColumn {
+        SimpleColumn {
+            table_name: "public.users".to_string(),
+            column_name: column_name.to_string(),
+        }
+    }
+
+    fn strategies() -> Vec<StrategyInFile> {
+        let column = |name: &str, data_category: DataCategory, transformer: TransformerType| {
+            let mut column = ColumnInFile::new(name);
+            column.data_category = data_category;
+            column.transformer = Transformer {
+                name: transformer,
+                args: None,
+            };
+            column
+        };
+        vec![StrategyInFile {
+            table_name: "public.users".to_string(),
+            description: "".to_string(),
+            truncate: false,
+            salt: None,
+            locale: None,
+            columns: vec![
+                column("id", DataCategory::General, TransformerType::Identity),
+                column("email", DataCategory::Pii, TransformerType::FakeEmail),
+                column(
+                    "first_name",
+                    DataCategory::Pii,
+                    TransformerType::FakeFirstName,
+                ),
+                column("notes", DataCategory::PotentialPii, TransformerType::Redact),
+            ],
+        }]
+    }
+
+    fn leaks(anonymised: &str, extract: &[&str]) -> Vec<Leak> {
+        let extract: Vec<String> = extract.iter().map(|name| name.to_string()).collect();
+        let values = original_values(
+            ORIGINAL.as_bytes(),
+            &sensitive_columns(&strategies()),
+            &extractors(&extract).unwrap(),
+            4,
+            0..usize::MAX,
+        )
+        .unwrap()
+        .0;
+        find_leaks(anonymised.as_bytes(), values).unwrap()
+    }
+
+    #[test]
+    fn only_general_and_identity_columns_are_left_out() {
+        let columns = sensitive_columns(&strategies());
+        assert_eq!(
+            columns,
+            HashSet::from([
+                users_column("email"),
+                users_column("first_name"),
+                users_column("notes"),
+            ])
+        );
+    }
+
+    #[test]
+    fn skips_nulls_and_short_values() {
+        let values = original_values(
+            ORIGINAL.as_bytes(),
+            &sensitive_columns(&strategies()),
+            &[],
+            4,
+            0..usize::MAX,
+        )
+        .unwrap()
+        .0;
+        assert_eq!(
+            values,
+            HashMap::from([
+                ("jane@example.com".to_string(), users_column("email")),
+                ("john@example.com".to_string(), users_column("email")),
+                ("Jane".to_string(), users_column("first_name")),
+                (
+                    "Call 07700 900123 after 5".to_string(),
+                    users_column("notes")
+                ),
+            ])
+        );
+    }
+
+    #[test]
+    fn keeps_the_values_of_one_pass_at_a_time() {
+        let pass = |pass: Range<usize>| {
+            let (values, more) = original_values(
+                ORIGINAL.as_bytes(),
+                &sensitive_columns(&strategies()),
+                &[],
+                4,
+                pass,
+            )
+            .unwrap();
+            (values.into_keys().sorted().collect::<Vec<_>>(), more)
+        };
+
+        assert_eq!(
+            pass(0..3),
+            (
+                vec![
+                    "Call 07700 900123 after 5".to_string(),
+                    "Jane".to_string(),
+                    "jane@example.com".to_string(),
+                ],
+                true
+            )
+        );
+        assert_eq!(pass(3..6), (vec!["john@example.com".to_string()], false));
+        assert!(!pass(0..4).1);
+    }
+
+    #[test]
+    fn rejects_passes_with_no_values() {
+        let error = verify("in.sql", "out.sql", &strategies(), &[], 4, 0).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidInput);
+        assert_eq!(error.to_string(), "--max-values has to be at least 1");
+    }
+
+    #[test]
+    fn adds_up_the_leaks_from_each_pass() {
+        let leak = |location: &str, count: usize| Leak {
+            location: Some(users_column(location)),
+            source: users_column("email"),
+            count,
+        };
+        assert_eq!(
+            merge(vec![leak("notes", 1), leak("email", 2), leak("notes", 3)]),
+            vec![leak("email", 2), leak("notes", 4)]
+        );
+    }
+
+    #[test]
+    fn finds_original_values_anywhere_in_the_output() {
+        let anonymised = "\
+-- sent to jane@example.com
+COPY public.users (id, email, first_name, notes) FROM stdin;
+1\tx1@example.test\tAmy\tJane said hi
+2\tjohn@example.com\tBob\tforwarded from john@example.com and jane@example.com
+\\.
+";
+        assert_eq!(
+            leaks(anonymised, &[]),
+            vec![
+                Leak {
+                    location: None,
+                    source: users_column("email"),
+                    count: 1,
+                },
+                Leak {
+                    location: Some(users_column("email")),
+                    source: users_column("email"),
+                    count: 1,
+                },
+                Leak {
+                    location: Some(users_column("notes")),
+                    source: users_column("email"),
+                    count: 1,
+                },
+                Leak {
+                    location: Some(users_column("notes")),
+                    source: users_column("first_name"),
+                    count: 1,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn finds_values_extracted_from_text() {
+        let anonymised = "\
+COPY public.messages (id, body) FROM stdin;
+1\tmy number is 07700 900123
+\\.
+";
+        assert_eq!(leaks(anonymised, &[]), vec![]);
+        assert_eq!(
+            leaks(anonymised, &["phone_number"]),
+            vec![Leak {
+                location: Some(SimpleColumn {
+                    table_name: "public.messages".to_string(),
+                    column_name: "body".to_string(),
+                }),
+                source: users_column("notes"),
+                count: 1,
+            }]
+        );
+    }
+
+    #[test]
+    fn finds_nothing_in_a_fully_anonymised_dump() {
+        let anonymised = "\
+COPY public.users (id, email, first_name, notes) FROM stdin;
+1\tx1@example.test\tAmy\tCall 07700 900999 after 5
+2\tx2@example.test\tBob\t\\N
+\\.
+";
+        assert_eq!(leaks(anonymised, &["email", "phone_number"]), vec![]);
+    }
+
+    #[test]
+    fn rejects_unknown_extractors() {
+        let error = extractors(&["emails".to_string()]).err().unwrap();
+        assert!(error.starts_with("Unknown pattern 'emails', expected one of: email,"));
+    }
+
+    #[test]
+    fn describes_leaks_without_the_values() {
+        let leak = Leak {
+            location: Some(users_column("notes")),
+            source: users_column("email"),
+            count: 3,
+        };
+        assert_eq!(
+            leak.to_string(),
+            "public.users => notes: 3 values contain original values of public.users => email"
+        );
+    }
+}